@@ -1,6 +1,6 @@
 import test from 'ava'
 
-import { createCanvas, Canvas, SvgExportFlag } from '../index'
+import { createCanvas, Canvas, DOMMatrix, SvgExportFlag } from '../index'
 
 test('Canvas constructor should be equal to createCanvas', (t) => {
   t.true(new Canvas(100, 100) instanceof createCanvas(100, 100).constructor)
@@ -19,3 +19,100 @@ test('ctx.canvas should be equal to canvas', (t) => {
   const ctx = canvas.getContext('2d')
   t.is(ctx.canvas, canvas)
 })
+
+test('context should be lost when the surface can not be allocated', (t) => {
+  const canvas = createCanvas(100000, 100000)
+  const ctx = canvas.getContext('2d')
+  t.true(ctx.isContextLost())
+  t.is(canvas.width, 100000)
+  t.notThrows(() => {
+    ctx.fillStyle = 'red'
+    ctx.fillRect(0, 0, 100000, 100000)
+    ctx.beginPath()
+    ctx.arc(50000, 50000, 1000, 0, Math.PI * 2)
+    ctx.stroke()
+    ctx.fillText('lost', 10, 10)
+  })
+  t.throws(() => canvas.toBuffer('image/png'))
+  // a size that fits brings the context back
+  canvas.width = 100
+  canvas.height = 100
+  t.false(canvas.getContext('2d').isContextLost())
+  t.false(createCanvas(100, 100).getContext('2d').isContextLost())
+})
+
+test('toBuffer raw should return straight or premultiplied RGBA', (t) => {
+  const canvas = createCanvas(4, 3)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'rgba(255, 0, 0, 0.5)'
+  ctx.fillRect(0, 0, 4, 3)
+  const straight = canvas.toBuffer('raw')
+  const premultiplied = canvas.toBuffer('raw', { premultiplied: true })
+  // the rows are tightly packed
+  t.is(straight.length, 4 * 3 * 4)
+  t.is(premultiplied.length, 4 * 3 * 4)
+  t.deepEqual(Array.from(straight.subarray(0, 4)), [255, 0, 0, 128])
+  t.true(Math.abs(premultiplied[0] - 127) <= 1)
+  t.deepEqual(Array.from(premultiplied.subarray(1, 4)), [0, 0, 128])
+  t.deepEqual(straight, canvas.toBuffer('raw', { premultiplied: false }))
+})
+
+test('getImageData should throw when the pixels can not be read', (t) => {
+  const ctx = createCanvas(100000, 100000).getContext('2d')
+  t.throws(() => ctx.getImageData(0, 0, 1, 1), { message: /^failed to read pixels: the context is lost/ })
+  t.throws(() => ctx.getImageData(0, 0, 1, 1, { colorSpace: 'srgb', storageFormat: 'float16' }), {
+    message: /^failed to read pixels/,
+  })
+})
+
+test('resize should clear the canvas unless preserveContent is set', (t) => {
+  const draw = () => {
+    const canvas = createCanvas(20, 20)
+    const ctx = canvas.getContext('2d')
+    ctx.fillStyle = 'red'
+    ctx.translate(5, 5)
+    ctx.fillRect(0, 0, 10, 10)
+    return { canvas, ctx }
+  }
+  const pixelAt = (ctx: ReturnType<typeof draw>['ctx'], x: number, y: number) =>
+    Array.from(ctx.getImageData(x, y, 1, 1).data)
+
+  const cleared = draw()
+  cleared.canvas.resize(40, 30)
+  t.is(cleared.canvas.width, 40)
+  t.is(cleared.canvas.height, 30)
+  t.deepEqual(pixelAt(cleared.ctx, 10, 10), [0, 0, 0, 0])
+  // the state is reset
+  t.is(cleared.ctx.fillStyle, '#000')
+  t.deepEqual(cleared.ctx.getTransform(), new DOMMatrix([1, 0, 0, 1, 0, 0]))
+
+  const preserved = draw()
+  preserved.canvas.resize(40, 30, { preserveContent: true })
+  t.is(preserved.canvas.width, 40)
+  t.deepEqual(pixelAt(preserved.ctx, 10, 10), [255, 0, 0, 255])
+  t.deepEqual(pixelAt(preserved.ctx, 30, 10), [0, 0, 0, 0])
+  t.is(preserved.ctx.fillStyle, '#000')
+  t.deepEqual(preserved.ctx.getTransform(), new DOMMatrix([1, 0, 0, 1, 0, 0]))
+  preserved.ctx.fillRect(30, 0, 10, 10)
+  t.deepEqual(pixelAt(preserved.ctx, 35, 5), [0, 0, 0, 255])
+
+  // shrinking keeps the top left corner
+  const shrunk = draw()
+  shrunk.canvas.resize(8, 8, { preserveContent: true })
+  t.deepEqual(pixelAt(shrunk.ctx, 6, 6), [255, 0, 0, 255])
+  t.deepEqual(pixelAt(shrunk.ctx, 2, 2), [0, 0, 0, 0])
+})
+
+test('assigning width and height should keep the context attributes', (t) => {
+  const canvas = createCanvas(20, 20)
+  const ctx = canvas.getContext('2d', { alpha: false })
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 20, 20)
+  canvas.width = 30
+  t.is(canvas.width, 30)
+  t.is(ctx.fillStyle, '#000')
+  t.deepEqual(Array.from(ctx.getImageData(25, 5, 1, 1).data), [0, 0, 0, 255])
+  canvas.height = 40
+  t.is(canvas.height, 40)
+  t.deepEqual(Array.from(ctx.getImageData(5, 35, 1, 1).data), [0, 0, 0, 255])
+})
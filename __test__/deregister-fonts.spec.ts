@@ -0,0 +1,31 @@
+import { readFileSync } from 'fs'
+import { join } from 'path'
+import test from 'ava'
+
+import { GlobalFonts, createCanvas } from '../index'
+
+// runs in its own worker, dropping the fonts would break the other font tests
+const fontData = readFileSync(join(__dirname, 'fonts', 'iosevka-slab-regular.ttf'))
+
+test('deregisterAllFonts should drop the registered fonts', (t) => {
+  const family = 'Iosevka-skr-deregister-test'
+  const canvas = createCanvas(400, 100)
+  const ctx = canvas.getContext('2d')
+  const measure = () => {
+    ctx.font = `24px ${family}`
+    return ctx.measureText('iiiiiiiiii').width
+  }
+  t.true(GlobalFonts.register(fontData, family))
+  t.true(GlobalFonts.has(family))
+  // iosevka is monospaced, `i` is as wide as any other glyph
+  const registeredWidth = measure()
+  t.true(registeredWidth > 0)
+
+  GlobalFonts.deregisterAllFonts()
+  t.false(GlobalFonts.has(family))
+  t.false(GlobalFonts.families.some((f) => f.family.startsWith('Iosevka')))
+  // the text falls back to another font
+  const fallbackWidth = measure()
+  t.true(fallbackWidth > 0)
+  t.not(fallbackWidth, registeredWidth)
+})
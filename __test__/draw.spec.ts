@@ -177,6 +177,34 @@ test('clip', async (t) => {
   await snapshotImage(t)
 })
 
+test('clip with Path2D and evenodd fill rule', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const star = new Path2D()
+  for (let i = 0; i < 5; i++) {
+    const angle = -Math.PI / 2 + (i * 4 * Math.PI) / 5
+    const x = 256 + 200 * Math.cos(angle)
+    const y = 256 + 200 * Math.sin(angle)
+    i === 0 ? star.moveTo(x, y) : star.lineTo(x, y)
+  }
+  star.closePath()
+  ctx.save()
+  ctx.clip(star, 'evenodd')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 512, 512)
+  // the tips are painted, the pentagon in the middle is a hole
+  t.deepEqual(pixelAt(256, 80), [255, 0, 0, 255])
+  t.deepEqual(pixelAt(256, 256), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(10, 10), [0, 0, 0, 0])
+  ctx.restore()
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 0, 512, 512)
+  t.deepEqual(pixelAt(256, 256), [0, 0, 255, 255])
+  // the rule is not stored in the path
+  t.is(star.getFillTypeString(), 'nonzero')
+  t.throws(() => ctx.clip(star, 'odd' as CanvasFillRule))
+})
+
 test('closePath', async (t) => {
   const { ctx } = t.context
   ctx.beginPath()
@@ -221,6 +249,28 @@ test('createImageData', async (t) => {
   await snapshotImage(t)
 })
 
+test('createImageData(width, height) should be transparent black', (t) => {
+  const { ctx } = t.context
+  const imageData = ctx.createImageData(3, 2)
+  t.is(imageData.width, 3)
+  t.is(imageData.height, 2)
+  t.deepEqual(Array.from(imageData.data), new Array(3 * 2 * 4).fill(0))
+  t.throws(() => ctx.createImageData(-1, 2), { message: /IndexSizeError/ })
+  t.throws(() => ctx.createImageData(2, 0), { message: /IndexSizeError/ })
+})
+
+test('createImageData(imagedata) should copy the dimensions but not the pixels', (t) => {
+  const { ctx } = t.context
+  const source = ctx.createImageData(4, 3, { colorSpace: 'display-p3' })
+  source.data.fill(255)
+  const imageData = ctx.createImageData(source)
+  t.is(imageData.width, 4)
+  t.is(imageData.height, 3)
+  t.is(imageData.colorSpace, 'display-p3')
+  t.not(imageData.data, source.data)
+  t.true(imageData.data.every((channel) => channel === 0))
+})
+
 test('createLinearGradient', async (t) => {
   const { ctx } = t.context
   const gradient = ctx.createLinearGradient(20, 0, 220, 0)
@@ -271,6 +321,118 @@ test('createPattern-with-transform', async (t) => {
   await snapshotImage(t)
 })
 
+test('createPattern-setTransform-should-rotate-tiles', (t) => {
+  const { ctx } = t.context
+  // 20x20 tile, left half red and right half blue
+  const tile = new ImageData(20, 20)
+  for (let y = 0; y < 20; y++) {
+    for (let x = 0; x < 20; x++) {
+      const offset = (y * 20 + x) * 4
+      tile.data[offset] = x < 10 ? 255 : 0
+      tile.data[offset + 2] = x < 10 ? 0 : 255
+      tile.data[offset + 3] = 255
+    }
+  }
+  const pattern = ctx.createPattern(tile, 'repeat')
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.fillStyle = pattern
+  ctx.fillRect(0, 0, 100, 100)
+  t.deepEqual(pixelAt(5, 45), [255, 0, 0, 255])
+  pattern.setTransform(new DOMMatrix().rotate(45))
+  ctx.fillRect(0, 0, 100, 100)
+  t.deepEqual(pixelAt(5, 45), [0, 0, 255, 255])
+  pattern.setTransform()
+  ctx.fillRect(0, 0, 100, 100)
+  t.deepEqual(pixelAt(5, 45), [255, 0, 0, 255])
+})
+
+test('createPattern should tile along the axes of the repetition', (t) => {
+  const { ctx } = t.context
+  const tile = new ImageData(10, 10)
+  for (let i = 0; i < tile.data.length; i += 4) {
+    tile.data[i] = 255
+    tile.data[i + 3] = 255
+  }
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const RED = [255, 0, 0, 255]
+  const TRANSPARENT = [0, 0, 0, 0]
+  const cases: [Parameters<SKRSContext2D['createPattern']>[1], number[], number[], number[]][] = [
+    // repetition, inside of the first tile, along x, along y
+    ['repeat', RED, RED, RED],
+    [null, RED, RED, RED],
+    ['', RED, RED, RED],
+    ['repeat-x', RED, RED, TRANSPARENT],
+    ['repeat-y', RED, TRANSPARENT, RED],
+    ['no-repeat', RED, TRANSPARENT, TRANSPARENT],
+  ]
+  for (const [repetition, origin, alongX, alongY] of cases) {
+    ctx.clearRect(0, 0, 100, 100)
+    ctx.fillStyle = ctx.createPattern(tile, repetition)
+    ctx.fillRect(0, 0, 100, 100)
+    t.deepEqual(pixelAt(5, 5), origin, `${repetition}`)
+    t.deepEqual(pixelAt(55, 5), alongX, `${repetition}`)
+    t.deepEqual(pixelAt(5, 55), alongY, `${repetition}`)
+  }
+  // @ts-expect-error
+  t.throws(() => ctx.createPattern(tile, 'repeat-z'))
+})
+
+test('imageSmoothingEnabled false should upscale with nearest neighbor', (t) => {
+  const { ctx } = t.context
+  // 2x2 checkerboard
+  const checkerboard = new ImageData(2, 2)
+  for (const offset of [0, 12]) {
+    checkerboard.data[offset + 3] = 255
+  }
+  for (const offset of [4, 8]) {
+    checkerboard.data.fill(255, offset, offset + 4)
+  }
+  const source = createCanvas(2, 2)
+  source.getContext('2d').putImageData(checkerboard, 0, 0)
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+
+  ctx.imageSmoothingEnabled = false
+  ctx.drawImage(source, 0, 0, 16, 16)
+  const pattern = ctx.createPattern(source, 'repeat')
+  pattern.setTransform(new DOMMatrix().translate(32, 0).scale(8))
+  ctx.fillStyle = pattern
+  ctx.fillRect(32, 0, 16, 16)
+  for (const left of [0, 32]) {
+    // the edge between the cells stays hard
+    t.deepEqual(pixelAt(left + 7, 3), [0, 0, 0, 255])
+    t.deepEqual(pixelAt(left + 8, 3), [255, 255, 255, 255])
+    t.deepEqual(pixelAt(left + 7, 8), [255, 255, 255, 255])
+    t.deepEqual(pixelAt(left + 8, 8), [0, 0, 0, 255])
+  }
+
+  ctx.imageSmoothingEnabled = true
+  ctx.drawImage(source, 64, 0, 16, 16)
+  const [smoothed] = pixelAt(64 + 7, 3)
+  t.true(smoothed > 0 && smoothed < 255)
+})
+
+test('gradient interpolation should change the midpoint color', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const midpoint = (interpolation?: 'srgb' | 'oklab', hint?: number) => {
+    const gradient = ctx.createLinearGradient(0, 0, 100, 0)
+    if (interpolation) {
+      gradient.interpolation = interpolation
+    }
+    gradient.addColorStop(0, 'red')
+    gradient.addColorStop(1, 'blue', hint)
+    ctx.fillStyle = gradient
+    ctx.fillRect(0, 0, 100, 10)
+    return pixelAt(50, 5)
+  }
+  const srgb = midpoint()
+  t.deepEqual(midpoint('srgb'), srgb)
+  t.notDeepEqual(midpoint('oklab'), srgb)
+  // the color is closer to blue after the midpoint is moved to the left
+  const [red] = midpoint('srgb', 0.25)
+  t.true(red < srgb[0])
+})
+
 test('createRadialGradient', async (t) => {
   const { ctx } = t.context
   const gradient = ctx.createRadialGradient(110, 90, 30, 100, 100, 70)
@@ -303,6 +465,28 @@ test('createConicGradient', async (t) => {
   await snapshotImage(t)
 })
 
+test('createConicGradient start angle', (t) => {
+  const { ctx } = t.context
+  const gradient = ctx.createConicGradient(Math.PI / 2, 100, 100)
+  gradient.addColorStop(0, 'red')
+  gradient.addColorStop(1, 'blue')
+  ctx.fillStyle = gradient
+  ctx.fillRect(0, 0, 200, 200)
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  // starts from the bottom (PI / 2 clockwise from the x-axis)
+  const [startRed, , startBlue] = pixelAt(98, 160)
+  t.true(startRed > 240 && startBlue < 15)
+  // left, a quarter of the turn
+  const [leftRed, , leftBlue] = pixelAt(40, 100)
+  t.true(Math.abs(leftRed - 191) < 8 && Math.abs(leftBlue - 64) < 8)
+  // top, half of the turn
+  const [topRed, , topBlue] = pixelAt(100, 40)
+  t.true(Math.abs(topRed - 128) < 8 && Math.abs(topBlue - 128) < 8)
+  // right, three quarters of the turn
+  const [rightRed, , rightBlue] = pixelAt(160, 100)
+  t.true(Math.abs(rightRed - 64) < 8 && Math.abs(rightBlue - 191) < 8)
+})
+
 test('drawImage', async (t) => {
   const { ctx } = t.context
   const filePath = './javascript.png'
@@ -313,6 +497,35 @@ test('drawImage', async (t) => {
   await snapshotImage(t)
 })
 
+test('drawImage should clip the source rect to the image', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const source = createCanvas(10, 10)
+  const sourceCtx = source.getContext('2d')
+  sourceCtx.fillStyle = 'lime'
+  sourceCtx.fillRect(0, 0, 10, 10)
+  // the left half of the source rect is outside of the image
+  ctx.drawImage(source, -10, 0, 20, 10, 0, 0, 20, 10)
+  t.deepEqual(pixelAt(5, 5), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(15, 5), [0, 255, 0, 255])
+  t.deepEqual(pixelAt(25, 5), [0, 0, 0, 0])
+})
+
+test('drawImage should not bleed the pixels around a fractional source rect', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const source = createCanvas(2, 1)
+  const sourceCtx = source.getContext('2d')
+  sourceCtx.fillStyle = 'red'
+  sourceCtx.fillRect(0, 0, 1, 1)
+  sourceCtx.fillStyle = 'blue'
+  sourceCtx.fillRect(1, 0, 1, 1)
+  ctx.imageSmoothingEnabled = true
+  ctx.drawImage(source, 0.25, 0, 0.5, 1, 0, 0, 100, 100)
+  t.deepEqual(pixelAt(0, 50), [255, 0, 0, 255])
+  t.deepEqual(pixelAt(99, 50), [255, 0, 0, 255])
+})
+
 test('drawImage-svg', async (t) => {
   const { ctx } = t.context
   const filePath = './mountain.svg'
@@ -415,6 +628,51 @@ test('ellipse', async (t) => {
   await snapshotImage(t)
 })
 
+test('ellipse should draw the rotated half in the sweep direction', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const RED = [255, 0, 0, 255]
+  const TRANSPARENT = [0, 0, 0, 0]
+  ctx.fillStyle = 'red'
+
+  // the bottom half without rotation
+  ctx.beginPath()
+  ctx.ellipse(100, 100, 80, 40, 0, 0, Math.PI)
+  ctx.fill()
+  t.deepEqual(pixelAt(100, 130), RED)
+  t.deepEqual(pixelAt(100, 70), TRANSPARENT)
+
+  // the vertical major axis after rotating by 90 degrees, the left half is swept clockwise
+  ctx.clearRect(0, 0, 512, 512)
+  ctx.beginPath()
+  ctx.ellipse(100, 100, 80, 40, Math.PI / 2, 0, Math.PI)
+  ctx.fill()
+  t.deepEqual(pixelAt(70, 100), RED)
+  t.deepEqual(pixelAt(100, 30), RED)
+  t.deepEqual(pixelAt(130, 100), TRANSPARENT)
+
+  // the right half is swept anticlockwise
+  ctx.clearRect(0, 0, 512, 512)
+  ctx.beginPath()
+  ctx.ellipse(100, 100, 80, 40, Math.PI / 2, 0, Math.PI, true)
+  ctx.fill()
+  t.deepEqual(pixelAt(130, 100), RED)
+  t.deepEqual(pixelAt(100, 170), RED)
+  t.deepEqual(pixelAt(70, 100), TRANSPARENT)
+})
+
+test('ellipse should validate the arguments', (t) => {
+  const { ctx } = t.context
+  t.throws(() => ctx.ellipse(100, 100, -1, 40, 0, 0, Math.PI), { message: /IndexSizeError/ })
+  t.throws(() => new Path2D().ellipse(100, 100, 40, -1, 0, 0, Math.PI), { message: /IndexSizeError/ })
+  t.throws(() => ctx.arc(100, 100, -1, 0, Math.PI), { message: /IndexSizeError/ })
+  // non-finite arguments are ignored
+  ctx.beginPath()
+  ctx.ellipse(100, 100, 80, NaN, 0, 0, Math.PI)
+  ctx.arc(100, 100, 40, 0, Infinity)
+  t.false(ctx.isPointInPath(100, 130))
+})
+
 test('fill', async (t) => {
   const { ctx } = t.context
   const region = new Path2D()
@@ -474,6 +732,36 @@ test('fillText-maxWidth', async (t) => {
   await snapshotImage(t, { canvas, ctx }, 'png', 0.8)
 })
 
+test('fillText and strokeText should condense the text into maxWidth', (t) => {
+  const drawnWidth = (draw: (ctx: SKRSContext2D) => void) => {
+    const canvas = createCanvas(600, 60)
+    const ctx = canvas.getContext('2d')
+    ctx.font = '24px Iosevka Slab'
+    draw(ctx)
+    const { data } = ctx.getImageData(0, 0, 600, 60)
+    let left = Infinity
+    let right = -Infinity
+    for (let i = 3; i < data.length; i += 4) {
+      if (data[i] > 0) {
+        const x = ((i - 3) / 4) % 600
+        left = Math.min(left, x)
+        right = Math.max(right, x + 1)
+      }
+    }
+    return right > left ? right - left : 0
+  }
+  const text = 'A long line of text that does not fit'
+  const { ctx } = t.context
+  ctx.font = '24px Iosevka Slab'
+  t.true(ctx.measureText(text).width > 100)
+  // one pixel of slack for the antialiased edges
+  t.true(drawnWidth((ctx) => ctx.fillText(text, 10, 40, 100)) <= 101)
+  t.true(drawnWidth((ctx) => ctx.strokeText(text, 10, 40, 100)) <= 102)
+  t.true(drawnWidth((ctx) => ctx.fillText(text, 10, 40)) > 100)
+  t.is(drawnWidth((ctx) => ctx.fillText(text, 10, 40, 0)), 0)
+  t.is(drawnWidth((ctx) => ctx.strokeText(text, 10, 40, NaN)), 0)
+})
+
 test('fillText-AA', async (t) => {
   const { ctx, canvas } = t.context
   ctx.imageSmoothingEnabled = false
@@ -505,6 +793,60 @@ test('getContextAttributes', (t) => {
   const customAttrs = ctx.getContextAttributes()
   t.is(customAttrs.alpha, false)
   t.is(customAttrs.desynchronized, false)
+  t.is(defaultAttrs.colorSpace, 'srgb')
+  const p3Ctx = createCanvas(10, 10).getContext('2d', { colorSpace: 'display-p3' })
+  t.is(p3Ctx.getContextAttributes().colorSpace, 'display-p3')
+})
+
+test('alpha-false-should-stay-opaque', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d', { alpha: false })
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 0, 255])
+  ctx.fillStyle = 'rgba(255, 0, 0, 0.5)'
+  ctx.fillRect(0, 0, 100, 100)
+  t.is(ctx.getImageData(50, 50, 1, 1).data[3], 255)
+  ctx.clearRect(0, 0, 100, 100)
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 0, 255])
+})
+
+test('display-p3 context should keep colors outside of the sRGB gamut', (t) => {
+  const canvas = createCanvas(10, 10)
+  const ctx = canvas.getContext('2d', { colorSpace: 'display-p3' })
+  // sRGB red is inside of the P3 gamut, so it is not the most saturated P3 red
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 10, 10)
+  const [r, g] = ctx.getImageData(0, 0, 1, 1, { colorSpace: 'display-p3' }).data
+  t.true(r < 255)
+  t.true(g > 0)
+
+  const p3Red = new ImageData(new Uint8ClampedArray([255, 0, 0, 255]), 1, 1, { colorSpace: 'display-p3' })
+  ctx.putImageData(p3Red, 0, 0)
+  const p3Pixel = ctx.getImageData(0, 0, 1, 1)
+  t.is(p3Pixel.colorSpace, 'display-p3')
+  t.deepEqual(Array.from(p3Pixel.data), [255, 0, 0, 255])
+  // clamped to the sRGB gamut when read back as sRGB
+  t.deepEqual(Array.from(ctx.getImageData(0, 0, 1, 1, { colorSpace: 'srgb' }).data), [255, 0, 0, 255])
+})
+
+test('float16 ImageData should round-trip values outside of the 0 to 1 range', (t) => {
+  const canvas = createCanvas(2, 2)
+  const ctx = canvas.getContext('2d', { colorType: 'float16' })
+  t.is(ctx.getContextAttributes().colorType, 'float16')
+  const imageData = ctx.createImageData(1, 1, { storageFormat: 'float16' })
+  t.is(imageData.storageFormat, 'float16')
+  t.true(imageData.data instanceof Float32Array)
+  imageData.data.set([2, 0.5, 0, 1])
+  ctx.putImageData(imageData, 0, 0)
+  const [r, g, b, a] = ctx.getImageData(0, 0, 1, 1, { storageFormat: 'float16' }).data
+  t.true(Math.abs(r - 2) < 0.01)
+  t.true(Math.abs(g - 0.5) < 0.01)
+  t.is(b, 0)
+  t.is(a, 1)
+  // uint8 stays the default and clamps
+  const uint8 = ctx.getImageData(0, 0, 1, 1)
+  t.is(uint8.storageFormat, 'uint8')
+  t.true(uint8.data instanceof Uint8ClampedArray)
+  t.is(uint8.data[0], 255)
 })
 
 test('getImageData', async (t) => {
@@ -563,6 +905,24 @@ test('isPointInStroke', (t) => {
   t.is(ctx.isPointInStroke(path, 50, 11), false)
 })
 
+test('isPointInStroke should respect lineWidth and lineCap', (t) => {
+  const { ctx } = t.context
+  const path = new Path2D()
+  path.rect(50, 50, 100, 100)
+  ctx.lineWidth = 20
+  t.is(ctx.isPointInStroke(path, 45, 100), true) // On the thick stroke
+  t.is(ctx.isPointInStroke(path, 100, 100), false) // In the hollow interior
+  t.is(path.isPointInStroke(45, 100, { width: 20 }), true)
+  t.is(path.isPointInStroke(100, 100, { width: 20 }), false)
+
+  ctx.beginPath()
+  ctx.moveTo(50, 200)
+  ctx.lineTo(150, 200)
+  t.is(ctx.isPointInStroke(45, 200), false)
+  ctx.lineCap = 'square'
+  t.is(ctx.isPointInStroke(45, 200), true)
+})
+
 test('lineTo', async (t) => {
   const { ctx } = t.context
   ctx.beginPath() // Start a new path
@@ -669,6 +1029,29 @@ test('putImageData', async (t) => {
   await snapshotImage(t)
 })
 
+test('putImageData should only copy the dirty rect', (t) => {
+  const canvas = createCanvas(20, 20)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 0, 20, 20)
+  const imageData = ctx.createImageData(10, 10)
+  for (let i = 0; i < imageData.data.length; i += 4) {
+    imageData.data.set([255, 0, 0, 255], i)
+  }
+  ctx.putImageData(imageData, 5, 5, 2, 2, 4, 4)
+  const pixel = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  t.deepEqual(pixel(7, 7), [255, 0, 0, 255])
+  t.deepEqual(pixel(10, 10), [255, 0, 0, 255])
+  // inside of the image but outside of the dirty rect
+  t.deepEqual(pixel(6, 6), [0, 0, 255, 255])
+  t.deepEqual(pixel(11, 7), [0, 0, 255, 255])
+  t.deepEqual(pixel(7, 11), [0, 0, 255, 255])
+  // negative dirty dimensions are normalized
+  ctx.putImageData(imageData, 5, 5, 10, 10, -2, -2)
+  t.deepEqual(pixel(13, 13), [255, 0, 0, 255])
+  t.deepEqual(pixel(12, 12), [0, 0, 255, 255])
+})
+
 test('quadraticCurveTo', async (t) => {
   const { ctx } = t.context
   // Quadratic Bézier curve
@@ -898,6 +1281,43 @@ test('strokeRoundRect', async (t) => {
   await snapshotImage(t, { canvas, ctx })
 })
 
+test('roundRect radii', (t) => {
+  const { ctx } = t.context
+  // scalar radius, the corner pixel is outside of the arc
+  ctx.beginPath()
+  ctx.roundRect(10, 10, 100, 100, 20)
+  t.false(ctx.isPointInPath(11, 11))
+  t.true(ctx.isPointInPath(60, 60))
+
+  // upper left, upper right, lower right, lower left
+  ctx.beginPath()
+  ctx.roundRect(10, 10, 100, 100, [0, 30, 0, { x: 30, y: 30 }])
+  t.true(ctx.isPointInPath(11, 11))
+  t.false(ctx.isPointInPath(109, 11))
+  t.true(ctx.isPointInPath(109, 109))
+  t.false(ctx.isPointInPath(11, 109))
+
+  const path = new Path2D()
+  path.roundRect(10, 10, 100, 100, [0, 30, 0, 30])
+  t.true(path.isPointInPath(11, 11))
+  t.false(path.isPointInPath(109, 11))
+
+  t.throws(() => ctx.roundRect(10, 10, 100, 100, []))
+  t.throws(() => ctx.roundRect(10, 10, 100, 100, [1, 2, 3, 4, 5]))
+  t.throws(() => ctx.roundRect(10, 10, 100, 100, -1))
+})
+
+test('roundRect should scale down radii larger than the sides', (t) => {
+  const { ctx } = t.context
+  // the radii of the top side add up to 300 for a width of 100, so every radius is scaled by 1/3
+  ctx.beginPath()
+  ctx.roundRect(10, 10, 100, 100, [150, 150, 0, 0])
+  // still rounded where a 50px radius would be rounded
+  t.false(ctx.isPointInPath(14, 14))
+  t.true(ctx.isPointInPath(60, 40))
+  t.true(ctx.isPointInPath(11, 109))
+})
+
 test('strokeText', async (t) => {
   const { ctx, canvas } = t.context
   ctx.fillStyle = 'yellow'
@@ -973,12 +1393,80 @@ test('webp-output', async (t) => {
   await snapshotImage(t, t.context, 'webp')
 })
 
+test('webp toBuffer', (t) => {
+  const { ctx, canvas } = t.context
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(0, 0, canvas.width, canvas.height)
+  const lossy = canvas.toBuffer('image/webp', 80)
+  t.is(lossy.toString('ascii', 0, 4), 'RIFF')
+  t.is(lossy.toString('ascii', 8, 12), 'WEBP')
+  t.is(lossy.toString('ascii', 12, 16), 'VP8 ')
+  // quality 100 switches to the lossless encoding
+  const lossless = canvas.toBuffer('image/webp', 100)
+  t.is(lossless.toString('ascii', 8, 12), 'WEBP')
+  t.is(lossless.toString('ascii', 12, 16), 'VP8L')
+})
+
 test('avif-output', async (t) => {
   const { ctx } = t.context
   drawTranslate(ctx)
   await snapshotImage(t, t.context, 'avif')
 })
 
+test('png toBuffer with compression level', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+  const level0 = canvas.toBuffer('image/png', { compressionLevel: 0, filters: 'none' })
+  const level9 = canvas.toBuffer('image/png', { compressionLevel: 9 })
+  t.true(level9.length <= level0.length)
+  t.deepEqual(canvas.toBuffer('image/png'), await canvas.encode('png'))
+  t.throws(() => canvas.toBuffer('image/png', { filters: 'median' as any }))
+})
+
+test('createPNGStream chunks should reassemble into the png buffer', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+  for (const cfg of [undefined, { compressionLevel: 0, filters: 'none' as const }]) {
+    const chunks: Buffer[] = []
+    for await (const chunk of canvas.createPNGStream(cfg)) {
+      chunks.push(chunk)
+    }
+    t.true(chunks.length > 1)
+    const png = Buffer.concat(chunks)
+    t.is(png.toString('ascii', 1, 4), 'PNG')
+    t.deepEqual(png, canvas.toBuffer('image/png', cfg))
+  }
+})
+
+test('createPNGStream should encode the canvas as it was when the stream was created', async (t) => {
+  const { ctx, canvas } = t.context
+  drawTranslate(ctx)
+  const expected = canvas.toBuffer('image/png')
+  const stream = canvas.createPNGStream()
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, canvas.width, canvas.height)
+  const chunks: Buffer[] = []
+  stream.on('data', (chunk: Buffer) => {
+    chunks.push(chunk)
+    ctx.clearRect(0, 0, canvas.width, canvas.height)
+  })
+  await new Promise((resolve, reject) => stream.on('end', resolve).on('error', reject))
+  t.deepEqual(Buffer.concat(chunks), expected)
+})
+
+test('avif toBuffer', async (t) => {
+  const { ctx, canvas } = t.context
+  const gradient = ctx.createLinearGradient(0, 0, canvas.width, 0)
+  gradient.addColorStop(0, 'green')
+  gradient.addColorStop(1, 'white')
+  ctx.fillStyle = gradient
+  ctx.fillRect(0, 0, canvas.width, canvas.height)
+  const output = canvas.toBuffer('image/avif', { quality: 60, speed: 10 })
+  t.is(output.toString('ascii', 4, 12), 'ftypavif')
+  const asyncOutput = await canvas.encode('avif', { quality: 60, speed: 10 })
+  t.is(asyncOutput.toString('ascii', 4, 12), 'ftypavif')
+})
+
 test('raw output', async (t) => {
   const { ctx, canvas } = t.context
   drawTranslate(ctx)
@@ -1013,6 +1501,21 @@ test('JPEG toDataURL with quality', async (t) => {
   t.deepEqual(pngBuffer, await canvas.encode('jpeg', 20))
 })
 
+test('toDataURL quality should shrink photographic jpeg and be clamped', async (t) => {
+  const { ctx, canvas } = t.context
+  const image = new Image()
+  image.src = await promises.readFile(join(__dirname, 'fixtures', 'filter-drop-shadow.jpeg'))
+  ctx.drawImage(image, 0, 0, canvas.width, canvas.height)
+  const low = canvas.toDataURL('image/jpeg', 0.5)
+  const high = canvas.toDataURL('image/jpeg', 1.0)
+  t.true(low.startsWith('data:image/jpeg;base64,'))
+  t.true(low.length < high.length)
+  t.is(canvas.toDataURL('image/jpeg', 3), high)
+  t.is(canvas.toDataURL('image/jpeg', -1), canvas.toDataURL('image/jpeg', 0))
+  // unsupported types are encoded as png
+  t.true(canvas.toDataURL('image/bmp' as any).startsWith('data:image/png;base64,'))
+})
+
 test('WebP toDataURL with quality', async (t) => {
   const { ctx, canvas } = t.context
   drawTranslate(ctx)
@@ -1036,6 +1539,52 @@ test('toDataURLAsync', async (t) => {
   t.deepEqual(pngBuffer, await canvas.encode('png'))
 })
 
+test('shadow is offset in canvas pixels', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.fillStyle = 'red'
+  ctx.shadowColor = 'rgba(0, 0, 255, 1)'
+  ctx.shadowBlur = 10
+  ctx.shadowOffsetX = 5
+  ctx.shadowOffsetY = 5
+  ctx.fillRect(100, 100, 100, 100)
+  const [r, g, b, a] = pixelAt(203, 150)
+  t.deepEqual([r, g, b], [0, 0, 255])
+  t.true(a > 100)
+  // the blur fades out before the other side of the shadow
+  t.true(pixelAt(97, 150)[3] < a)
+  t.deepEqual(pixelAt(150, 150), [255, 0, 0, 255])
+  const shadow = ctx.getImageData(0, 0, 512, 512).data
+  ctx.clearRect(0, 0, 512, 512)
+  // the transform moves the rect but does not scale the offset
+  ctx.translate(50, 50)
+  ctx.scale(2, 2)
+  ctx.fillRect(25, 25, 50, 50)
+  ctx.resetTransform()
+  t.deepEqual(pixelAt(203, 150), Array.from(shadow.slice((150 * 512 + 203) * 4, (150 * 512 + 204) * 4)))
+})
+
+test('image shadow is offset in canvas pixels under a rotation', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  const source = createCanvas(50, 50)
+  const sourceCtx = source.getContext('2d')
+  sourceCtx.fillStyle = 'red'
+  sourceCtx.fillRect(0, 0, 50, 50)
+  ctx.shadowColor = 'blue'
+  ctx.shadowOffsetX = 20
+  ctx.translate(300, 100)
+  ctx.rotate(Math.PI / 2)
+  // covers x 250 to 300 and y 100 to 150 in canvas pixels
+  ctx.drawImage(source, 0, 0)
+  ctx.resetTransform()
+  t.deepEqual(pixelAt(275, 125), [255, 0, 0, 255])
+  // the shadow is 20 canvas pixels to the right, not along the rotated x-axis
+  t.deepEqual(pixelAt(310, 125), [0, 0, 255, 255])
+  t.deepEqual(pixelAt(325, 125), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(275, 160), [0, 0, 0, 0])
+})
+
 test('shadowOffsetX', async (t) => {
   const { ctx } = t.context
   ctx.shadowColor = 'red'
@@ -1087,3 +1636,29 @@ function drawTranslate(ctx: SKRSContext2D) {
   ctx.fillStyle = 'gray'
   ctx.fillRect(0, 0, 80, 80)
 }
+
+test('clampFilters should let brightness exceed 1 on a float16 canvas', (t) => {
+  const canvas = createCanvas(2, 2)
+  const ctx = canvas.getContext('2d', { colorType: 'float16' })
+  t.true(ctx.clampFilters)
+  ctx.fillStyle = 'white'
+  ctx.filter = 'brightness(2)'
+  ctx.fillRect(0, 0, 2, 2)
+  t.deepEqual(Array.from(ctx.getImageData(0, 0, 1, 1, { storageFormat: 'float16' }).data), [1, 1, 1, 1])
+
+  ctx.save()
+  ctx.clampFilters = false
+  t.false(ctx.clampFilters)
+  ctx.fillRect(0, 0, 2, 2)
+  const [r, g, b, a] = ctx.getImageData(0, 0, 1, 1, { storageFormat: 'float16' }).data
+  t.true(Math.abs(r - 2) < 0.01)
+  t.true(Math.abs(g - 2) < 0.01)
+  t.true(Math.abs(b - 2) < 0.01)
+  t.is(a, 1)
+  // the flag belongs to the context, the restored filter isn't clamped either
+  ctx.restore()
+  t.is(ctx.filter, 'brightness(2)')
+  ctx.clearRect(0, 0, 2, 2)
+  ctx.fillRect(0, 0, 2, 2)
+  t.true(ctx.getImageData(0, 0, 1, 1, { storageFormat: 'float16' }).data[0] > 1)
+})
@@ -120,8 +120,367 @@ test('filter-save-restore', async (t) => {
   await snapshotImage(t)
 })
 
+test('restore should bring back the saved filter', (t) => {
+  const { ctx } = t.context
+  const source = createCanvas(50, 50)
+  const sourceCtx = source.getContext('2d')
+  sourceCtx.fillStyle = 'white'
+  sourceCtx.fillRect(0, 0, 50, 50)
+  sourceCtx.fillStyle = 'black'
+  sourceCtx.fillRect(0, 0, 25, 50)
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+
+  ctx.filter = 'blur(5px)'
+  ctx.save()
+  ctx.filter = 'none'
+  ctx.drawImage(source, 100, 0)
+  ctx.restore()
+  t.is(ctx.filter, 'blur(5px)')
+  ctx.drawImage(source, 0, 0)
+  // only the draw after restore is blurred
+  const [blurred] = pixelAt(24, 25)
+  t.true(blurred > 0 && blurred < 255)
+  t.deepEqual(pixelAt(124, 25), [0, 0, 0, 255])
+  t.deepEqual(pixelAt(125, 25), [255, 255, 255, 255])
+})
+
+test('filter-invalid-should-throw', (t) => {
+  const { ctx } = t.context
+  ctx.filter = 'blur(2px)'
+  t.throws(
+    () => {
+      ctx.filter = 'blur(2px) frobnicate(1)'
+    },
+    { message: /`frobnicate`\] is not a known filter function/ },
+  )
+  t.is(ctx.filter, 'blur(2px)')
+})
+
+test('drop-shadow should reject the box-shadow syntax', (t) => {
+  const { ctx } = t.context
+  t.throws(
+    () => {
+      ctx.filter = 'drop-shadow(1px 1px 2px 3px black)'
+    },
+    { message: /doesn't accept a spread radius/ },
+  )
+  t.throws(
+    () => {
+      ctx.filter = 'drop-shadow(inset 1px 1px black)'
+    },
+    { message: /doesn't accept the `inset` keyword/ },
+  )
+  t.is(ctx.filter, 'none')
+})
+
+test('filter chain should apply every filter in order', (t) => {
+  const { ctx } = t.context
+  const source = createCanvas(50, 50)
+  const sourceCtx = source.getContext('2d')
+  sourceCtx.fillStyle = 'rgb(100, 100, 100)'
+  sourceCtx.fillRect(0, 0, 25, 50)
+  sourceCtx.fillStyle = 'white'
+  sourceCtx.fillRect(25, 0, 25, 50)
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+
+  ctx.filter = 'blur(2px) brightness(1.5)'
+  ctx.drawImage(source, 0, 0)
+  // brightened far from the edge
+  t.deepEqual(pixelAt(5, 25), [150, 150, 150, 255])
+  // blurred across the edge
+  const [edge] = pixelAt(24, 25)
+  t.true(edge > 150 && edge < 255)
+
+  // a transparent drop-shadow is a no-op and must not drop the filters before it
+  ctx.filter = 'brightness(1.5) drop-shadow(0 0 0 transparent)'
+  ctx.drawImage(source, 100, 0)
+  t.deepEqual(pixelAt(105, 25), [150, 150, 150, 255])
+})
+
+test('filter order should change the output', (t) => {
+  const source = createCanvas(100, 20)
+  const sourceCtx = source.getContext('2d')
+  const gradient = sourceCtx.createLinearGradient(0, 0, 100, 0)
+  gradient.addColorStop(0, 'black')
+  gradient.addColorStop(1, 'white')
+  sourceCtx.fillStyle = gradient
+  sourceCtx.fillRect(0, 0, 100, 20)
+  const render = (filter: string) => {
+    const canvas = createCanvas(100, 20)
+    const ctx = canvas.getContext('2d')
+    ctx.filter = filter
+    ctx.drawImage(source, 0, 0)
+    return canvas.data()
+  }
+
+  const blurFirst = render('blur(2px) brightness(2)')
+  const brightnessFirst = render('brightness(2) blur(2px)')
+  t.deepEqual(render('blur(2px) brightness(2)'), blurFirst)
+  t.notDeepEqual(blurFirst, brightnessFirst)
+})
+
+test('drop-shadow currentColor should resolve to the fill color', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.filter = 'drop-shadow(20px 0 currentColor)'
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(pixelAt(25, 5), [255, 0, 0, 255])
+  // resolved when drawing, not when the filter is set
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 50, 10, 10)
+  t.deepEqual(pixelAt(25, 55), [0, 0, 255, 255])
+})
+
+test('drop-shadow blur radius should spread over two standard deviations', (t) => {
+  const { ctx } = t.context
+  ctx.filter = 'drop-shadow(0 0 10px black)'
+  ctx.fillStyle = 'white'
+  ctx.fillRect(100, 100, 100, 100)
+  const alphaAt = (x: number) => ctx.getImageData(x, 150, 1, 1).data[3]
+  // a Gaussian with a standard deviation of 5px across the right edge of the square,
+  // the pixel centers are 5.5px and 10.5px away from it
+  const expected = [
+    [205, 255 * 0.1357],
+    [210, 255 * 0.0179],
+  ]
+  for (const [x, alpha] of expected) {
+    t.true(Math.abs(alphaAt(x) - alpha) <= 8, `alpha at ${x} is ${alphaAt(x)}, expected about ${alpha}`)
+  }
+  t.is(alphaAt(220), 0)
+})
+
+test('blur(0) should be a no-op', (t) => {
+  const render = (filter: string) => {
+    const canvas = createCanvas(50, 50)
+    const ctx = canvas.getContext('2d')
+    ctx.filter = filter
+    ctx.fillStyle = 'red'
+    ctx.fillRect(10, 10, 20, 20)
+    return canvas.data()
+  }
+  t.deepEqual(render('blur(0)'), render('none'))
+  t.deepEqual(render('blur(0px) invert(1)'), render('invert(1)'))
+})
+
+test('huge blur radius should not crash', (t) => {
+  const canvas = createCanvas(50, 50)
+  const ctx = canvas.getContext('2d')
+  ctx.filter = 'blur(1000px)'
+  t.notThrows(() => {
+    ctx.fillStyle = 'red'
+    ctx.fillRect(10, 10, 20, 20)
+  })
+  const [r, g, b] = ctx.getImageData(20, 20, 1, 1).data
+  t.true(r > 0)
+  t.is(g, 0)
+  t.is(b, 0)
+})
+
+test('assigning the same filter repeatedly should draw the same', (t) => {
+  const render = (times: number) => {
+    const canvas = createCanvas(50, 50)
+    const ctx = canvas.getContext('2d')
+    ctx.fillStyle = 'red'
+    for (let i = 0; i < times; i++) {
+      ctx.filter = 'blur(2px) drop-shadow(2px 2px 2px currentColor)'
+      ctx.fillRect(10, 10, 20, 20)
+    }
+    return canvas.data()
+  }
+  const once = createCanvas(50, 50)
+  const onceCtx = once.getContext('2d')
+  onceCtx.fillStyle = 'red'
+  onceCtx.filter = 'blur(2px) drop-shadow(2px 2px 2px currentColor)'
+  for (let i = 0; i < 10; i++) {
+    onceCtx.fillRect(10, 10, 20, 20)
+  }
+  t.deepEqual(render(10), once.data())
+})
+
+test('cached filters should follow the font size', (t) => {
+  const { ctx } = t.context
+  const edgeAt = (fontSize: number) => {
+    ctx.clearRect(0, 0, 300, 300)
+    ctx.font = `${fontSize}px sans-serif`
+    ctx.filter = 'blur(0.5em)'
+    ctx.fillRect(100, 100, 100, 100)
+    return ctx.getImageData(95, 150, 1, 1).data[3]
+  }
+  const small = edgeAt(2)
+  t.true(edgeAt(20) > small)
+  t.is(edgeAt(2), small)
+})
+
+test('drawImageWithFilter should only filter that draw', (t) => {
+  const { ctx } = t.context
+  const source = createCanvas(50, 50)
+  const sourceCtx = source.getContext('2d')
+  sourceCtx.fillStyle = 'white'
+  sourceCtx.fillRect(0, 0, 50, 50)
+  sourceCtx.fillStyle = 'black'
+  sourceCtx.fillRect(0, 0, 25, 50)
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+
+  ctx.drawImageWithFilter(source, 0, 0, 50, 50, 'blur(5px)')
+  ctx.drawImage(source, 100, 0, 50, 50)
+  t.is(ctx.filter, 'none')
+  // the hard edge is smoothed in the blurred copy only
+  const [blurred] = pixelAt(24, 25)
+  t.true(blurred > 0 && blurred < 255)
+  t.deepEqual(pixelAt(124, 25), [0, 0, 0, 255])
+  t.deepEqual(pixelAt(125, 25), [255, 255, 255, 255])
+})
+
 async function createImage(name: string) {
   const i = new Image()
   i.src = await fs.readFile(join(__dirname, 'fixtures', name))
   return i
 }
+
+test('contrast should pivot around the mid gray', (t) => {
+  const { ctx } = t.context
+  const filtered = (filter: string, color: string) => {
+    ctx.reset()
+    ctx.filter = filter
+    ctx.fillStyle = color
+    ctx.fillRect(0, 0, 10, 10)
+    return Array.from(ctx.getImageData(5, 5, 1, 1).data)
+  }
+  for (const color of ['red', 'white', 'rgb(20, 200, 90)']) {
+    const [r, g, b, a] = filtered('contrast(0)', color)
+    t.true([r, g, b].every((channel) => Math.abs(channel - 128) <= 1), `${color}: ${[r, g, b]}`)
+    t.is(a, 255)
+  }
+  // (160 / 255 - 0.5) * 2 + 0.5 and (96 / 255 - 0.5) * 2 + 0.5
+  const [light] = filtered('contrast(2)', 'rgb(160, 160, 160)')
+  const [dark] = filtered('contrast(2)', 'rgb(96, 96, 96)')
+  t.true(Math.abs(light - 192.5) <= 1, `${light}`)
+  t.true(Math.abs(dark - 64.5) <= 1, `${dark}`)
+  const halved = filtered('brightness(0.5)', 'rgb(200, 100, 40)')
+  t.true([100, 50, 20].every((channel, i) => Math.abs(halved[i] - channel) <= 1), `${halved}`)
+})
+
+test('comma separated filters should draw like the space separated ones', (t) => {
+  const render = (filter: string) => {
+    const canvas = createCanvas(50, 50)
+    const ctx = canvas.getContext('2d')
+    ctx.filter = filter
+    t.is(ctx.filter, filter)
+    ctx.fillStyle = 'rgb(100, 50, 20)'
+    ctx.fillRect(10, 10, 20, 20)
+    return canvas.data()
+  }
+  const expected = render('blur(2px) brightness(2) invert(1)')
+  t.deepEqual(render('blur(2px), brightness(2), invert(1)'), expected)
+  t.deepEqual(render('blur(2px),brightness(2) invert(1)'), expected)
+  const { ctx } = t.context
+  t.throws(() => {
+    ctx.filter = 'blur(2px),'
+  })
+  t.is(ctx.filter, 'none')
+})
+
+test('ctx.filter should compose with the default filter of the canvas', (t) => {
+  const render = (setup: (canvas: Canvas, ctx: SKRSContext2D) => void) => {
+    const canvas = createCanvas(50, 50)
+    const ctx = canvas.getContext('2d')
+    setup(canvas, ctx)
+    ctx.fillStyle = 'red'
+    ctx.fillRect(10, 10, 30, 30)
+    return canvas.data()
+  }
+  const grayscaleBlurred = render((_, ctx) => {
+    ctx.filter = 'grayscale(1) blur(2px)'
+  })
+  const blurred = render((_, ctx) => {
+    ctx.filter = 'blur(2px)'
+  })
+  const grayscale = render((_, ctx) => {
+    ctx.filter = 'grayscale(1)'
+  })
+  t.deepEqual(
+    render((canvas, ctx) => {
+      canvas.defaultFilter = 'grayscale(1)'
+      canvas.composeWithDefault = true
+      ctx.filter = 'blur(2px)'
+      t.is(ctx.filter, 'blur(2px)')
+    }),
+    grayscaleBlurred,
+  )
+  // without composing the context filter replaces the default one
+  t.deepEqual(
+    render((canvas, ctx) => {
+      canvas.defaultFilter = 'grayscale(1)'
+      ctx.filter = 'blur(2px)'
+    }),
+    blurred,
+  )
+  t.deepEqual(
+    render((canvas) => {
+      canvas.defaultFilter = 'grayscale(1)'
+    }),
+    grayscale,
+  )
+  // the default filter survives the context reset of a resize
+  t.deepEqual(
+    render((canvas) => {
+      canvas.defaultFilter = 'grayscale(1)'
+      canvas.width = 50
+    }),
+    grayscale,
+  )
+  const canvas = createCanvas(10, 10)
+  t.is(canvas.defaultFilter, 'none')
+  t.false(canvas.composeWithDefault)
+  canvas.defaultFilter = 'grayscale(1)'
+  canvas.defaultFilter = 'frobnicate(1)'
+  t.is(canvas.defaultFilter, 'grayscale(1)')
+})
+
+test('drop-shadow should keep the alpha of hex colors', (t) => {
+  const shadowAlpha = (color: string) => {
+    const canvas = createCanvas(50, 50)
+    const ctx = canvas.getContext('2d')
+    ctx.filter = `drop-shadow(20px 20px ${color})`
+    ctx.fillStyle = 'red'
+    ctx.fillRect(0, 0, 10, 10)
+    return ctx.getImageData(25, 25, 1, 1).data[3]
+  }
+  t.is(shadowAlpha('#00000080'), 128)
+  t.is(shadowAlpha('#0008'), 0x88)
+  t.is(shadowAlpha('#000'), 255)
+})
+
+test('url() should reference the filters registered on the context', (t) => {
+  const { ctx } = t.context
+  const pixelAt = (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+  ctx.fillStyle = 'rgb(200, 200, 200)'
+  ctx.registerFilter('dim', 'brightness(0.5)')
+  ctx.filter = 'url(#dim)'
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(pixelAt(5, 5), [100, 100, 100, 255])
+  // registering again updates the current filter
+  ctx.registerFilter('#dim', 'invert(1)')
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(pixelAt(5, 5), [55, 55, 55, 255])
+  // the unregistered references are skipped
+  ctx.registerFilter('dim', 'none')
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(pixelAt(5, 5), [200, 200, 200, 255])
+  t.throws(() => ctx.registerFilter('dim', 'frobnicate(1)'))
+  t.throws(() => ctx.registerFilter('#', 'blur(1px)'))
+})
+
+test('ctx.computedFilter should serialize the parsed filters', (t) => {
+  const { canvas, ctx } = t.context
+  t.is(ctx.computedFilter, 'none')
+  ctx.font = '20px sans-serif'
+  ctx.filter = 'blur(1em)  brightness(200%) drop-shadow(#2F14DF 2px 2px)'
+  t.is(ctx.computedFilter, 'blur(20px) brightness(2) drop-shadow(2px 2px 0px rgba(47,20,223,1))')
+  // `ctx.filter` keeps the assigned string
+  t.is(ctx.filter, 'blur(1em)  brightness(200%) drop-shadow(#2F14DF 2px 2px)')
+  ctx.filter = 'none'
+  canvas.defaultFilter = 'grayscale(100%)'
+  t.is(ctx.computedFilter, 'grayscale(1)')
+})
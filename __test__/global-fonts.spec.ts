@@ -2,7 +2,7 @@ import { readFileSync } from 'fs'
 import { join } from 'path'
 import test from 'ava'
 
-import { GlobalFonts } from '../index'
+import { GlobalFonts, createCanvas } from '../index'
 
 const fontPath = join(__dirname, 'fonts', 'SourceSerifPro-Regular.ttf')
 const fontData = readFileSync(fontPath)
@@ -35,6 +35,12 @@ test('return false if font path not existed', (t) => {
   t.false(GlobalFonts.register(Buffer.from('whatever')))
 })
 
+test('should throw if font file not existed', (t) => {
+  const missingPath = join(__dirname, 'fonts', 'not-existed.ttf')
+  const err = t.throws(() => GlobalFonts.registerFromPath(missingPath))
+  t.true(err?.message.includes(missingPath))
+})
+
 test('should be able to register font with name alias', (t) => {
   const fontAliasName = 'Cascadia-skr-canvas-test'
   t.true(GlobalFonts.registerFromPath(join(__dirname, 'fonts', 'Cascadia.woff2'), fontAliasName))
@@ -45,6 +51,23 @@ test('should be able to register font with name alias', (t) => {
   })
 })
 
+test('should be able to register font buffer with a descriptor', (t) => {
+  const family = 'Source-Serif-skr-buffer-test'
+  t.true(GlobalFonts.register(Buffer.from(fontData), { family, weight: 'bold', style: 'italic' }))
+  const styleSet = GlobalFonts.families.find((f) => f.family === family)
+  t.deepEqual(styleSet, {
+    family,
+    styles: [{ weight: 700, width: 'normal', style: 'italic' }],
+  })
+  const canvas = createCanvas(200, 100)
+  const ctx = canvas.getContext('2d')
+  ctx.font = `bold italic 24px ${family}`
+  ctx.fillText('Hello', 10, 50)
+  t.true(ctx.measureText('Hello').width > 0)
+  t.throws(() => GlobalFonts.register(fontData, { family, weight: 1200 }))
+  t.throws(() => GlobalFonts.register(fontData, { family, style: 'slanted' as any }))
+})
+
 test('should be able to register fonts from dir', (t) => {
   t.is(GlobalFonts.loadFontsFromDir(join(__dirname, 'fonts-dir')), 3)
 })
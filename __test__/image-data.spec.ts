@@ -1,6 +1,6 @@
 import test from 'ava'
 
-import { ImageData } from '../index'
+import { createCanvas, ImageData } from '../index'
 
 test('should be able to create ImageData', (t) => {
   t.notThrows(() => new ImageData(1024, 768))
@@ -35,6 +35,26 @@ test('should throw if width * height * 4 not equal to arraybuffer length', (t) =
   })
 })
 
+test('should throw if the size of the array overflows', (t) => {
+  t.throws(() => new ImageData(new Float32Array(0), 65536, 16384), {
+    code: 'InvalidArg',
+    message: /^RangeError/,
+  })
+  t.throws(() => new ImageData(new Float32Array(4), 0), { code: 'InvalidArg' })
+  t.throws(() => new ImageData(new Uint8ClampedArray(4), 0), { code: 'InvalidArg' })
+})
+
+test('getImageData should truncate the arguments before normalizing the rect', (t) => {
+  const canvas = createCanvas(20, 20)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(6, 10, 1, 1)
+  const imageData = ctx.getImageData(10, 10, -4.5, 2)
+  t.is(imageData.width, 4)
+  t.deepEqual(Array.from(imageData.data.slice(0, 4)), [255, 0, 0, 255])
+  t.throws(() => ctx.getImageData(0, 0, 2 ** 31 - 1, 2 ** 31 - 1), { message: /^RangeError/ })
+})
+
 test('properties should be readonly', (t) => {
   const imageData = new ImageData(1024, 768)
   const fakeData = new Uint8ClampedArray()
@@ -51,3 +71,24 @@ test('properties should be readonly', (t) => {
   // @ts-expect-error
   t.throws(() => (imageData.height = 514), expectation)
 })
+
+test('getImageData should zero the pixels outside of the canvas', (t) => {
+  const canvas = createCanvas(20, 20)
+  const ctx = canvas.getContext('2d')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 20, 20)
+  const imageData = ctx.getImageData(-5, -5, 10, 10)
+  t.is(imageData.width, 10)
+  t.is(imageData.height, 10)
+  const pixelAt = (x: number, y: number) => Array.from(imageData.data.slice((y * 10 + x) * 4, (y * 10 + x + 1) * 4))
+  t.deepEqual(pixelAt(0, 0), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(4, 9), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(9, 4), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(5, 5), [255, 0, 0, 255])
+  t.deepEqual(pixelAt(9, 9), [255, 0, 0, 255])
+  // fully outside
+  t.true(ctx.getImageData(100, 100, 2, 2).data.every((v) => v === 0))
+  // negative sizes span from the other corner
+  t.deepEqual(ctx.getImageData(5, 5, -10, -10).data, imageData.data)
+  t.throws(() => ctx.getImageData(0, 0, 0, 10))
+})
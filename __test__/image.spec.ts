@@ -2,7 +2,7 @@ import { promises as fs } from 'fs'
 import { join } from 'path'
 import test from 'ava'
 
-import { createCanvas, Image, loadImage } from '../index'
+import { createCanvas, createImageBitmap, Image, ImageBitmap, ImageData, loadImage } from '../index'
 
 import { snapshotImage } from './image-snapshot'
 
@@ -110,6 +110,25 @@ test('load invalid image should not throw if onerror is provided', async (t) =>
   )
 })
 
+test('decode should resolve for valid images and reject for truncated ones', async (t) => {
+  const file = await loadImageFile()
+  const image = new Image()
+  image.src = file
+  await t.notThrowsAsync(() => image.decode())
+
+  const truncated = new Image()
+  truncated.onerror = () => {}
+  truncated.src = file.subarray(0, 16)
+  await t.throwsAsync(() => truncated.decode(), { message: /^failed to decode png image/ })
+  t.is(truncated.naturalWidth, 0)
+
+  // without `src` the Promise waits for the assignment
+  const pending = new Image()
+  const decoded = pending.decode()
+  pending.src = file
+  await t.notThrowsAsync(() => decoded)
+})
+
 test('reset src to empty should not throw error', async (t) => {
   await t.notThrowsAsync(
     () =>
@@ -125,3 +144,122 @@ test('reset src to empty should not throw error', async (t) => {
       }),
   )
 })
+
+test('should expose the frames of animated images', async (t) => {
+  const image = new Image()
+  image.src = await fs.readFile(join(__dirname, 'fixtures', 'animated-3-frames.webp'))
+  t.is(image.frames.length, 3)
+  t.deepEqual(
+    image.frames.map((frame) => frame.duration),
+    [100, 200, 300],
+  )
+  const still = new Image()
+  still.src = await loadImageFile()
+  t.deepEqual(still.frames, [{ duration: 0 }])
+})
+
+test('should draw a specific frame of animated images', async (t) => {
+  const image = new Image()
+  image.src = await fs.readFile(join(__dirname, 'fixtures', 'animated-3-frames.webp'))
+  const canvas = createCanvas(2, 2)
+  const ctx = canvas.getContext('2d')
+  const expected = [
+    [255, 0, 0, 255],
+    [0, 255, 0, 255],
+    [0, 0, 255, 255],
+  ]
+  for (const [frame, pixel] of expected.entries()) {
+    ctx.drawImageFrame(image, frame, 0, 0)
+    t.deepEqual(Array.from(ctx.getImageData(0, 0, 1, 1).data), pixel)
+  }
+  t.throws(() => ctx.drawImageFrame(image, 3, 0, 0))
+})
+
+test('should rotate images upright by their EXIF orientation', async (t) => {
+  const data = await fs.readFile(join(__dirname, 'fixtures', 'exif-orientation-6.jpeg'))
+  const stored = new Image()
+  stored.imageOrientation = 'none'
+  stored.src = data
+  const upright = new Image()
+  upright.src = data
+  t.is(stored.orientation, 6)
+  t.is(upright.orientation, 6)
+  t.is(upright.imageOrientation, 'from-image')
+  t.deepEqual([stored.naturalWidth, stored.naturalHeight], [240, 151])
+  t.deepEqual([upright.naturalWidth, upright.naturalHeight], [151, 240])
+  const storedPixel = pixelsOfImage(stored)
+  const uprightPixel = pixelsOfImage(upright)
+  // orientation 6 is rotated 90° clockwise for display
+  for (const [x, y] of [
+    [0, 0],
+    [239, 0],
+    [30, 120],
+    [239, 150],
+  ]) {
+    t.deepEqual(uprightPixel(150 - y, x), storedPixel(x, y))
+  }
+})
+
+function pixelsOfImage(image: Image) {
+  const canvas = createCanvas(image.naturalWidth, image.naturalHeight)
+  const ctx = canvas.getContext('2d')
+  ctx.drawImage(image, 0, 0)
+  return (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+}
+
+function quadrants() {
+  const canvas = createCanvas(20, 20)
+  const ctx = canvas.getContext('2d')
+  const colors = ['red', 'lime', 'blue', 'white']
+  colors.forEach((color, i) => {
+    ctx.fillStyle = color
+    ctx.fillRect((i % 2) * 10, Math.floor(i / 2) * 10, 10, 10)
+  })
+  return canvas
+}
+
+function pixelsOf(bitmap: ImageBitmap) {
+  const canvas = createCanvas(bitmap.width, bitmap.height)
+  const ctx = canvas.getContext('2d')
+  ctx.drawImage(bitmap, 0, 0)
+  return (x: number, y: number) => Array.from(ctx.getImageData(x, y, 1, 1).data)
+}
+
+test('createImageBitmap should crop and resize the source', async (t) => {
+  // the right half of the crop rect is outside of the source
+  const bitmap = await createImageBitmap(quadrants(), 10, 0, 20, 20, {
+    resizeWidth: 40,
+    resizeHeight: 40,
+    resizeQuality: 'pixelated',
+  })
+  t.is(bitmap.width, 40)
+  t.is(bitmap.height, 40)
+  const pixelAt = pixelsOf(bitmap)
+  t.deepEqual(pixelAt(0, 0), [0, 255, 0, 255])
+  t.deepEqual(pixelAt(0, 39), [255, 255, 255, 255])
+  t.deepEqual(pixelAt(39, 0), [0, 0, 0, 0])
+  t.deepEqual(pixelAt(39, 39), [0, 0, 0, 0])
+})
+
+test('createImageBitmap should keep the aspect ratio and flip the source', async (t) => {
+  const bitmap = await createImageBitmap(quadrants(), { resizeWidth: 10, imageOrientation: 'flipY' })
+  t.is(bitmap.width, 10)
+  t.is(bitmap.height, 10)
+  const pixelAt = pixelsOf(bitmap)
+  t.deepEqual(pixelAt(1, 1), [0, 0, 255, 255])
+  t.deepEqual(pixelAt(8, 8), [0, 255, 0, 255])
+  const fromImageData = await createImageBitmap(new ImageData(new Uint8ClampedArray([255, 0, 0, 255]), 1, 1))
+  t.deepEqual(pixelsOf(fromImageData)(0, 0), [255, 0, 0, 255])
+  await t.throwsAsync(() => createImageBitmap(quadrants(), 0, 0, 0, 10))
+})
+
+
+test('createImageBitmap should copy the source before resolving', async (t) => {
+  const source = quadrants()
+  const pending = createImageBitmap(source)
+  const ctx = source.getContext('2d')
+  ctx.fillStyle = 'black'
+  ctx.fillRect(0, 0, source.width, source.height)
+  const bitmap = await pending
+  t.deepEqual(pixelsOf(bitmap)(15, 5), [0, 255, 0, 255])
+})
@@ -40,6 +40,20 @@ test('globalAlpha state should be ok', (t) => {
   t.is(ctx.globalAlpha, 0.2)
 })
 
+test('globalAlpha should ignore out of range and NaN values', (t) => {
+  const { ctx } = t.context
+  ctx.globalAlpha = 0.5
+  ctx.globalAlpha = 2
+  t.is(ctx.globalAlpha, 0.5)
+  ctx.globalAlpha = NaN
+  t.is(ctx.globalAlpha, 0.5)
+  ctx.globalAlpha = -1
+  t.is(ctx.globalAlpha, 0.5)
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [255, 0, 0, 128])
+})
+
 test('globalCompositeOperation state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.globalCompositeOperation, 'source-over')
@@ -47,6 +61,23 @@ test('globalCompositeOperation state should be ok', (t) => {
   t.is(ctx.globalCompositeOperation, 'xor')
 })
 
+test('invalid globalCompositeOperation should keep the current mode', (t) => {
+  const { ctx } = t.context
+  ctx.globalCompositeOperation = 'multiply'
+  // @ts-expect-error
+  ctx.globalCompositeOperation = 'not-a-mode'
+  t.is(ctx.globalCompositeOperation, 'multiply')
+  // @ts-expect-error
+  ctx.globalCompositeOperation = ''
+  t.is(ctx.globalCompositeOperation, 'multiply')
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 10, 10)
+  ctx.fillStyle = 'blue'
+  ctx.fillRect(0, 0, 10, 10)
+  // red multiplied by blue, source-over would leave blue
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [0, 0, 0, 255])
+})
+
 test('imageSmoothingEnabled state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.imageSmoothingEnabled, true)
@@ -61,6 +92,39 @@ test('imageSmoothingQuality state should be ok', (t) => {
   t.is(ctx.imageSmoothingQuality, 'high')
 })
 
+test('antialias state should be ok', (t) => {
+  const { ctx } = t.context
+  t.is(ctx.antialias, 'default')
+  ctx.antialias = 'none'
+  t.is(ctx.antialias, 'none')
+  // @ts-expect-error
+  ctx.antialias = 'off'
+  t.is(ctx.antialias, 'none')
+})
+
+test('antialias none should draw hard edges', (t) => {
+  const partialAlphas = (antialias: SKRSContext2D['antialias']) => {
+    const canvas = createCanvas(50, 50)
+    const ctx = canvas.getContext('2d')
+    ctx.antialias = antialias
+    ctx.lineWidth = 3
+    ctx.beginPath()
+    ctx.moveTo(0, 5)
+    ctx.lineTo(50, 45)
+    ctx.stroke()
+    const { data } = ctx.getImageData(0, 0, 50, 50)
+    let count = 0
+    for (let i = 3; i < data.length; i += 4) {
+      if (data[i] > 0 && data[i] < 255) {
+        count++
+      }
+    }
+    return count
+  }
+  t.true(partialAlphas('default') > 0)
+  t.is(partialAlphas('none'), 0)
+})
+
 test('lineCap state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.lineCap, 'butt')
@@ -89,6 +153,28 @@ test('lineWidth state should be ok', (t) => {
   t.is(ctx.lineWidth, 10)
 })
 
+test('invalid lineWidth and miterLimit should be ignored', (t) => {
+  const { ctx } = t.context
+  ctx.lineWidth = 3
+  for (const width of [0, -5, NaN, Infinity, -Infinity, 1e40]) {
+    ctx.lineWidth = width
+    t.is(ctx.lineWidth, 3)
+  }
+  ctx.miterLimit = 4
+  for (const limit of [0, -1, NaN, Infinity]) {
+    ctx.miterLimit = limit
+    t.is(ctx.miterLimit, 4)
+  }
+  ctx.strokeStyle = 'black'
+  ctx.beginPath()
+  ctx.moveTo(0, 50.5)
+  ctx.lineTo(100, 50.5)
+  ctx.stroke()
+  // 3px wide around y = 50.5
+  const column = Array.from({ length: 7 }, (_, i) => ctx.getImageData(50, 47 + i, 1, 1).data[3])
+  t.deepEqual(column, [0, 0, 255, 255, 255, 0, 0])
+})
+
 test('fillStyle state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.fillStyle, '#000')
@@ -96,6 +182,22 @@ test('fillStyle state should be ok', (t) => {
   t.is(ctx.fillStyle, 'hotpink')
 })
 
+test('fillStyle should accept the lab, lch, oklab and oklch colors', (t) => {
+  const { ctx } = t.context
+  const pixel = (color: string) => {
+    ctx.fillStyle = color
+    ctx.fillRect(0, 0, 10, 10)
+    return Array.from(ctx.getImageData(5, 5, 1, 1).data)
+  }
+  t.deepEqual(pixel('oklab(0.6 0.1 -0.1)'), [159, 99, 186, 255])
+  t.is(ctx.fillStyle, 'oklab(0.6 0.1 -0.1)')
+  t.deepEqual(pixel('oklch(0.7 0.15 200)'), [0, 185, 195, 255])
+  t.deepEqual(pixel('lab(54.29 80.82 69.88)'), [255, 0, 0, 255])
+  t.deepEqual(pixel('lch(54.29 106.84 40.85deg)'), [255, 0, 0, 255])
+  ctx.fillStyle = 'oklab(0.6 0.1)'
+  t.is(ctx.fillStyle, 'lch(54.29 106.84 40.85deg)')
+})
+
 test('strokeStyle state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.strokeStyle, '#000')
@@ -138,6 +240,40 @@ test('lineDash state should be ok', (t) => {
   t.deepEqual(ctx.getLineDash(), lineDash)
 })
 
+test('odd line dash should be duplicated and invalid segments should throw', (t) => {
+  const { ctx } = t.context
+  ctx.setLineDash([5, 10, 15])
+  t.deepEqual(ctx.getLineDash(), [5, 10, 15, 5, 10, 15])
+  t.throws(() => ctx.setLineDash([5, -1]))
+  t.throws(() => ctx.setLineDash([5, NaN]))
+  t.deepEqual(ctx.getLineDash(), [5, 10, 15, 5, 10, 15])
+  ctx.save()
+  ctx.setLineDash([1, 1])
+  ctx.lineDashOffset = 2
+  ctx.restore()
+  t.deepEqual(ctx.getLineDash(), [5, 10, 15, 5, 10, 15])
+  t.is(ctx.lineDashOffset, 0)
+})
+
+test('line dash should alternate drawn and gap pixels', (t) => {
+  const canvas = createCanvas(40, 10)
+  const ctx = canvas.getContext('2d')
+  ctx.setLineDash([5, 5])
+  ctx.lineWidth = 2
+  ctx.beginPath()
+  ctx.moveTo(0, 5)
+  ctx.lineTo(40, 5)
+  ctx.stroke()
+  const alphaAt = (x: number) => ctx.getImageData(x, 4, 1, 1).data[3]
+  t.is(alphaAt(2), 255)
+  t.is(alphaAt(7), 0)
+  t.is(alphaAt(12), 255)
+  t.is(alphaAt(17), 0)
+  // the dash only applies to strokes
+  ctx.fillRect(0, 0, 40, 10)
+  t.is(alphaAt(7), 255)
+})
+
 test('textAlign state should be ok', (t) => {
   const { ctx } = t.context
   t.is(ctx.textAlign, 'start')
@@ -157,6 +293,75 @@ test('getTransform', (t) => {
   t.deepEqual(ctx.getTransform(), new DOMMatrix([1, 0, 0, 1, 0, 0]))
 })
 
+test('getTransform should reflect accumulated transforms', (t) => {
+  const { ctx } = t.context
+  ctx.translate(10, 20)
+  ctx.scale(2, 3)
+  const matrix = ctx.getTransform()
+  t.true(matrix instanceof DOMMatrix)
+  t.deepEqual(matrix, new DOMMatrix([2, 0, 0, 3, 10, 20]))
+  t.is(matrix.m11, 2)
+  t.is(matrix.m22, 3)
+  t.is(matrix.m41, 10)
+  t.is(matrix.m42, 20)
+  ctx.resetTransform()
+  t.deepEqual(ctx.getTransform(), new DOMMatrix([1, 0, 0, 1, 0, 0]))
+})
+
+test('non-finite transform arguments should be ignored', (t) => {
+  const { ctx } = t.context
+  ctx.translate(10, 20)
+  const matrix = ctx.getTransform()
+  ctx.scale(NaN, 1)
+  ctx.scale(1, Infinity)
+  ctx.rotate(NaN)
+  ctx.translate(-Infinity, 0)
+  ctx.transform(1, 0, 0, 1, NaN, 0)
+  ctx.setTransform(NaN, 0, 0, 1, 0, 0)
+  ctx.setTransform({ a: 1, b: 0, c: 0, d: Infinity, e: 0, f: 0 })
+  t.deepEqual(ctx.getTransform(), matrix)
+  ctx.fillStyle = 'red'
+  ctx.fillRect(0, 0, 10, 10)
+  t.deepEqual(Array.from(ctx.getImageData(15, 25, 1, 1).data), [255, 0, 0, 255])
+})
+
+test('reset should restore the initial state', (t) => {
+  const { ctx } = t.context
+  const initial = createCanvas(1024, 768).getContext('2d')
+  const snapshotState = (context: SKRSContext2D) => ({
+    fillStyle: context.fillStyle,
+    strokeStyle: context.strokeStyle,
+    filter: context.filter,
+    font: context.font,
+    globalAlpha: context.globalAlpha,
+    lineWidth: context.lineWidth,
+    textAlign: context.textAlign,
+    transform: context.getTransform(),
+  })
+  ctx.fillStyle = 'red'
+  ctx.strokeStyle = 'blue'
+  ctx.filter = 'blur(2px)'
+  ctx.font = '20px sans-serif'
+  ctx.globalAlpha = 0.5
+  ctx.lineWidth = 10
+  ctx.textAlign = 'center'
+  ctx.save()
+  ctx.translate(10, 10)
+  ctx.rect(0, 0, 100, 100)
+  ctx.clip()
+  ctx.fillRect(0, 0, 200, 200)
+  ctx.beginPath()
+  ctx.rect(0, 0, 50, 50)
+
+  ctx.reset()
+  t.deepEqual(snapshotState(ctx), snapshotState(initial))
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 0, 0])
+  t.false(ctx.isPointInPath(10, 10))
+  // the clip is gone too
+  ctx.fillRect(0, 0, 1024, 768)
+  t.deepEqual(Array.from(ctx.getImageData(500, 500, 1, 1).data), [0, 0, 0, 255])
+})
+
 test('stroke-and-filling-jpeg', async (t) => {
   const { ctx } = t.context
   ctx.lineWidth = 16
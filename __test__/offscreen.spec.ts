@@ -0,0 +1,78 @@
+import test from 'ava'
+
+import { createCanvas, OffscreenCanvas, SKRSContext2D } from '../index'
+
+function draw(ctx: SKRSContext2D) {
+  ctx.fillStyle = 'hotpink'
+  ctx.fillRect(10, 10, 80, 60)
+  ctx.strokeStyle = 'navy'
+  ctx.lineWidth = 4
+  ctx.beginPath()
+  ctx.arc(100, 80, 30, 0, Math.PI * 2)
+  ctx.stroke()
+}
+
+test('OffscreenCanvas should render like a regular canvas', async (t) => {
+  const offscreen = new OffscreenCanvas(200, 150)
+  const ctx = offscreen.getContext('2d')
+  t.is(ctx.canvas as unknown, offscreen)
+  draw(ctx)
+  const canvas = createCanvas(200, 150)
+  draw(canvas.getContext('2d'))
+
+  const blob = await offscreen.convertToBlob()
+  t.is(blob.type, 'image/png')
+  t.deepEqual(Buffer.from(await blob.arrayBuffer()), canvas.toBuffer('image/png'))
+  const jpeg = await offscreen.convertToBlob({ type: 'image/jpeg', quality: 0.5 })
+  t.is(jpeg.type, 'image/jpeg')
+  t.deepEqual(Buffer.from(await jpeg.arrayBuffer()), canvas.toBuffer('image/jpeg', 50))
+})
+
+test('transferToImageBitmap should move the pixels out of the OffscreenCanvas', (t) => {
+  const offscreen = new OffscreenCanvas(200, 150)
+  const ctx = offscreen.getContext('2d')
+  draw(ctx)
+  const bitmap = offscreen.transferToImageBitmap()
+  t.is(bitmap.width, 200)
+  t.is(bitmap.height, 150)
+  t.deepEqual(Array.from(ctx.getImageData(50, 50, 1, 1).data), [0, 0, 0, 0])
+
+  const canvas = createCanvas(200, 150)
+  const target = canvas.getContext('2d')
+  target.drawImage(bitmap, 0, 0)
+  t.deepEqual(Array.from(target.getImageData(50, 50, 1, 1).data), [255, 105, 180, 255])
+  bitmap.close()
+  t.is(bitmap.width, 0)
+})
+
+test('resizing the OffscreenCanvas should keep the context attributes', (t) => {
+  const offscreen = new OffscreenCanvas(20, 20)
+  const ctx = offscreen.getContext('2d', { alpha: false })
+  t.deepEqual(Array.from(ctx.getImageData(5, 5, 1, 1).data), [0, 0, 0, 255])
+  offscreen.width = 40
+  t.is(offscreen.getContext('2d'), ctx)
+  t.deepEqual(Array.from(ctx.getImageData(30, 5, 1, 1).data), [0, 0, 0, 255])
+})
+
+test('drawImage should composite a snapshot of the source canvas at draw time', (t) => {
+  const canvas = createCanvas(100, 100)
+  const ctx = canvas.getContext('2d')
+  const sourceCanvas = createCanvas(20, 20)
+  const offscreen = new OffscreenCanvas(20, 20)
+  const sources = [
+    [sourceCanvas, sourceCanvas.getContext('2d')],
+    [offscreen, offscreen.getContext('2d')],
+  ] as const
+  for (const [source, sourceCtx] of sources) {
+    sourceCtx.fillStyle = 'red'
+    sourceCtx.fillRect(0, 0, 20, 20)
+    ctx.clearRect(0, 0, 100, 100)
+    ctx.drawImage(source, 30, 40)
+    sourceCtx.fillStyle = 'blue'
+    sourceCtx.fillRect(0, 0, 20, 20)
+    t.deepEqual(Array.from(ctx.getImageData(35, 45, 1, 1).data), [255, 0, 0, 255])
+    t.deepEqual(Array.from(ctx.getImageData(25, 45, 1, 1).data), [0, 0, 0, 0])
+    ctx.drawImage(source, 30, 40)
+    t.deepEqual(Array.from(ctx.getImageData(49, 59, 1, 1).data), [0, 0, 255, 255])
+  }
+})
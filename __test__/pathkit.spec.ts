@@ -8,6 +8,51 @@ test('should be able to call toSVGString', (t) => {
   t.is(path.toSVGString(), 'M0 0L100 0L100 100L0 100L0 0Z')
 })
 
+test('should be able to call toSVGString with relative commands', (t) => {
+  const path = new Path2D()
+  path.rect(10, 10, 100, 100)
+  const d = path.toSVGString({ relative: true })
+  t.true(d.startsWith('m10 10'))
+  t.true(d.includes('l100 0'))
+  t.true(d.includes('l0 100'))
+  t.true(d.includes('l-100 0'))
+  t.true(d.toLowerCase().endsWith('z'))
+  t.deepEqual(new Path2D(d).getBounds(), path.getBounds())
+})
+
+const roundBounds = (bounds: number[]) => bounds.map((v) => Math.round(v * 1000) / 1000)
+
+test('should build path from SVG path data', (t) => {
+  // relative commands
+  t.deepEqual(new Path2D('m10 10 l20 0 v20 h-20 z').computeTightBounds(), [10, 10, 30, 30])
+  // the sweep flag selects the upper half circle
+  t.deepEqual(roundBounds(new Path2D('M10 50 A40 40 0 0 1 90 50').computeTightBounds()), [10, 10, 90, 50])
+  t.deepEqual(roundBounds(new Path2D('M10 50 a40 40 0 0 0 80 0').computeTightBounds()), [10, 50, 90, 90])
+  // the smooth curves reflect the previous control point
+  t.deepEqual(roundBounds(new Path2D('M0 0 C0 20 20 20 20 0 S40 -20 40 0').computeTightBounds()), [0, -15, 40, 15])
+  t.deepEqual(roundBounds(new Path2D('M0 0 Q10 20 20 0 T40 0').computeTightBounds()), [0, -10, 40, 10])
+  t.deepEqual(roundBounds(new Path2D('M0 0 q10 20 20 0 t20 0').computeTightBounds()), [0, -10, 40, 10])
+})
+
+test('should keep the SVG path commands before the first error', (t) => {
+  t.deepEqual(new Path2D('M10 10 L20 20 L30 foo').getBounds(), [10, 10, 20, 20])
+  t.deepEqual(new Path2D('M10 10 L1e1 2e1 L30').getBounds(), [10, 10, 10, 20])
+  t.deepEqual(new Path2D('oops').getBounds(), new Path2D().getBounds())
+})
+
+test('addPath should append a transformed copy of the path', (t) => {
+  const rect = new Path2D()
+  rect.rect(0, 0, 10, 10)
+  const path = new Path2D()
+  path.addPath(rect)
+  path.addPath(rect, { e: 100, f: 50 })
+  t.deepEqual(path.getBounds(), [0, 0, 110, 60])
+  t.deepEqual(rect.getBounds(), [0, 0, 10, 10])
+  // the sub paths are not connected
+  t.false(path.isPointInPath(55, 30))
+  t.is(path.toSVGString(), 'M0 0L10 0L10 10L0 10L0 0ZM100 50L110 50L110 60L100 60L100 50Z')
+})
+
 test('should be able to create mountain via op', (t) => {
   const pathOne = new Path2D()
   const pathTwo = new Path2D()
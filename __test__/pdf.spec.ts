@@ -0,0 +1,58 @@
+import { join } from 'path'
+import { setFlagsFromString } from 'v8'
+import { runInNewContext } from 'vm'
+
+import test from 'ava'
+
+import { GlobalFonts, PdfDocument } from '../index'
+
+test.before(() => {
+  GlobalFonts.registerFromPath(join(__dirname, 'fonts', 'SourceSerifPro-Regular.ttf'), 'Source Serif Pro')
+})
+
+test('should export a multi pages pdf document', (t) => {
+  const doc = new PdfDocument()
+  for (const text of ['first page', 'second page']) {
+    const ctx = doc.beginPage(300, 200)
+    ctx.font = '24px Source Serif Pro'
+    ctx.fillText(text, 20, 50)
+    doc.endPage()
+  }
+  t.is(doc.pageCount, 2)
+  const buf = doc.toBuffer('application/pdf')
+  t.is(buf.subarray(0, 5).toString('latin1'), '%PDF-')
+  t.is(buf.toString('latin1').match(/\/Type\s*\/Page\b/g)?.length, 2)
+})
+
+test('should not begin a page after the document is finished', (t) => {
+  const doc = new PdfDocument()
+  doc.beginPage(100, 100).fillRect(0, 0, 50, 50)
+  const buf = doc.toBuffer()
+  t.deepEqual(doc.toBuffer(), buf)
+  t.throws(() => doc.beginPage(100, 100))
+})
+
+test('should reject unsupported mime types', (t) => {
+  const doc = new PdfDocument()
+  // @ts-expect-error
+  t.throws(() => doc.toBuffer('image/png'))
+})
+
+test('the page context should not read pixels', (t) => {
+  const doc = new PdfDocument()
+  const ctx = doc.beginPage(100, 100)
+  ctx.fillRect(0, 0, 50, 50)
+  t.throws(() => ctx.getImageData(0, 0, 10, 10), {
+    message: /PDF page/,
+  })
+})
+
+test('the page context should keep the document alive', async (t) => {
+  setFlagsFromString('--expose-gc')
+  const gc: () => void = runInNewContext('gc')
+  const ctx = new PdfDocument().beginPage(100, 100)
+  gc()
+  await new Promise((resolve) => setImmediate(resolve))
+  gc()
+  t.notThrows(() => ctx.fillRect(0, 0, 50, 50))
+})
@@ -38,3 +38,34 @@ test('should be able to export text', (t) => {
   ctx.strokeText('@napi-rs/canvas', 50, 300)
   t.snapshot(canvas.getContent().toString('utf8'))
 })
+
+test('toBuffer should return the svg document', (t) => {
+  const canvas = createCanvas(200, 100, 0)
+  const ctx = canvas.getContext('2d')
+  ctx.fillRect(10, 10, 50, 50)
+  ctx.beginPath()
+  ctx.moveTo(0, 80)
+  ctx.lineTo(200, 80)
+  ctx.stroke()
+  ctx.font = '20px sans-serif'
+  ctx.fillText('Hello', 100, 40)
+  const svg = canvas.toBuffer('image/svg+xml').toString('utf8')
+  t.true(svg.startsWith('<?xml'))
+  t.true(svg.includes('<rect'))
+  t.true(svg.includes('<path'))
+  t.true(svg.includes('<text'))
+  t.is(svg, canvas.getContent().toString('utf8'))
+  // @ts-expect-error
+  t.throws(() => canvas.toBuffer('image/png'))
+})
+
+test('svg export flags can be combined', (t) => {
+  const canvas = createCanvas(200, 100, SvgExportFlag.ConvertTextToPaths | SvgExportFlag.NoPrettyXML)
+  const ctx = canvas.getContext('2d')
+  ctx.font = '20px sans-serif'
+  ctx.fillText('Hello', 10, 40)
+  const svg = canvas.toBuffer('image/svg+xml').toString('utf8')
+  t.false(svg.includes('<text'))
+  t.false(svg.includes('\n  '))
+  t.throws(() => createCanvas(10, 10, 0x08 as SvgExportFlag))
+})
@@ -3,7 +3,7 @@ import { readFileSync, promises as fs } from 'fs'
 
 import test from 'ava'
 
-import { convertSVGTextToPath, GlobalFonts } from '../index'
+import { convertSVGTextToPath, convertSVGTextToPathAsync, GlobalFonts } from '../index'
 
 GlobalFonts.registerFromPath(join(__dirname, 'fonts', 'iosevka-slab-regular.ttf'))
 
@@ -19,3 +19,36 @@ test('convertSVGTextToPath should work', async (t) => {
     t.deepEqual(result.toString('utf8'), output)
   }
 })
+
+test('convertSVGTextToPathAsync should work', async (t) => {
+  const result = await convertSVGTextToPathAsync(FIXTURE)
+  const outputPath = join(__dirname, 'text-to-path.svg')
+  const output = await fs.readFile(outputPath, 'utf8')
+  if (process.platform === 'win32') {
+    t.pass('Skip on windows')
+  } else {
+    t.deepEqual(result.toString('utf8'), output)
+  }
+})
+
+test('convertSVGTextToPath should throw on empty input', (t) => {
+  t.throws(() => convertSVGTextToPath(Buffer.from([])), {
+    message: 'Convert svg text to path failed: input is empty',
+  })
+})
+
+test('convertSVGTextToPath should throw on invalid svg', async (t) => {
+  t.throws(() => convertSVGTextToPath('<svg><text>'), {
+    message: 'Convert svg text to path failed: input is not a valid svg document',
+  })
+  await t.throwsAsync(() => convertSVGTextToPathAsync('<svg><text>'), {
+    message: 'Convert svg text to path failed: input is not a valid svg document',
+  })
+})
+
+test('convertSVGTextToPath should keep text if convertAll is false', (t) => {
+  const converted = convertSVGTextToPath(FIXTURE)
+  const result = convertSVGTextToPath(FIXTURE, { convertAll: false })
+  t.notDeepEqual(result.toString('utf8'), converted.toString('utf8'))
+  t.true(result.toString('utf8').includes('<text'))
+})
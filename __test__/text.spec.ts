@@ -1,3 +1,5 @@
+import { existsSync } from 'fs'
+import { platform } from 'os'
 import { join } from 'path'
 
 import ava, { TestFn } from 'ava'
@@ -62,6 +64,246 @@ test(`measureText with suffix spaces`, async (t) => {
   t.is(ctx.measureText('  ').width, widthWithTwoSpace - width)
 })
 
+test('measureText should report the bounds of non ASCII glyphs', (t) => {
+  const { ctx } = t.context
+  ctx.font = '50px Iosevka Slab'
+  const a = ctx.measureText('A')
+  const ring = ctx.measureText('Å')
+  // the ring above is taller than the capital, both sit on the baseline
+  t.true(ring.actualBoundingBoxAscent > a.actualBoundingBoxAscent)
+  t.true(ring.actualBoundingBoxAscent <= ring.fontBoundingBoxAscent)
+  t.true(Math.abs(ring.actualBoundingBoxDescent - a.actualBoundingBoxDescent) < 0.001)
+  t.is(ring.fontBoundingBoxAscent, a.fontBoundingBoxAscent)
+  t.true(Math.abs(ring.emHeightAscent + ring.emHeightDescent - 50) < 0.001)
+  ctx.textBaseline = 'top'
+  const top = ctx.measureText('Å')
+  t.true(top.actualBoundingBoxAscent < ring.actualBoundingBoxAscent)
+  t.true(Math.abs(top.emHeightAscent + top.emHeightDescent - 50) < 0.001)
+  // the horizontal box is relative to the aligned text position
+  ctx.textAlign = 'right'
+  const right = ctx.measureText('Å')
+  t.true(Math.abs(right.actualBoundingBoxLeft - (top.actualBoundingBoxLeft + top.width)) < 0.001)
+  t.true(Math.abs(right.actualBoundingBoxRight - (top.actualBoundingBoxRight - top.width)) < 0.001)
+})
+
+test('font shorthand should apply font-variant-caps and font-stretch', (t) => {
+  const { ctx } = t.context
+  GlobalFonts.registerFromPath(join(__dirname, 'fonts', 'SourceSerifPro-Regular.ttf'))
+  ctx.font = '16px Source Serif Pro'
+  const { width } = ctx.measureText('small caps')
+  ctx.font = 'small-caps 16px Source Serif Pro'
+  t.is(ctx.font, 'small-caps 16px Source Serif Pro')
+  t.not(ctx.measureText('small caps').width, width)
+  ctx.font = 'condensed 20px serif'
+  t.is(ctx.font, 'condensed 20px serif')
+  t.true(ctx.measureText('condensed').width > 0)
+})
+
+test('fontKerning', (t) => {
+  const { ctx } = t.context
+  GlobalFonts.registerFromPath(join(__dirname, 'fonts', 'SourceSerifPro-Regular.ttf'))
+  ctx.font = '50px Source Serif Pro'
+  t.is(ctx.fontKerning, 'auto')
+  ctx.fontKerning = 'normal'
+  const kerned = ctx.measureText('AV').width
+  ctx.fontKerning = 'none'
+  t.is(ctx.fontKerning, 'none')
+  t.true(ctx.measureText('AV').width > kerned)
+  // invalid values are ignored
+  // @ts-expect-error
+  ctx.fontKerning = 'off'
+  t.is(ctx.fontKerning, 'none')
+})
+
+test('textRendering', (t) => {
+  const { ctx } = t.context
+  t.is(ctx.textRendering, 'auto')
+  ctx.textRendering = 'optimizeLegibility'
+  t.is(ctx.textRendering, 'optimizeLegibility')
+  // invalid values are ignored
+  // @ts-expect-error
+  ctx.textRendering = 'optimize-speed'
+  t.is(ctx.textRendering, 'optimizeLegibility')
+
+  const partialAlphas = (textRendering: SKRSContext2D['textRendering']) => {
+    const canvas = createCanvas(200, 50)
+    const context = canvas.getContext('2d')
+    context.font = '30px Iosevka Slab'
+    context.textRendering = textRendering
+    context.fillText('Hello', 10, 35)
+    const { data } = context.getImageData(0, 0, 200, 50)
+    let count = 0
+    for (let i = 3; i < data.length; i += 4) {
+      if (data[i] > 0 && data[i] < 255) {
+        count++
+      }
+    }
+    return count
+  }
+  // aliased glyphs have hard edges
+  t.is(partialAlphas('optimizeSpeed'), 0)
+  t.true(partialAlphas('geometricPrecision') > 0)
+})
+
+test('fillTextWrapped should break long text into lines', (t) => {
+  const { ctx } = t.context
+  ctx.font = '20px Iosevka Slab'
+  const sentence = 'The quick brown fox jumps over the lazy dog'
+  t.true(ctx.measureText(sentence).width > 150)
+  const rowsWithInk = () => {
+    const { data } = ctx.getImageData(0, 0, 512, 512)
+    const rows = new Set<number>()
+    for (let i = 3; i < data.length; i += 4) {
+      if (data[i] > 0) {
+        rows.add(Math.floor((i - 3) / 4 / 512 / 30))
+      }
+    }
+    return rows.size
+  }
+  const lines = ctx.fillTextWrapped(sentence, 10, 20, 150, 30)
+  t.true(lines > 1)
+  t.is(rowsWithInk(), lines)
+})
+
+test('fillTextWrapped should break at newlines', (t) => {
+  const { ctx } = t.context
+  ctx.font = '20px Iosevka Slab'
+  t.is(ctx.fillTextWrapped('Hi\nthere', 10, 20, 500, 30), 2)
+  t.is(ctx.fillTextWrapped('Hi there', 10, 20, 500, 30), 1)
+})
+
+test('fillTextWrapped should align the lines in maxWidth', (t) => {
+  const { ctx } = t.context
+  ctx.font = '20px Iosevka Slab'
+  ctx.textAlign = 'right'
+  ctx.fillTextWrapped('Hi', 10, 20, 200, 30)
+  const { data } = ctx.getImageData(0, 0, 512, 30)
+  let right = 0
+  for (let i = 3; i < data.length; i += 4) {
+    if (data[i] > 0) {
+      right = Math.max(right, ((i - 3) / 4) % 512)
+    }
+  }
+  t.true(right > 190 && right <= 210)
+})
+
+// width of the text if every code point was laid out on its own
+function naiveWidth(ctx: SKRSContext2D, text: string) {
+  return Array.from(text).reduce((width, codePoint) => width + ctx.measureText(codePoint).width, 0)
+}
+
+test('Devanagari cluster should be shaped as a single run', (t) => {
+  const { ctx } = t.context
+  const devanagari = GlobalFonts.families.find(({ family }) => family.includes('Devanagari'))
+  if (!devanagari) {
+    t.pass('no Devanagari font installed')
+    return
+  }
+  ctx.font = `40px "${devanagari.family}"`
+  // ka + virama + ssa + vowel sign i, the conjunct ksha with the i matra attached
+  const cluster = 'क्षि'
+  const { width } = ctx.measureText(cluster)
+  t.true(width > 0)
+  t.true(width < naiveWidth(ctx, cluster))
+})
+
+test('emoji ZWJ sequence should be shaped as a single glyph', (t) => {
+  const { ctx } = t.context
+  const emojiFont = join(__dirname, 'fonts', 'AppleColorEmoji@2x.ttf')
+  if (platform() !== 'darwin') {
+    if (!existsSync(emojiFont)) {
+      t.pass('no emoji font available')
+      return
+    }
+    GlobalFonts.registerFromPath(emojiFont)
+  }
+  ctx.font = '40px Apple Color Emoji'
+  const family = '👨‍👩‍👧‍👦'
+  const { width } = ctx.measureText(family)
+  t.true(width > 0)
+  t.true(Math.abs(width - ctx.measureText('👨').width) < 1)
+  t.true(width < naiveWidth(ctx, family) / 2)
+})
+
+test('direction should resolve textAlign start', (t) => {
+  const { ctx } = t.context
+  t.is(ctx.direction, 'inherit')
+  ctx.direction = 'rtl'
+  t.is(ctx.direction, 'rtl')
+  // @ts-expect-error
+  ctx.direction = 'up'
+  t.is(ctx.direction, 'rtl')
+  // `textDirection` is the old name
+  t.is(ctx.textDirection, 'rtl')
+  ctx.textDirection = 'ltr'
+  t.is(ctx.direction, 'ltr')
+  ctx.direction = 'rtl'
+
+  const inkBounds = (direction: CanvasDirection) => {
+    const canvas = createCanvas(500, 60)
+    const context = canvas.getContext('2d')
+    context.font = '30px Iosevka Slab'
+    context.direction = direction
+    context.textAlign = 'start'
+    context.fillText('مرحبا بالعالم', 250, 40)
+    const { data } = context.getImageData(0, 0, 500, 60)
+    let left = Infinity
+    let right = -Infinity
+    for (let i = 3; i < data.length; i += 4) {
+      if (data[i] > 0) {
+        const x = ((i - 3) / 4) % 500
+        left = Math.min(left, x)
+        right = Math.max(right, x)
+      }
+    }
+    return [left, right]
+  }
+  const [ltrLeft] = inkBounds('ltr')
+  const [rtlLeft, rtlRight] = inkBounds('rtl')
+  // `start` is the left edge for ltr and the right edge for rtl
+  t.true(ltrLeft >= 245)
+  t.true(rtlRight <= 255)
+  t.true(rtlLeft < ltrLeft)
+  t.deepEqual(inkBounds('inherit'), inkBounds('ltr'))
+})
+
+test('fontStretch', (t) => {
+  const { ctx } = t.context
+  t.is(ctx.fontStretch, 'normal')
+  ctx.fontStretch = 'condensed'
+  t.is(ctx.fontStretch, 'condensed')
+  // @ts-expect-error
+  ctx.fontStretch = '75%'
+  t.is(ctx.fontStretch, 'condensed')
+  ctx.font = 'expanded 20px serif'
+  t.is(ctx.fontStretch, 'expanded')
+})
+
+test('letterSpacing', (t) => {
+  const { ctx } = t.context
+  ctx.font = '50px Iosevka Slab'
+  const text = 'Hello'
+  const { width } = ctx.measureText(text)
+  ctx.letterSpacing = '5px'
+  t.is(ctx.letterSpacing, '5px')
+  const { width: widthWithSpacing } = ctx.measureText(text)
+  t.is(Math.round(widthWithSpacing - width), (text.length - 1) * 5)
+  ctx.letterSpacing = '-2px'
+  t.true(ctx.measureText(text).width < width)
+})
+
+test('letterSpacing and wordSpacing should reject invalid length', (t) => {
+  const { ctx } = t.context
+  t.is(ctx.letterSpacing, '0px')
+  t.is(ctx.wordSpacing, '0px')
+  t.throws(() => {
+    ctx.letterSpacing = 'foo'
+  })
+  t.is(ctx.letterSpacing, '0px')
+  ctx.wordSpacing = '1em'
+  t.is(ctx.wordSpacing, '1em')
+})
+
 // https://github.com/Brooooooklyn/canvas/issues/386
 test('text-baseline', async (t) => {
   const { ctx } = t.context
@@ -169,8 +169,9 @@ export const DOMPoint: {
 export class ImageData {
   /**
    * Returns the one-dimensional array containing the data in RGBA order, as integers in the range 0 to 255.
+   * `float16` ImageData is backed by a `Float32Array`, values are not clamped to the 0 to 1 range.
    */
-  readonly data: Uint8ClampedArray
+  readonly data: Uint8ClampedArray | Float32Array
   /**
    * Returns the actual dimensions of the data in the ImageData object, in pixels.
    */
@@ -179,10 +180,30 @@ export class ImageData {
    * Returns the actual dimensions of the data in the ImageData object, in pixels.
    */
   readonly width: number
+  /**
+   * The color space of the pixels in `data`
+   */
+  readonly colorSpace: ColorSpace
+  /**
+   * The storage format of the pixels in `data`
+   */
+  readonly storageFormat: ImageDataStorageFormat
+
+  constructor(sw: number, sh: number, attr?: ImageDataSettings)
+  constructor(imageData: ImageData, attr?: ImageDataSettings)
+  constructor(data: Uint8ClampedArray | Float32Array, sw: number, sh?: number, attr?: ImageDataSettings)
+}
+
+export type ImageDataStorageFormat = 'uint8' | 'float16'
+
+export interface ImageDataSettings {
+  colorSpace?: ColorSpace
+  storageFormat?: ImageDataStorageFormat
+}
 
-  constructor(sw: number, sh: number, attr?: { colorSpace?: ColorSpace })
-  constructor(imageData: ImageData, attr?: { colorSpace?: ColorSpace })
-  constructor(data: Uint8ClampedArray, sw: number, sh?: number)
+export interface ImageFrame {
+  // milliseconds, `0` for still images
+  duration: number
 }
 
 export class Image {
@@ -194,10 +215,18 @@ export class Image {
   readonly naturalWidth: number
   readonly naturalHeight: number
   readonly complete: boolean
+  // still images have a single frame
+  readonly frames: ImageFrame[]
+  // EXIF orientation of the source, `1` when it has none
+  readonly orientation: number
+  // set before `src`, `none` keeps the stored pixels instead of rotating them upright
+  imageOrientation: 'from-image' | 'none'
   alt: string
   src: Buffer
   onload?(): void
   onerror?(err: Error): void
+  // resolves once `src` is decoded, rejects if it can't be decoded, waits for `src` if it's not assigned yet
+  decode(): Promise<void>
 }
 
 export class Path2D {
@@ -222,11 +251,17 @@ export class Path2D {
   moveTo(x: number, y: number): void
   quadraticCurveTo(cpx: number, cpy: number, x: number, y: number): void
   rect(x: number, y: number, w: number, h: number): void
-  roundRect(x: number, y: number, w: number, h: number, radii?: number | number[]): void
+  roundRect(
+    x: number,
+    y: number,
+    w: number,
+    h: number,
+    radii?: number | DOMPointInit | (number | DOMPointInit)[],
+  ): void
 
   // PathKit methods
   op(path: Path2D, operation: PathOp): Path2D
-  toSVGString(): string
+  toSVGString(options?: SvgStringOptions): string
   getFillType(): FillType
   getFillTypeString(): string
   setFillType(type: FillType): void
@@ -239,6 +274,14 @@ export class Path2D {
   trim(start: number, end: number, isComplement?: boolean): Path2D
   dash(on: number, off: number, phase: number): Path2D
   equals(path: Path2D): boolean
+  // `stroke` defaults to a 1px wide stroke with `butt` cap, `miter` join and miter limit 4
+  isPointInPath(x: number, y: number, fillType?: FillType): boolean
+  isPointInStroke(x: number, y: number, stroke?: StrokeOptions): boolean
+}
+
+export interface SvgStringOptions {
+  // encode the commands relative to the current point, default is `false`
+  relative?: boolean
 }
 
 export interface StrokeOptions {
@@ -248,10 +291,29 @@ export interface StrokeOptions {
   join?: StrokeJoin
 }
 
+export type GradientInterpolation = 'srgb' | 'oklab'
+
+export interface SKRSCanvasGradient extends CanvasGradient {
+  // `hint` is the position of the color midpoint between this stop and the one before it
+  addColorStop(offset: number, color: string, hint?: number): void
+  // the color space the colors between the stops are interpolated in, `srgb` by default
+  interpolation: GradientInterpolation
+}
+
 export interface SKRSContext2D
   extends Omit<
     CanvasRenderingContext2D,
-    'drawImage' | 'createPattern' | 'getTransform' | 'drawFocusIfNeeded' | 'scrollPathIntoView' | 'canvas'
+    | 'drawImage'
+    | 'createPattern'
+    | 'createLinearGradient'
+    | 'createRadialGradient'
+    | 'getTransform'
+    | 'drawFocusIfNeeded'
+    | 'scrollPathIntoView'
+    | 'canvas'
+    | 'createImageData'
+    | 'getImageData'
+    | 'putImageData'
   > {
   canvas: Canvas
   /**
@@ -259,11 +321,13 @@ export interface SKRSContext2D
    * @param x The x-axis coordinate of the centre of the gradient.
    * @param y The y-axis coordinate of the centre of the gradient.
    */
-  createConicGradient(startAngle: number, x: number, y: number): CanvasGradient
-  drawImage(image: Image | Canvas, dx: number, dy: number): void
-  drawImage(image: Image | Canvas, dx: number, dy: number, dw: number, dh: number): void
+  createConicGradient(startAngle: number, x: number, y: number): SKRSCanvasGradient
+  createLinearGradient(x0: number, y0: number, x1: number, y1: number): SKRSCanvasGradient
+  createRadialGradient(x0: number, y0: number, r0: number, x1: number, y1: number, r1: number): SKRSCanvasGradient
+  drawImage(image: Image | Canvas | OffscreenCanvas | ImageBitmap, dx: number, dy: number): void
+  drawImage(image: Image | Canvas | OffscreenCanvas | ImageBitmap, dx: number, dy: number, dw: number, dh: number): void
   drawImage(
-    image: Image | Canvas,
+    image: Image | Canvas | OffscreenCanvas | ImageBitmap,
     sx: number,
     sy: number,
     sw: number,
@@ -275,17 +339,82 @@ export interface SKRSContext2D
   ): void
   createPattern(
     image: Image | ImageData,
-    repeat: 'repeat' | 'repeat-x' | 'repeat-y' | 'no-repeat' | null,
+    // `null` and `''` are the same as `'repeat'`
+    repeat: 'repeat' | 'repeat-x' | 'repeat-y' | 'no-repeat' | '' | null,
   ): CanvasPattern
-  getContextAttributes(): { alpha: boolean; desynchronized: boolean }
+  // `false` lets `brightness()`, `contrast()` and `saturate()` go beyond the `[0, 1]` range on the `float16` canvases, default is `true`
+  clampFilters: boolean
+  /**
+   * @deprecated use `direction`
+   */
+  textDirection: CanvasDirection
+  // the effective `filter` serialized after parsing, the lengths in `px` and the colors in `rgba()`
+  readonly computedFilter: string
+  // `url(#id)` in `filter` references the filter registered with the `id`, `'none'` removes it
+  registerFilter(id: string, filter: string): void
+  // draw the image with a one-shot CSS `filter`, `ctx.filter` is ignored and left untouched
+  drawImageWithFilter(image: Image | Canvas | OffscreenCanvas | ImageBitmap, dx: number, dy: number, dw: number, dh: number, filter: string): void
+  // draw the frame at `frame` of an animated image, throw if the image doesn't have the frame
+  drawImageFrame(image: Image, frame: number, dx: number, dy: number, dw?: number, dh?: number): void
+  // wraps the text at the whitespaces into lines fitting `maxWidth`, `\n` forces a break
+  // `x` is the left edge of the `maxWidth` box, `textAlign` aligns the lines in it
+  // returns the number of lines
+  fillTextWrapped(text: string, x: number, y: number, maxWidth: number, lineHeight: number): number
+  createImageData(sw: number, sh: number, settings?: ImageDataSettings): ImageData
+  createImageData(imageData: ImageData): ImageData
+  getImageData(sx: number, sy: number, sw: number, sh: number, settings?: ImageDataSettings): ImageData
+  putImageData(imageData: ImageData, dx: number, dy: number): void
+  putImageData(
+    imageData: ImageData,
+    dx: number,
+    dy: number,
+    dirtyX: number,
+    dirtyY: number,
+    dirtyWidth: number,
+    dirtyHeight: number,
+  ): void
+  getContextAttributes(): {
+    alpha: boolean
+    colorSpace: ColorSpace
+    colorType: ContextColorType
+    desynchronized: boolean
+  }
   getTransform(): DOMMatrix
+  // clear the bitmap, the current path and the saved states, and restore every property to its default value
+  reset(): void
+  // `true` when the canvas is too large to be allocated, the drawing is discarded and the encoding throws
+  isContextLost(): boolean
+  // `none` draws with hard edges, `gray` and `subpixel` are the same as `default`
+  antialias: 'default' | 'none' | 'gray' | 'subpixel'
+  // `none` disables the kerning of the font, `normal` forces it on
+  fontKerning: 'auto' | 'normal' | 'none'
+  // hinting and antialiasing of the glyphs, the layout is the same for every value
+  textRendering: 'auto' | 'optimizeSpeed' | 'optimizeLegibility' | 'geometricPrecision'
+  fontStretch:
+    | 'ultra-condensed'
+    | 'extra-condensed'
+    | 'condensed'
+    | 'semi-condensed'
+    | 'normal'
+    | 'semi-expanded'
+    | 'expanded'
+    | 'extra-expanded'
+    | 'ultra-expanded'
+  // CSS `<length>`, e.g. `2px` or `0.1em`, applied between characters in `fillText`/`strokeText`/`measureText`
+  letterSpacing: string
+  // CSS `<length>` applied between words in `fillText`/`strokeText`/`measureText`
+  wordSpacing: string
 }
 
 export type ColorSpace = 'srgb' | 'display-p3'
 
+// `float16` backs the canvas with a half float surface, so pixels keep values outside of the 0 to 1 range
+export type ContextColorType = 'unorm8' | 'float16'
+
 export interface ContextAttributes {
   alpha?: boolean
   colorSpace?: ColorSpace
+  colorType?: ContextColorType
 }
 
 export interface SvgCanvas {
@@ -294,6 +423,20 @@ export interface SvgCanvas {
   getContext(contextType: '2d', contextAttributes?: ContextAttributes): SKRSContext2D
 
   getContent(): Buffer
+  // the serialized SVG document, same as `getContent()`
+  toBuffer(mime: 'image/svg+xml'): Buffer
+}
+
+export interface PngConfig {
+  /** zlib compression level, 0-9 scale, default is 6 */
+  compressionLevel?: number
+  /** row filters tried by the encoder, default is 'all' */
+  filters?: 'none' | 'sub' | 'up' | 'avg' | 'paeth' | 'all'
+}
+
+export interface RawConfig {
+  /** premultiply the color channels by alpha, default is `false` */
+  premultiplied?: boolean
 }
 
 export interface AvifConfig {
@@ -356,17 +499,36 @@ export class Canvas {
 
   width: number
   height: number
+  /**
+   * The filter applied to every drawing when `ctx.filter` is `none`, default is `none`
+   */
+  defaultFilter: string
+  /**
+   * Apply `ctx.filter` on top of `defaultFilter` instead of replacing it, default is `false`
+   */
+  composeWithDefault: boolean
   getContext(contextType: '2d', contextAttributes?: ContextAttributes): SKRSContext2D
+  /**
+   * Resize the canvas and reset the context state, the drawing is cleared unless `preserveContent` is `true`
+   */
+  resize(width: number, height: number, options?: { preserveContent?: boolean }): void
   encodeSync(format: 'webp' | 'jpeg', quality?: number): Buffer
-  encodeSync(format: 'png'): Buffer
+  encodeSync(format: 'png', cfg?: PngConfig): Buffer
   encodeSync(format: 'avif', cfg?: AvifConfig): Buffer
   encode(format: 'webp' | 'jpeg', quality?: number): Promise<Buffer>
-  encode(format: 'png'): Promise<Buffer>
+  encode(format: 'png', cfg?: PngConfig): Promise<Buffer>
   encode(format: 'avif', cfg?: AvifConfig): Promise<Buffer>
 
-  toBuffer(mime: 'image/png'): Buffer
+  toBuffer(mime: 'image/png', cfg?: PngConfig): Buffer
+  /**
+   * `quality` is in 0-100 scale, `image/webp` with quality 100 is encoded losslessly
+   */
   toBuffer(mime: 'image/jpeg' | 'image/webp', quality?: number): Buffer
   toBuffer(mime: 'image/avif', cfg?: AvifConfig): Buffer
+  /**
+   * RGBA pixels in the color space of the canvas, 4 bytes per pixel, the rows are tightly packed with a stride of `width * 4` bytes
+   */
+  toBuffer(mime: 'raw', cfg?: RawConfig): Buffer
   // raw pixels
   data(): Buffer
   toDataURL(mime?: 'image/png'): string
@@ -378,12 +540,89 @@ export class Canvas {
   toDataURLAsync(mime: 'image/jpeg' | 'image/webp', quality?: number): Promise<string>
   toDataURLAsync(mime?: 'image/jpeg' | 'image/webp' | 'image/png', quality?: number): Promise<string>
   toDataURLAsync(mime?: 'image/avif', cfg?: AvifConfig): Promise<string>
+
+  /**
+   * Encode the canvas as png chunk by chunk instead of building the whole buffer in memory,
+   * the stream encodes the canvas as it is when `createPNGStream` is called
+   */
+  createPNGStream(cfg?: PngConfig): import('stream').Readable
+}
+
+export class ImageBitmap {
+  // `0` once closed
+  readonly width: number
+  readonly height: number
+  // release the pixels, a closed bitmap draws nothing
+  close(): void
+}
+
+export interface ImageBitmapOptions {
+  resizeWidth?: number
+  resizeHeight?: number
+  // default is `low`
+  resizeQuality?: 'pixelated' | 'low' | 'medium' | 'high'
+  // default is `from-image`
+  imageOrientation?: 'from-image' | 'flipY' | 'none'
+}
+
+export type ImageBitmapSource = Image | Canvas | OffscreenCanvas | ImageBitmap | ImageData
+
+// the parts of the crop rect outside of the source are transparent
+export function createImageBitmap(image: ImageBitmapSource, options?: ImageBitmapOptions): Promise<ImageBitmap>
+export function createImageBitmap(
+  image: ImageBitmapSource,
+  sx: number,
+  sy: number,
+  sw: number,
+  sh: number,
+  options?: ImageBitmapOptions,
+): Promise<ImageBitmap>
+
+export interface ImageEncodeOptions {
+  // `image/png` if the type has no encoder
+  type?: 'image/png' | 'image/jpeg' | 'image/webp' | 'image/avif'
+  // 0-1 scale, for `image/jpeg` and `image/webp`
+  quality?: number
+}
+
+// a canvas without any display, for rendering in workers
+export class OffscreenCanvas {
+  constructor(width: number, height: number)
+
+  width: number
+  height: number
+  getContext(contextType: '2d', contextAttributes?: ContextAttributes): SKRSContext2D
+  encode(format: 'webp' | 'jpeg', quality?: number): Promise<Buffer>
+  encode(format: 'png', cfg?: PngConfig): Promise<Buffer>
+  encode(format: 'avif', cfg?: AvifConfig): Promise<Buffer>
+  convertToBlob(options?: ImageEncodeOptions): Promise<Blob>
+  // move the pixels into an `ImageBitmap`, the canvas is left transparent
+  transferToImageBitmap(): ImageBitmap
+}
+
+export class PdfDocument {
+  constructor()
+  readonly pageCount: number
+  // ends the current page if any, the returned context draws into the new page
+  beginPage(width: number, height: number): SKRSContext2D
+  endPage(): void
+  // finishes the document, no page can be added afterwards
+  toBuffer(mime?: 'application/pdf'): Buffer
 }
 
 export function createCanvas(width: number, height: number): Canvas
 
+// `svgExportFlag` can combine flags with `|`, `0` keeps text as `<text>` elements
 export function createCanvas(width: number, height: number, svgExportFlag: SvgExportFlag): SvgCanvas
 
+export interface FontDescriptor {
+  family: string
+  /** defaults to the weight in the font file */
+  weight?: number | 'normal' | 'bold'
+  /** defaults to the style in the font file */
+  style?: 'normal' | 'italic' | 'oblique'
+}
+
 interface IGlobalFonts {
   readonly families: {
     family: string
@@ -395,10 +634,14 @@ interface IGlobalFonts {
   }[]
   // return true if succeeded
   register(font: Buffer, nameAlias?: string): boolean
-  // absolute path
+  // the font is matched by the weight and style of the descriptor instead of the ones in the font file
+  register(font: Buffer, descriptor: FontDescriptor): boolean
+  // absolute path, throw if the font file could not be read
   registerFromPath(path: string, nameAlias?: string): boolean
   has(name: string): boolean
   loadFontsFromDir(path: string): number
+  // drop every registered font, text falls back to the system fonts
+  deregisterAllFonts(): void
 }
 
 export const GlobalFonts: IGlobalFonts
@@ -436,7 +679,20 @@ export enum SvgExportFlag {
   RelativePathEncoding = 0x04,
 }
 
-export function convertSVGTextToPath(svg: Buffer | string): Buffer
+export interface SvgTextToPathOptions {
+  /**
+   * Convert all of the text to paths, default is `true`.
+   * If `false`, the text is kept as `<text>` elements.
+   */
+  convertAll?: boolean
+  /**
+   * Embedding fonts is not supported yet, `true` throws
+   */
+  embedFonts?: boolean
+}
+
+export function convertSVGTextToPath(svg: Buffer | string, options?: SvgTextToPathOptions): Buffer
+export function convertSVGTextToPathAsync(svg: Buffer | string, options?: SvgTextToPathOptions): Promise<Buffer>
 
 export interface LoadImageOptions {
   alt?: string
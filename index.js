@@ -1,11 +1,16 @@
 const { platform, homedir } = require('os')
 const { join } = require('path')
+const { Readable } = require('stream')
+const { Blob } = require('buffer')
 
 const {
   clearAllCache,
   CanvasRenderingContext2D,
   CanvasElement,
   SVGCanvas,
+  PdfDocument,
+  OffscreenCanvas,
+  ImageBitmap,
   Path: Path2D,
   ImageData,
   Image,
@@ -16,6 +21,8 @@ const {
   StrokeJoin,
   StrokeCap,
   convertSVGTextToPath,
+  convertSVGTextToPathAsync,
+  createImageBitmapAsync,
 } = require('./js-binding')
 
 const { DOMPoint, DOMMatrix, DOMRect } = require('./geometry')
@@ -77,6 +84,63 @@ class Canvas {
   }
 }
 
+// encodes a snapshot taken when the stream is created, drawing on the canvas afterwards,
+// including from a `data` listener, doesn't change the streamed image
+class PNGStream extends Readable {
+  constructor(canvas, options) {
+    super()
+    try {
+      this.encoder = canvas.pngStreamEncoder(options)
+    } catch (e) {
+      // report it on the next tick so the consumer can attach its listeners first
+      process.nextTick(() => this.destroy(e))
+    }
+  }
+
+  _read() {
+    if (!this.encoder) {
+      return
+    }
+    try {
+      let chunk
+      // stop once the consumer is full, `_read` is called again when it drains
+      do {
+        chunk = this.encoder.next()
+      } while (this.push(chunk) && chunk !== null)
+    } catch (e) {
+      this.destroy(e)
+    }
+  }
+}
+
+CanvasElement.prototype.createPNGStream = function createPNGStream(options) {
+  return new PNGStream(this, options)
+}
+
+const BLOB_FORMATS = {
+  'image/png': 'png',
+  'image/jpeg': 'jpeg',
+  'image/webp': 'webp',
+  'image/avif': 'avif',
+}
+
+OffscreenCanvas.prototype.convertToBlob = async function convertToBlob(options = {}) {
+  // the types without an encoder fall back to png, as the browsers do
+  const type = BLOB_FORMATS[options.type] ? options.type : 'image/png'
+  const format = BLOB_FORMATS[type]
+  const quality =
+    typeof options.quality === 'number' && !Number.isNaN(options.quality)
+      ? Math.round(Math.min(Math.max(options.quality, 0), 1) * 100)
+      : undefined
+  const data = await this.encode(format, format === 'png' || format === 'avif' ? undefined : quality)
+  return new Blob([data], { type })
+}
+
+// the invalid arguments throw synchronously in `createImageBitmapAsync`, they reject the Promise instead
+async function createImageBitmap(...args) {
+  return createImageBitmapAsync(...args)
+}
+
 if (!process.env.DISABLE_SYSTEM_FONTS_LOAD) {
   GlobalFonts.loadSystemFonts()
   const platformName = platform()
@@ -107,10 +171,15 @@ module.exports = {
   StrokeCap,
   StrokeJoin,
   SvgExportFlag,
+  PdfDocument,
+  OffscreenCanvas,
+  ImageBitmap,
   GlobalFonts: GlobalFonts,
   convertSVGTextToPath,
+  convertSVGTextToPathAsync,
   DOMPoint,
   DOMMatrix,
   DOMRect,
   loadImage,
+  createImageBitmap,
 }
@@ -232,8 +232,14 @@ const {
   Path,
   CanvasPattern,
   convertSVGTextToPath,
+  convertSVGTextToPathAsync,
   CanvasElement,
   SVGCanvas,
+  PdfDocument,
+  OffscreenCanvas,
+  ImageBitmap,
+  createImageBitmapSync,
+  createImageBitmapAsync,
   clearAllCache,
   GlobalFonts,
 } = nativeBinding
@@ -251,7 +257,13 @@ module.exports.StrokeJoin = StrokeJoin
 module.exports.Path = Path
 module.exports.CanvasPattern = CanvasPattern
 module.exports.convertSVGTextToPath = convertSVGTextToPath
+module.exports.convertSVGTextToPathAsync = convertSVGTextToPathAsync
 module.exports.CanvasElement = CanvasElement
 module.exports.SVGCanvas = SVGCanvas
+module.exports.PdfDocument = PdfDocument
+module.exports.OffscreenCanvas = OffscreenCanvas
+module.exports.ImageBitmap = ImageBitmap
+module.exports.createImageBitmapSync = createImageBitmapSync
+module.exports.createImageBitmapAsync = createImageBitmapAsync
 module.exports.clearAllCache = clearAllCache
 module.exports.GlobalFonts = GlobalFonts
@@ -87,12 +87,12 @@ extern "C"
 
   // Surface
 
-  static SkSurface *skiac_surface_create(int width, int height, SkAlphaType alphaType, uint8_t cs)
+  static SkSurface *skiac_surface_create(int width, int height, SkAlphaType alphaType, uint8_t cs, SkColorType color_type = kRGBA_8888_SkColorType)
   {
     // Init() is idempotent, so can be called more than once with no adverse effect.
     SkGraphics::Init();
     auto color_space = COLOR_SPACE_CAST;
-    auto info = SkImageInfo::Make(width, height, kRGBA_8888_SkColorType, alphaType, color_space);
+    auto info = SkImageInfo::Make(width, height, color_type, alphaType, color_space);
     auto surface = SkSurface::MakeRaster(info);
 
     if (surface)
@@ -137,6 +137,28 @@ extern "C"
         skiac_surface_create(width, height, kUnpremul_SkAlphaType, cs));
   }
 
+  skiac_surface *skiac_surface_create_opaque(int width, int height, uint8_t cs)
+  {
+    auto surface = skiac_surface_create(width, height, kOpaque_SkAlphaType, cs);
+    if (surface)
+    {
+      // opaque surfaces start out as opaque black
+      surface->getCanvas()->clear(SK_ColorBLACK);
+    }
+    return reinterpret_cast<skiac_surface *>(surface);
+  }
+
+  skiac_surface *skiac_surface_create_rgba_f16(int width, int height, bool opaque, uint8_t cs)
+  {
+    auto alpha_type = opaque ? kOpaque_SkAlphaType : kPremul_SkAlphaType;
+    auto surface = skiac_surface_create(width, height, alpha_type, cs, kRGBA_F16_SkColorType);
+    if (surface && opaque)
+    {
+      surface->getCanvas()->clear(SK_ColorBLACK);
+    }
+    return reinterpret_cast<skiac_surface *>(surface);
+  }
+
   bool skiac_surface_save(skiac_surface *c_surface, const char *path)
   {
     auto image = SURFACE_CAST->makeImageSnapshot();
@@ -209,18 +231,32 @@ extern "C"
     }
   }
 
-  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs)
+  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs, bool f32, bool premultiplied)
   {
     auto color_space = COLOR_SPACE_CAST;
-    auto image_info = SkImageInfo::Make(w, h, SkColorType::kRGBA_8888_SkColorType, SkAlphaType::kUnpremul_SkAlphaType, color_space);
-    auto result = SURFACE_CAST->readPixels(image_info, data, w * 4, x, y);
+    auto color_type = f32 ? SkColorType::kRGBA_F32_SkColorType : SkColorType::kRGBA_8888_SkColorType;
+    auto alpha_type = premultiplied ? SkAlphaType::kPremul_SkAlphaType : SkAlphaType::kUnpremul_SkAlphaType;
+    auto image_info = SkImageInfo::Make(w, h, color_type, alpha_type, color_space);
+    auto result = SURFACE_CAST->readPixels(image_info, data, image_info.minRowBytes(), x, y);
     return result;
   }
 
-  void skiac_surface_png_data(skiac_surface *c_surface, skiac_sk_data *data)
+  void skiac_surface_png_data(skiac_surface *c_surface, skiac_sk_data *data, int compression_level, int filter_flags)
   {
-    auto image = SURFACE_CAST->makeImageSnapshot();
-    auto png_data = image->encodeToData().release();
+    SkPixmap pixmap;
+    if (!SURFACE_CAST->peekPixels(&pixmap))
+    {
+      return;
+    }
+    SkPngEncoder::Options options;
+    options.fZLibLevel = compression_level;
+    options.fFilterFlags = static_cast<SkPngEncoder::FilterFlag>(filter_flags);
+    SkDynamicMemoryWStream stream;
+    if (!SkPngEncoder::Encode(&stream, pixmap, options))
+    {
+      return;
+    }
+    auto png_data = stream.detachAsData().release();
     if (png_data)
     {
       data->ptr = png_data->bytes();
@@ -229,6 +265,66 @@ extern "C"
     }
   }
 
+  struct skiac_png_stream
+  {
+    SkBitmap bitmap;
+    SkDynamicMemoryWStream stream;
+    // declared last so it is destroyed before the stream and the pixels it writes from
+    std::unique_ptr<SkEncoder> encoder;
+    int rows_left;
+  };
+
+  skiac_png_stream *skiac_surface_png_stream_create(skiac_surface *c_surface, int compression_level, int filter_flags)
+  {
+    auto c_png_stream = new skiac_png_stream();
+    // encode a snapshot, drawing on the canvas while the stream is read doesn't change the output
+    auto image = SURFACE_CAST->makeImageSnapshot();
+    if (!image || !c_png_stream->bitmap.tryAllocPixels(image->imageInfo()) || !image->readPixels(c_png_stream->bitmap.pixmap(), 0, 0))
+    {
+      delete c_png_stream;
+      return nullptr;
+    }
+    SkPngEncoder::Options options;
+    options.fZLibLevel = compression_level;
+    options.fFilterFlags = static_cast<SkPngEncoder::FilterFlag>(filter_flags);
+    c_png_stream->encoder = SkPngEncoder::Make(&c_png_stream->stream, c_png_stream->bitmap.pixmap(), options);
+    if (!c_png_stream->encoder)
+    {
+      delete c_png_stream;
+      return nullptr;
+    }
+    c_png_stream->rows_left = c_png_stream->bitmap.height();
+    return c_png_stream;
+  }
+
+  bool skiac_png_stream_encode_rows(skiac_png_stream *c_png_stream, int rows, skiac_sk_data *data)
+  {
+    rows = std::min(rows, c_png_stream->rows_left);
+    if (rows > 0)
+    {
+      if (!c_png_stream->encoder->encodeRows(rows))
+      {
+        return false;
+      }
+      c_png_stream->rows_left -= rows;
+    }
+    auto d = c_png_stream->stream.detachAsData().release();
+    data->data = reinterpret_cast<skiac_data *>(d);
+    data->size = d->size();
+    data->ptr = d->bytes();
+    return true;
+  }
+
+  int skiac_png_stream_rows_left(skiac_png_stream *c_png_stream)
+  {
+    return c_png_stream->rows_left;
+  }
+
+  void skiac_png_stream_destroy(skiac_png_stream *c_png_stream)
+  {
+    delete c_png_stream;
+  }
+
   void skiac_surface_encode_data(skiac_surface *c_surface, skiac_sk_data *data, int format, int quality)
   {
     auto image = SURFACE_CAST->makeImageSnapshot();
@@ -327,7 +423,8 @@ extern "C"
     auto fq = enable_smoothing ? filter_quality : 0;
     const auto sampling = SamplingOptionsFromFQ(fq);
     auto paint = reinterpret_cast<const SkPaint *>(c_paint);
-    CANVAS_CAST->drawImageRect(sk_image, src_rect, dst_rect, sampling, paint, SkCanvas::kFast_SrcRectConstraint);
+    // strict keeps the sampling of a fractional source rect from bleeding the pixels around it
+    CANVAS_CAST->drawImageRect(sk_image, src_rect, dst_rect, sampling, paint, SkCanvas::kStrict_SrcRectConstraint);
   }
 
   void skiac_canvas_draw_path(skiac_canvas *c_canvas, skiac_path *c_path, skiac_paint *c_paint)
@@ -392,7 +489,12 @@ extern "C"
       int weight,
       int stretch,
       int slant,
+      int variant,
+      int kerning,
+      int text_rendering,
       const char *font_family,
+      float letter_spacing,
+      float word_spacing,
       int baseline,
       int align,
       int direction,
@@ -413,9 +515,19 @@ extern "C"
     }
     text_style.setFontFamilies(families_vec);
     text_style.setFontSize(font_size);
-    text_style.setWordSpacing(0);
+    text_style.setWordSpacing(word_spacing);
+    text_style.setLetterSpacing(letter_spacing);
     text_style.setHeight(1);
     text_style.setFontStyle(font_style);
+    if (variant == 1)
+    {
+      text_style.addFontFeature(SkString("smcp"), 1);
+    }
+    // 0 is `auto`, leave it to the shaper
+    if (kerning > 0)
+    {
+      text_style.addFontFeature(SkString("kern"), kerning == 1 ? 1 : 0);
+    }
     text_style.setForegroundColor(*PAINT_CAST);
     text_style.setTextBaseline(TextBaseline::kAlphabetic);
 
@@ -433,10 +545,11 @@ extern "C"
     auto font = run.font();
     SkFontMetrics font_metrics;
     font.getMetrics(&font_metrics);
-    SkRect bounds[text_len];
     auto glyphs = run.glyphs();
     auto glyphs_size = glyphs.size();
-    font.getBounds(glyphs.data(), text_len, &bounds[0], nullptr);
+    // one bounds per glyph, `text_len` is the UTF-8 length and is larger for non ASCII text
+    std::vector<SkRect> bounds(glyphs_size);
+    font.getBounds(glyphs.data(), glyphs_size, bounds.data(), nullptr);
     auto text_box = paragraph->getRectsForRange(0, text_len, RectHeightStyle::kTight, RectWidthStyle::kTight);
     // line_metrics.fWidth doesn't contain the suffix spaces
     // run.calculateWidth will return 0 if font is rendering as fallback
@@ -540,6 +653,36 @@ extern "C"
 
     if (c_canvas)
     {
+      // 0 is `auto`, keep the antialiased fonts picked by the shaper
+      if (text_rendering > 0)
+      {
+        for (auto &text_run : paragraph->runs())
+        {
+          auto &run_font = const_cast<SkFont &>(text_run.font());
+          switch (text_rendering)
+          {
+          // optimizeSpeed
+          case 1:
+            run_font.setEdging(SkFont::Edging::kAlias);
+            run_font.setHinting(SkFontHinting::kNone);
+            run_font.setSubpixel(false);
+            break;
+          // optimizeLegibility
+          case 2:
+            run_font.setEdging(SkFont::Edging::kAntiAlias);
+            run_font.setHinting(SkFontHinting::kFull);
+            run_font.setSubpixel(false);
+            break;
+          // geometricPrecision
+          default:
+            run_font.setEdging(SkFont::Edging::kAntiAlias);
+            run_font.setHinting(SkFontHinting::kNone);
+            run_font.setSubpixel(true);
+            run_font.setLinearMetrics(true);
+            break;
+          }
+        }
+      }
       auto need_scale = line_width > max_width;
       float ratio = need_scale ? max_width / line_width : 1.0;
       if (need_scale)
@@ -565,6 +708,10 @@ extern "C"
       c_line_metrics->font_ascent = -font_metrics.fAscent + offset;
       c_line_metrics->font_descent = font_metrics.fDescent - offset;
       c_line_metrics->alphabetic_baseline = -font_metrics.fAscent + offset;
+      // the em square is the ascent and descent normalized to the font size
+      auto em_height_ascent = font.getSize() * -font_metrics.fAscent / (-font_metrics.fAscent + font_metrics.fDescent);
+      c_line_metrics->em_height_ascent = em_height_ascent + offset;
+      c_line_metrics->em_height_descent = font.getSize() - em_height_ascent - offset;
     }
     delete paragraph;
   }
@@ -601,22 +748,22 @@ extern "C"
     CANVAS_CAST->restoreToCount(1);
   }
 
-  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y)
+  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y, uint8_t cs, bool f32)
   {
-    auto info = SkImageInfo::Make(width, height, SkColorType::kRGBA_8888_SkColorType, SkAlphaType::kUnpremul_SkAlphaType);
+    auto color_space = COLOR_SPACE_CAST;
+    auto color_type = f32 ? SkColorType::kRGBA_F32_SkColorType : SkColorType::kRGBA_8888_SkColorType;
+    auto info = SkImageInfo::Make(width, height, color_type, SkAlphaType::kUnpremul_SkAlphaType, color_space);
     CANVAS_CAST->writePixels(info, pixels, row_bytes, x, y);
   }
 
-  void skiac_canvas_write_pixels_dirty(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, size_t length, float x, float y, float dirty_x, float dirty_y, float dirty_width, float dirty_height, uint8_t cs)
+  void skiac_canvas_write_pixels_dirty(skiac_canvas *c_canvas, uint8_t *pixels, size_t row_bytes, int x, int y, int dirty_x, int dirty_y, int dirty_width, int dirty_height, uint8_t cs, bool f32)
   {
     auto color_space = COLOR_SPACE_CAST;
-    auto info = SkImageInfo::Make(width, height, SkColorType::kRGBA_8888_SkColorType, SkAlphaType::kUnpremul_SkAlphaType, color_space);
-    auto data = SkData::MakeFromMalloc(pixels, length);
-    auto image = SkImage::MakeRasterData(info, data, row_bytes);
-    auto src_rect = SkRect::MakeXYWH(dirty_x, dirty_y, dirty_width, dirty_height);
-    auto dst_rect = SkRect::MakeXYWH(x + dirty_x, y + dirty_y, dirty_width, dirty_height);
-    const auto sampling = SkSamplingOptions(SkCubicResampler::Mitchell());
-    CANVAS_CAST->drawImageRect(image, src_rect, dst_rect, sampling, nullptr, SkCanvas::kFast_SrcRectConstraint);
+    auto color_type = f32 ? SkColorType::kRGBA_F32_SkColorType : SkColorType::kRGBA_8888_SkColorType;
+    // only the dirty rect of the source pixels is copied, the pixels are owned by the JavaScript ImageData
+    auto info = SkImageInfo::Make(dirty_width, dirty_height, color_type, SkAlphaType::kUnpremul_SkAlphaType, color_space);
+    auto dirty_pixels = pixels + dirty_y * row_bytes + dirty_x * info.bytesPerPixel();
+    CANVAS_CAST->writePixels(info, dirty_pixels, row_bytes, x + dirty_x, y + dirty_y);
   }
 
   // Paint
@@ -759,10 +906,14 @@ extern "C"
     return reinterpret_cast<skiac_path *>(new SkPath());
   }
 
-  skiac_path *skiac_path_from_svg(char *svg_path)
+  skiac_path *skiac_path_from_svg(const char *svg_path)
   {
     auto path = new SkPath();
-    SkParsePath::FromSVGString(svg_path, path);
+    if (!SkParsePath::FromSVGString(svg_path, path))
+    {
+      delete path;
+      return nullptr;
+    }
     return reinterpret_cast<skiac_path *>(path);
   }
 
@@ -781,7 +932,8 @@ extern "C"
   void skiac_add_path(skiac_path *c_path, skiac_path *other_path, skiac_matrix *c_matrix)
   {
     auto path = PATH_CAST;
-    path->addPath(*reinterpret_cast<SkPath *>(other_path), *MATRIX_CAST, SkPath::AddPathMode::kExtend_AddPathMode);
+    // the sub paths are appended as they are, `kExtend_AddPathMode` would connect them with a line
+    path->addPath(*reinterpret_cast<SkPath *>(other_path), *MATRIX_CAST, SkPath::AddPathMode::kAppend_AddPathMode);
   }
 
   bool skiac_path_op(skiac_path *c_path_one, skiac_path *c_path_two, int op)
@@ -790,9 +942,10 @@ extern "C"
     return Op(*path_one, *reinterpret_cast<SkPath *>(c_path_two), (SkPathOp)op, path_one);
   }
 
-  void skiac_path_to_svg_string(skiac_path *c_path, skiac_string *c_string)
+  void skiac_path_to_svg_string(skiac_path *c_path, bool relative, skiac_string *c_string)
   {
-    auto string = SkParsePath::ToSVGString(*PATH_CAST);
+    auto encoding = relative ? SkParsePath::PathEncoding::Relative : SkParsePath::PathEncoding::Absolute;
+    auto string = SkParsePath::ToSVGString(*PATH_CAST, encoding);
     auto length = string.size();
     auto result_string = new SkString(length);
     string.swap(*result_string);
@@ -972,13 +1125,16 @@ extern "C"
     return result;
   }
 
-  bool skiac_path_stroke_hit_test(skiac_path *c_path, float x, float y, float stroke_w)
+  bool skiac_path_stroke_hit_test(skiac_path *c_path, float x, float y, int cap, uint8_t join, float stroke_w, float miter_limit)
   {
     auto path = PATH_CAST;
     auto prev_fill = path->getFillType();
     path->setFillType(SkPathFillType::kWinding);
     SkPaint paint;
+    paint.setStrokeCap((SkPaint::Cap)cap);
+    paint.setStrokeJoin((SkPaint::Join)join);
     paint.setStrokeWidth(stroke_w);
+    paint.setStrokeMiter(miter_limit);
     paint.setStyle(SkPaint::kStroke_Style);
     SkPath traced_path;
 
@@ -1009,15 +1165,16 @@ extern "C"
       bool clockwise)
   {
     auto path = PATH_CAST;
-    SkScalar radii_vec[8];
+    // radii are the (x, y) pairs of the upper left, upper right, lower right and lower left corners
+    SkVector radii_vec[4];
     for (size_t i = 0; i < 4; i++)
     {
-      radii_vec[i * 2] = radii[i];
-      radii_vec[i * 2 + 1] = radii[i];
+      radii_vec[i].set(radii[i * 2], radii[i * 2 + 1]);
     }
-    SkRect rect = SkRect::MakeXYWH(x, y, width, height);
+    SkRRect rrect;
+    rrect.setRectRadii(SkRect::MakeXYWH(x, y, width, height), radii_vec);
     auto ccw = clockwise ? SkPathDirection::kCW : SkPathDirection::kCCW;
-    path->addRoundRect(rect, radii_vec, ccw);
+    path->addRRect(rrect, ccw);
   }
 
   // PathEffect
@@ -1116,7 +1273,7 @@ extern "C"
   skiac_shader *skiac_shader_make_conic_gradient(
       SkScalar cx,
       SkScalar cy,
-      SkScalar radius,
+      SkScalar start_angle,
       const uint32_t *colors,
       const float *positions,
       int count,
@@ -1125,8 +1282,9 @@ extern "C"
       skiac_transform c_ts)
   {
     auto ts = conv_from_transform(c_ts);
-    // Skia's sweep gradient angles are relative to the x-axis, not the y-axis.
-    ts.preRotate(radius - 90.0, cx, cy);
+    // Both of the canvas conic gradient and Skia's sweep gradient start from the x-axis and go clockwise,
+    // the start angle is in radians.
+    ts.preRotate(SkRadiansToDegrees(start_angle), cx, cy);
     auto shader = SkGradientShader::MakeSweep(
                       cx,
                       cy,
@@ -1134,7 +1292,7 @@ extern "C"
                       positions,
                       count,
                       (SkTileMode)tile_mode,
-                      radius,
+                      0.0,
                       360.0,
                       flags,
                       &ts)
@@ -1394,6 +1552,25 @@ extern "C"
     }
   }
 
+  skiac_image_filter *skiac_image_filter_color_matrix(const float matrix[20], bool clamp, skiac_image_filter *c_image_filter)
+  {
+    auto chained_filter = sk_sp(IMAGE_FILTER_CAST);
+    if (c_image_filter)
+    {
+      chained_filter->ref();
+    }
+    auto color_filter = SkColorFilters::Matrix(matrix, clamp ? SkColorFilters::Clamp::kYes : SkColorFilters::Clamp::kNo);
+    auto filter = SkImageFilters::ColorFilter(color_filter, chained_filter).release();
+    if (filter)
+    {
+      return reinterpret_cast<skiac_image_filter *>(filter);
+    }
+    else
+    {
+      return nullptr;
+    }
+  }
+
   skiac_image_filter *skiac_image_filter_from_argb(const uint8_t table_a[256], const uint8_t table_r[256], const uint8_t table_g[256], const uint8_t table_b[256], skiac_image_filter *c_image_filter)
   {
     auto cf = SkTableColorFilter::MakeARGB(table_a, table_r, table_g, table_b);
@@ -1413,6 +1590,13 @@ extern "C"
     }
   }
 
+  skiac_image_filter *skiac_image_filter_make_compose(skiac_image_filter *c_outer, skiac_image_filter *c_inner)
+  {
+    auto outer = sk_ref_sp(reinterpret_cast<SkImageFilter *>(c_outer));
+    auto inner = sk_ref_sp(reinterpret_cast<SkImageFilter *>(c_inner));
+    return reinterpret_cast<skiac_image_filter *>(SkImageFilters::Compose(outer, inner).release());
+  }
+
   void skiac_image_filter_ref(skiac_image_filter *c_image_filter)
   {
     auto image_filter = IMAGE_FILTER_CAST;
@@ -1435,20 +1619,88 @@ extern "C"
 
   // Bitmap
 
-  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, skiac_bitmap_info *bitmap_info)
+  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, bool apply_orientation, skiac_bitmap_info *bitmap_info)
+  {
+    skiac_bitmap_make_from_buffer_frame(ptr, size, 0, bitmap_info);
+    if (!apply_orientation || !bitmap_info->bitmap)
+    {
+      return;
+    }
+    auto origin = static_cast<SkEncodedOrigin>(skiac_codec_get_orientation(ptr, size));
+    if (origin == kTopLeft_SkEncodedOrigin)
+    {
+      return;
+    }
+    auto decoded = reinterpret_cast<SkBitmap *>(bitmap_info->bitmap);
+    auto width = decoded->width();
+    auto height = decoded->height();
+    auto info = SkEncodedOriginSwapsWidthHeight(origin) ? decoded->info().makeWH(height, width) : decoded->info();
+    auto bitmap = new SkBitmap();
+    bitmap->allocPixels(info);
+    bitmap->eraseColor(SK_ColorTRANSPARENT);
+    SkCanvas canvas(*bitmap);
+    canvas.concat(SkEncodedOriginToMatrix(origin, width, height));
+    SkPaint paint;
+    paint.setBlendMode(SkBlendMode::kSrc);
+    canvas.drawImage(decoded->asImage(), 0, 0, SkSamplingOptions(), &paint);
+    delete decoded;
+    bitmap_info->bitmap = reinterpret_cast<skiac_bitmap *>(bitmap);
+    bitmap_info->width = info.width();
+    bitmap_info->height = info.height();
+  }
+
+  int skiac_codec_get_orientation(const uint8_t *ptr, size_t size)
+  {
+    auto data = SkData::MakeWithoutCopy(reinterpret_cast<const void *>(ptr), size);
+    auto codec = SkCodec::MakeFromData(data);
+    return codec ? static_cast<int>(codec->getOrigin()) : static_cast<int>(kTopLeft_SkEncodedOrigin);
+  }
+
+  void skiac_bitmap_make_from_buffer_frame(const uint8_t *ptr, size_t size, int frame_index, skiac_bitmap_info *bitmap_info)
   {
     auto data = SkData::MakeWithoutCopy(reinterpret_cast<const void *>(ptr), size);
     auto codec = SkCodec::MakeFromData(data);
+    if (!codec || frame_index < 0 || frame_index >= codec->getFrameCount())
+    {
+      return;
+    }
     auto info = codec->getInfo();
     auto row_bytes = info.width() * info.bytesPerPixel();
     auto bitmap = new SkBitmap();
     bitmap->allocPixels(info);
-    codec->getPixels(info, bitmap->getPixels(), row_bytes);
+    // frames depending on prior frames are composed by the codec on top of a transparent canvas
+    bitmap->eraseColor(SK_ColorTRANSPARENT);
+    SkCodec::Options options;
+    options.fFrameIndex = frame_index;
+    auto result = codec->getPixels(info, bitmap->getPixels(), row_bytes, &options);
+    if (result != SkCodec::kSuccess && result != SkCodec::kIncompleteInput)
+    {
+      delete bitmap;
+      return;
+    }
     bitmap_info->bitmap = reinterpret_cast<skiac_bitmap *>(bitmap);
     bitmap_info->width = info.width();
     bitmap_info->height = info.height();
   }
 
+  int skiac_codec_get_frames(const uint8_t *ptr, size_t size, int *durations, int capacity)
+  {
+    auto data = SkData::MakeWithoutCopy(reinterpret_cast<const void *>(ptr), size);
+    auto codec = SkCodec::MakeFromData(data);
+    if (!codec)
+    {
+      return 0;
+    }
+    auto count = codec->getFrameCount();
+    for (int i = 0; i < count && i < capacity; i++)
+    {
+      SkCodec::FrameInfo frame_info;
+      // still images have no frame info
+      durations[i] = codec->getFrameInfo(i, &frame_info) ? frame_info.fDuration : 0;
+    }
+    return count;
+  }
+
   void skiac_bitmap_make_from_svg(const uint8_t *data, size_t length, float width, float height, skiac_bitmap_info *bitmap_info, uint8_t cs)
   {
     auto color_space = COLOR_SPACE_CAST;
@@ -1496,6 +1748,20 @@ extern "C"
     return reinterpret_cast<skiac_bitmap *>(bitmap);
   }
 
+  void skiac_bitmap_copy(skiac_bitmap *c_bitmap, skiac_bitmap_info *bitmap_info)
+  {
+    auto source = reinterpret_cast<SkBitmap *>(c_bitmap);
+    auto bitmap = new SkBitmap();
+    if (!bitmap->tryAllocPixels(source->info()) || !source->readPixels(bitmap->pixmap()))
+    {
+      delete bitmap;
+      return;
+    }
+    bitmap_info->bitmap = reinterpret_cast<skiac_bitmap *>(bitmap);
+    bitmap_info->width = bitmap->width();
+    bitmap_info->height = bitmap->height();
+  }
+
   size_t skiac_bitmap_get_width(skiac_bitmap *c_bitmap)
   {
     auto bitmap = reinterpret_cast<SkBitmap *>(c_bitmap);
@@ -1512,13 +1778,13 @@ extern "C"
       skiac_bitmap *c_bitmap,
       int repeat_x,
       int repeat_y,
-      float B,
-      float C, // See SkSamplingOptions.h for docs.
+      int filter_quality,
       skiac_transform c_ts)
   {
     const auto ts = conv_from_transform(c_ts);
     auto bitmap = reinterpret_cast<SkBitmap *>(c_bitmap);
-    auto shader = bitmap->makeShader((SkTileMode)repeat_x, (SkTileMode)repeat_y, SkSamplingOptions({B, C}), &ts).release();
+    const auto sampling = SamplingOptionsFromFQ(filter_quality);
+    auto shader = bitmap->makeShader((SkTileMode)repeat_x, (SkTileMode)repeat_y, sampling, &ts).release();
     if (shader)
     {
       return reinterpret_cast<skiac_shader *>(shader);
@@ -1570,8 +1836,13 @@ extern "C"
 
   size_t skiac_font_collection_register(skiac_font_collection *c_font_collection, const uint8_t *font, size_t length, const char *name_alias)
   {
-    auto typeface_data = SkData::MakeWithoutCopy(font, length);
+    // the typeface outlives the buffer it is read from
+    auto typeface_data = SkData::MakeWithCopy(font, length);
     auto typeface = c_font_collection->font_mgr->makeFromData(typeface_data);
+    if (!typeface)
+    {
+      return 0;
+    }
     auto result = c_font_collection->assets->registerTypeface(typeface);
     if (name_alias)
     {
@@ -1581,6 +1852,22 @@ extern "C"
     return result;
   }
 
+  size_t skiac_font_collection_register_styled(skiac_font_collection *c_font_collection, const uint8_t *font, size_t length, const char *family, int weight, int slant)
+  {
+    auto typeface = c_font_collection->font_mgr->makeFromData(SkData::MakeWithCopy(font, length));
+    if (!typeface)
+    {
+      return 0;
+    }
+    // a negative weight or slant keeps the one of the font file
+    auto font_style = typeface->fontStyle();
+    auto style = SkFontStyle(
+        weight < 0 ? font_style.weight() : weight,
+        font_style.width(),
+        slant < 0 ? font_style.slant() : static_cast<SkFontStyle::Slant>(slant));
+    return c_font_collection->assets->registerTypeface(typeface, SkString(family), style);
+  }
+
   size_t skiac_font_collection_register_from_path(skiac_font_collection *c_font_collection, const char *font_path, const char *name_alias)
   {
     auto typeface = c_font_collection->font_mgr->makeFromFile(font_path);
@@ -1628,20 +1915,76 @@ extern "C"
     delete reinterpret_cast<SkDynamicMemoryWStream *>(c_w_memory_stream);
   }
 
+  // SkPDF
+  struct skiac_pdf_document
+  {
+    SkDynamicMemoryWStream stream;
+    sk_sp<SkDocument> document;
+  };
+
+  skiac_pdf_document *skiac_pdf_document_create()
+  {
+    auto c_document = new skiac_pdf_document();
+    c_document->document = SkPDF::MakeDocument(&c_document->stream);
+    if (!c_document->document)
+    {
+      delete c_document;
+      return nullptr;
+    }
+    return c_document;
+  }
+
+  skiac_canvas *skiac_pdf_document_begin_page(skiac_pdf_document *c_document, float width, float height)
+  {
+    // the canvas is owned by the document and is valid until the page ends
+    return reinterpret_cast<skiac_canvas *>(c_document->document->beginPage(width, height));
+  }
+
+  void skiac_pdf_document_end_page(skiac_pdf_document *c_document)
+  {
+    c_document->document->endPage();
+  }
+
+  void skiac_pdf_document_close(skiac_pdf_document *c_document, skiac_sk_data *data)
+  {
+    c_document->document->close();
+    auto d = c_document->stream.detachAsData().release();
+    data->data = reinterpret_cast<skiac_data *>(d);
+    data->size = d->size();
+    data->ptr = d->bytes();
+  }
+
+  void skiac_pdf_document_destroy(skiac_pdf_document *c_document)
+  {
+    // no-op if the document is closed, otherwise drop the unfinished pages
+    c_document->document->abort();
+    c_document->document.reset();
+    delete c_document;
+  }
+
   // SkSVG
-  void skiac_svg_text_to_path(const uint8_t *data, size_t length, skiac_font_collection *c_collection, skiac_sk_data *output_data)
+  int skiac_svg_text_to_path(const uint8_t *data, size_t length, skiac_font_collection *c_collection, uint32_t flag, skiac_sk_data *output_data)
   {
-    auto svg_stream = new SkMemoryStream(data, length, false);
-    auto w_stream = new SkDynamicMemoryWStream();
-    auto svg_dom = SkSVGDOM::Builder().setFontManager(c_collection->assets).make(*svg_stream);
+    if (length == 0)
+    {
+      return 1;
+    }
+    SkMemoryStream svg_stream(data, length, false);
+    auto svg_dom = SkSVGDOM::Builder().setFontManager(c_collection->assets).make(svg_stream);
+    if (!svg_dom || !svg_dom->getRoot())
+    {
+      return 2;
+    }
     auto svg_root = svg_dom->getRoot();
     auto svg_container_size = svg_root->intrinsicSize(SkSVGLengthContext(SkSize::Make(0, 0)));
-    auto canvas = SkSVGCanvas::Make(SkRect::MakeSize(svg_container_size), w_stream, SkSVGCanvas::kConvertTextToPaths_Flag);
+    SkDynamicMemoryWStream w_stream;
+    auto canvas = SkSVGCanvas::Make(SkRect::MakeSize(svg_container_size), &w_stream, flag);
     svg_dom->render(canvas.get());
     canvas.reset();
-    auto d = w_stream->detachAsData().release();
+    auto d = w_stream.detachAsData().release();
     output_data->data = reinterpret_cast<skiac_data *>(d);
     output_data->size = d->size();
     output_data->ptr = d->bytes();
+    return 0;
   }
 }
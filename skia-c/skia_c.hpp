@@ -2,6 +2,7 @@
 #define SKIA_CAPI_H
 
 #include <include/codec/SkCodec.h>
+#include <include/codec/SkEncodedOrigin.h>
 #include <include/core/SkPicture.h>
 #include <include/core/SkSamplingOptions.h>
 #include <include/core/SkString.h>
@@ -31,6 +32,8 @@
 #include <include/effects/SkTableColorFilter.h>
 #include <include/effects/SkTrimPathEffect.h>
 #include <include/effects/SkGradientShader.h>
+#include <include/encode/SkPngEncoder.h>
+#include <include/docs/SkPDFDocument.h>
 #include <include/svg/SkSVGCanvas.h>
 #include <modules/skparagraph/include/FontCollection.h>
 #include <modules/skparagraph/include/Paragraph.h>
@@ -47,6 +50,9 @@
 #include <src/xml/SkXMLWriter.h>
 
 #include <stdint.h>
+#include <string>
+#include <unordered_map>
+#include <vector>
 
 using namespace skia::textlayout;
 
@@ -68,6 +74,8 @@ typedef struct skiac_typeface skiac_typeface;
 typedef struct skiac_font_mgr skiac_font_mgr;
 typedef struct skiac_typeface_font_provider skiac_typeface_font_provider;
 typedef struct skiac_w_memory_stream skiac_w_memory_stream;
+typedef struct skiac_pdf_document skiac_pdf_document;
+typedef struct skiac_png_stream skiac_png_stream;
 
 #if defined(WIN32) || defined(_WIN32) || defined(__WIN32__) || defined(__NT__)
 #define SK_FONT_FILE_PREFIX "C:/Windows/Fonts"
@@ -90,6 +98,46 @@ enum class CssBaseline
   Bottom,
 };
 
+// faces matched by the style they were registered with rather than the style in the font file
+class DeclaredFontStyleSet : public SkFontStyleSet
+{
+public:
+  int count() override
+  {
+    return static_cast<int>(faces.size());
+  }
+
+  void getStyle(int index, SkFontStyle *style, SkString *name) override
+  {
+    if (style)
+    {
+      *style = faces[index].first;
+    }
+    if (name)
+    {
+      name->reset();
+    }
+  }
+
+  SkTypeface *createTypeface(int index) override
+  {
+    return SkRef(faces[index].second.get());
+  }
+
+  SkTypeface *matchStyle(const SkFontStyle &pattern) override
+  {
+    return this->matchStyleCSS3(pattern);
+  }
+
+  void append(SkFontStyle style, sk_sp<SkTypeface> typeface)
+  {
+    faces.emplace_back(style, std::move(typeface));
+  }
+
+private:
+  std::vector<std::pair<SkFontStyle, sk_sp<SkTypeface>>> faces;
+};
+
 class TypefaceFontProviderCustom : public TypefaceFontProvider
 {
 public:
@@ -99,6 +147,31 @@ public:
 
   ~TypefaceFontProviderCustom(){};
 
+  size_t registerTypeface(sk_sp<SkTypeface> typeface, const SkString &family, SkFontStyle style)
+  {
+    auto result = TypefaceFontProvider::registerTypeface(typeface, family);
+    if (result)
+    {
+      auto &style_set = declared_families[family.c_str()];
+      if (!style_set)
+      {
+        style_set = sk_make_sp<DeclaredFontStyleSet>();
+      }
+      style_set->append(style, std::move(typeface));
+    }
+    return result;
+  }
+
+  SkFontStyleSet *onMatchFamily(const char family_name[]) const override
+  {
+    auto declared = declared_families.find(family_name);
+    if (declared != declared_families.end())
+    {
+      return SkRef(declared->second.get());
+    }
+    return TypefaceFontProvider::onMatchFamily(family_name);
+  }
+
   sk_sp<SkTypeface> onLegacyMakeTypeface(const char family_name[], SkFontStyle style) const override
   {
     auto style_set = this->onMatchFamily(family_name);
@@ -116,6 +189,7 @@ public:
 
 private:
   sk_sp<SkFontMgr> font_mgr;
+  std::unordered_map<std::string, sk_sp<DeclaredFontStyleSet>> declared_families;
 };
 
 struct skiac_svg_surface
@@ -150,6 +224,8 @@ struct skiac_line_metrics
   float font_ascent;
   float font_descent;
   float alphabetic_baseline;
+  float em_height_ascent;
+  float em_height_descent;
 };
 
 struct skiac_rect
@@ -218,6 +294,8 @@ extern "C"
   void skiac_clear_all_cache();
   // Surface
   skiac_surface *skiac_surface_create_rgba_premultiplied(int width, int height, uint8_t cs);
+  skiac_surface *skiac_surface_create_opaque(int width, int height, uint8_t cs);
+  skiac_surface *skiac_surface_create_rgba_f16(int width, int height, bool opaque, uint8_t cs);
   void skiac_surface_create_svg(skiac_svg_surface *c_surface, int width, int height, int alphaType, uint32_t flag, uint8_t cs);
   skiac_surface *skiac_surface_create_rgba(int width, int height, uint8_t cs);
   void skiac_surface_destroy(skiac_surface *c_surface);
@@ -232,8 +310,12 @@ extern "C"
   int skiac_surface_get_width(skiac_surface *c_surface);
   int skiac_surface_get_height(skiac_surface *c_surface);
   void skiac_surface_read_pixels(skiac_surface *c_surface, skiac_surface_data *data);
-  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs);
-  void skiac_surface_png_data(skiac_surface *c_surface, skiac_sk_data *data);
+  bool skiac_surface_read_pixels_rect(skiac_surface *c_surface, uint8_t *data, int x, int y, int w, int h, uint8_t cs, bool f32, bool premultiplied);
+  void skiac_surface_png_data(skiac_surface *c_surface, skiac_sk_data *data, int compression_level, int filter_flags);
+  skiac_png_stream *skiac_surface_png_stream_create(skiac_surface *c_surface, int compression_level, int filter_flags);
+  bool skiac_png_stream_encode_rows(skiac_png_stream *c_png_stream, int rows, skiac_sk_data *data);
+  int skiac_png_stream_rows_left(skiac_png_stream *c_png_stream);
+  void skiac_png_stream_destroy(skiac_png_stream *c_png_stream);
   void skiac_surface_encode_data(skiac_surface *c_surface, skiac_sk_data *data, int format, int quality);
   int skiac_surface_get_alpha_type(skiac_surface *c_surface);
   bool skiac_surface_save(skiac_surface *c_surface, const char *path);
@@ -300,7 +382,12 @@ extern "C"
       int weight,
       int stretch,
       int slant,
+      int variant,
+      int kerning,
+      int text_rendering,
       const char *font_family,
+      float letter_spacing,
+      float word_spacing,
       int baseline,
       int align,
       int direction,
@@ -313,8 +400,8 @@ extern "C"
   void skiac_canvas_save(skiac_canvas *c_canvas);
   void skiac_canvas_restore(skiac_canvas *c_canvas);
   void skiac_canvas_reset(skiac_canvas *c_canvas);
-  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y);
-  void skiac_canvas_write_pixels_dirty(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, size_t length, float x, float y, float dirty_x, float dirty_y, float dirty_width, float dirty_height, uint8_t cs);
+  void skiac_canvas_write_pixels(skiac_canvas *c_canvas, int width, int height, uint8_t *pixels, size_t row_bytes, int x, int y, uint8_t cs, bool f32);
+  void skiac_canvas_write_pixels_dirty(skiac_canvas *c_canvas, uint8_t *pixels, size_t row_bytes, int x, int y, int dirty_x, int dirty_y, int dirty_width, int dirty_height, uint8_t cs, bool f32);
 
   // Paint
   skiac_paint *skiac_paint_create();
@@ -342,12 +429,12 @@ extern "C"
 
   // Path
   skiac_path *skiac_path_create();
-  skiac_path *skiac_path_from_svg(char *svg_path);
+  skiac_path *skiac_path_from_svg(const char *svg_path);
   skiac_path *skiac_path_clone(skiac_path *c_path);
   void skiac_path_swap(skiac_path *c_path, skiac_path *other_path);
   void skiac_add_path(skiac_path *c_path, skiac_path *other_path, skiac_matrix *c_matrix);
   bool skiac_path_op(skiac_path *c_path_one, skiac_path *c_path_two, int op);
-  void skiac_path_to_svg_string(skiac_path *c_path, skiac_string *c_string);
+  void skiac_path_to_svg_string(skiac_path *c_path, bool relative, skiac_string *c_string);
   bool skiac_path_simplify(skiac_path *c_path);
   bool skiac_path_stroke(skiac_path *c_path, int cap, uint8_t join, float width, float miter_limit);
   void skiac_path_get_bounds(skiac_path *c_path, skiac_rect *c_rect);
@@ -374,7 +461,7 @@ extern "C"
   void skiac_path_transform_self(skiac_path *c_path, skiac_matrix *c_matrix);
   bool skiac_path_is_empty(skiac_path *c_path);
   bool skiac_path_hit_test(skiac_path *c_path, float x, float y, int type);
-  bool skiac_path_stroke_hit_test(skiac_path *c_path, float x, float y, float stroke_w);
+  bool skiac_path_stroke_hit_test(skiac_path *c_path, float x, float y, int cap, uint8_t join, float stroke_w, float miter_limit);
   void skiac_path_round_rect(
       skiac_path *c_path,
       SkScalar x,
@@ -411,7 +498,7 @@ extern "C"
   skiac_shader *skiac_shader_make_conic_gradient(
       float cx,
       float cy,
-      float radius,
+      float start_angle,
       const uint32_t *colors,
       const float *positions,
       int count,
@@ -454,24 +541,29 @@ extern "C"
   skiac_image_filter *skiac_image_filter_make_drop_shadow(float dx, float dy, float sigma_x, float sigma_y, uint32_t color, skiac_image_filter *c_image_filter);
   skiac_image_filter *skiac_image_filter_make_blur(float sigma_x, float sigma_y, int tile_mode, skiac_image_filter *c_image_filter);
   skiac_image_filter *skiac_image_filter_color_filter(float m00, float m01, float m02, float m10, float m11, float m12, float m20, float m21, float m22, float opacity, skiac_image_filter *c_image_filter);
+  skiac_image_filter *skiac_image_filter_color_matrix(const float matrix[20], bool clamp, skiac_image_filter *c_image_filter);
   skiac_image_filter *skiac_image_filter_from_argb(const uint8_t table_a[256], const uint8_t table_r[256], const uint8_t table_g[256], const uint8_t table_b[256], skiac_image_filter *c_image_filter);
+  skiac_image_filter *skiac_image_filter_make_compose(skiac_image_filter *c_outer, skiac_image_filter *c_inner);
   void skiac_image_filter_destroy(skiac_image_filter *c_image_filter);
 
   // Data
   void skiac_sk_data_destroy(skiac_data *c_data);
 
   // Bitmap
-  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, skiac_bitmap_info *bitmap_info);
+  void skiac_bitmap_make_from_buffer(const uint8_t *ptr, size_t size, bool apply_orientation, skiac_bitmap_info *bitmap_info);
+  int skiac_codec_get_orientation(const uint8_t *ptr, size_t size);
+  void skiac_bitmap_make_from_buffer_frame(const uint8_t *ptr, size_t size, int frame_index, skiac_bitmap_info *bitmap_info);
+  int skiac_codec_get_frames(const uint8_t *ptr, size_t size, int *durations, int capacity);
   void skiac_bitmap_make_from_svg(const uint8_t *data, size_t length, float width, float height, skiac_bitmap_info *bitmap_info, uint8_t cs);
   skiac_bitmap *skiac_bitmap_make_from_image_data(uint8_t *ptr, size_t width, size_t height, size_t row_bytes, size_t size, int ct, int at);
+  void skiac_bitmap_copy(skiac_bitmap *c_bitmap, skiac_bitmap_info *bitmap_info);
   size_t skiac_bitmap_get_width(skiac_bitmap *c_bitmap);
   size_t skiac_bitmap_get_height(skiac_bitmap *c_bitmap);
   skiac_shader *skiac_bitmap_get_shader(
       skiac_bitmap *c_bitmap,
       int repeat_x,
       int repeat_y,
-      float B,
-      float C, // See SkSamplingOptions.h for docs.
+      int filter_quality,
       skiac_transform c_ts);
   void skiac_bitmap_destroy(skiac_bitmap *c_bitmap);
 
@@ -483,6 +575,7 @@ extern "C"
   uint32_t skiac_font_collection_get_default_fonts_count(skiac_font_collection *c_font_collection);
   void skiac_font_collection_get_family(skiac_font_collection *c_font_collection, uint32_t i, skiac_string *c_string, void *on_get_style_rust, skiac_on_match_font_style on_match_font_style);
   size_t skiac_font_collection_register(skiac_font_collection *c_font_collection, const uint8_t *font, size_t length, const char *name_alias);
+  size_t skiac_font_collection_register_styled(skiac_font_collection *c_font_collection, const uint8_t *font, size_t length, const char *family, int weight, int slant);
   size_t skiac_font_collection_register_from_path(skiac_font_collection *c_font_collection, const char *font_path, const char *name_alias);
   void skiac_font_collection_set_alias(skiac_font_collection *c_font_collection, const char *family, const char *alias);
   void skiac_font_collection_destroy(skiac_font_collection *c_font_collection);
@@ -491,8 +584,15 @@ extern "C"
   void skiac_sk_w_stream_get(skiac_w_memory_stream *c_w_memory_stream, skiac_sk_data *sk_data, int width, int height);
   void skiac_sk_w_stream_destroy(skiac_w_memory_stream *c_w_memory_stream);
 
+  // SkPDF
+  skiac_pdf_document *skiac_pdf_document_create();
+  skiac_canvas *skiac_pdf_document_begin_page(skiac_pdf_document *c_document, float width, float height);
+  void skiac_pdf_document_end_page(skiac_pdf_document *c_document);
+  void skiac_pdf_document_close(skiac_pdf_document *c_document, skiac_sk_data *data);
+  void skiac_pdf_document_destroy(skiac_pdf_document *c_document);
+
   // SkSVG
-  void skiac_svg_text_to_path(const uint8_t *data, size_t length, skiac_font_collection *c_collection, skiac_sk_data *output_data);
+  int skiac_svg_text_to_path(const uint8_t *data, size_t length, skiac_font_collection *c_collection, uint32_t flag, skiac_sk_data *output_data);
 }
 
 #endif // SKIA_CAPI_H
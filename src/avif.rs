@@ -79,14 +79,14 @@ impl From<AvifConfig> for Config {
   fn from(config: AvifConfig) -> Self {
     Config {
       // See also: https://github.com/kornelski/cavif-rs#usage
-      quality: config.quality.unwrap_or(80) as u8,
+      quality: config.quality.unwrap_or(80).min(100) as u8,
       // Calculate alphaQuality, this is consistent with cavif.
       // https://github.com/kornelski/cavif-rs/blob/37847b95bb81d4cf90e36b7fab2c7fbbcf95abe2/src/main.rs#L97
-      alpha_quality: config.alpha_quality.unwrap_or(90) as u8,
+      alpha_quality: config.alpha_quality.unwrap_or(90).min(100) as u8,
       // Encoding speed between 1 (best, but slowest) and 10 (fastest, but a blurry mess), the default value is 4.
       // Speeds 1 and 2 are unbelievably slow, but make files ~3-5% smaller.
       // Speeds 7 and above degrade compression significantly, and are not recommended.
-      speed: config.speed.unwrap_or(5) as u8,
+      speed: config.speed.unwrap_or(5).min(10) as u8,
       threads: config
         .threads
         .map(|n| n as usize)
@@ -98,16 +98,6 @@ impl From<AvifConfig> for Config {
   }
 }
 
-impl From<&Either3<u32, AvifConfig, Unknown>> for AvifConfig {
-  fn from(value: &Either3<u32, AvifConfig, Unknown>) -> Self {
-    if let Either3::B(a) = value {
-      a.clone()
-    } else {
-      Default::default()
-    }
-  }
-}
-
 pub(crate) fn encode(
   avif_image: &[u8],
   width: u32,
@@ -123,3 +113,21 @@ pub(crate) fn encode(
   encoder.set_max_threads(config.threads);
   encoder.encode(&image).map_err(SkError::EncodeAvifError)
 }
+
+#[test]
+fn avif_config_clamp() {
+  let config = Config::from(AvifConfig {
+    quality: Some(300),
+    alpha_quality: Some(101),
+    speed: Some(42),
+    threads: Some(1),
+    chroma_subsampling: None,
+  });
+  assert_eq!(config.quality, 100);
+  assert_eq!(config.alpha_quality, 100);
+  assert_eq!(config.speed, 10);
+  let config = Config::from(AvifConfig::default());
+  assert_eq!(config.quality, 80);
+  assert_eq!(config.alpha_quality, 90);
+  assert_eq!(config.speed, 5);
+}
@@ -0,0 +1,254 @@
+use cssparser::{BasicParseError, Color, Parser, Token, RGBA};
+
+/// The CSS Color 4 functions cssparser doesn't know about
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColorFunction {
+  Lab,
+  Lch,
+  Oklab,
+  Oklch,
+}
+
+impl ColorFunction {
+  /// The values `100%` of the lightness and the chroma (or the a/b axes) resolve to
+  fn percentage_references(&self) -> (f32, f32) {
+    match self {
+      Self::Lab => (100.0, 125.0),
+      Self::Lch => (100.0, 150.0),
+      Self::Oklab | Self::Oklch => (1.0, 0.4),
+    }
+  }
+}
+
+/// Parse a `<color>`, the `lab()`, `lch()`, `oklab()` and `oklch()` colors are converted to sRGB
+/// and the colors out of the sRGB gamut are clipped
+pub(crate) fn parse_color<'i>(parser: &mut Parser<'i, '_>) -> Result<Color, BasicParseError<'i>> {
+  if let Ok(color) = parser.try_parse(Color::parse) {
+    return Ok(color);
+  }
+  let location = parser.current_source_location();
+  let name = parser.expect_function()?.clone();
+  let function = match name.to_ascii_lowercase().as_str() {
+    "lab" => ColorFunction::Lab,
+    "lch" => ColorFunction::Lch,
+    "oklab" => ColorFunction::Oklab,
+    "oklch" => ColorFunction::Oklch,
+    _ => return Err(location.new_basic_unexpected_token_error(Token::Function(name))),
+  };
+  parser
+    .parse_nested_block(|arguments| {
+      parse_color_function(arguments, function).map_err(|e: BasicParseError| e.into())
+    })
+    .map_err(|e: cssparser::ParseError<()>| e.basic())
+}
+
+fn parse_color_function<'i>(
+  arguments: &mut Parser<'i, '_>,
+  function: ColorFunction,
+) -> Result<Color, BasicParseError<'i>> {
+  let (lightness_reference, chroma_reference) = function.percentage_references();
+  let lightness = parse_component(arguments, lightness_reference)?;
+  let (a, b) = match function {
+    ColorFunction::Lab | ColorFunction::Oklab => (
+      parse_component(arguments, chroma_reference)?,
+      parse_component(arguments, chroma_reference)?,
+    ),
+    ColorFunction::Lch | ColorFunction::Oklch => {
+      let chroma = parse_component(arguments, chroma_reference)?.max(0.0);
+      let hue = parse_hue(arguments)?.to_radians();
+      (chroma * hue.cos(), chroma * hue.sin())
+    }
+  };
+  let alpha = if arguments.try_parse(|p| p.expect_delim('/')).is_ok() {
+    parse_component(arguments, 1.0)?
+  } else {
+    1.0
+  };
+  arguments.expect_exhausted()?;
+  let [red, green, blue] = match function {
+    ColorFunction::Lab | ColorFunction::Lch => lab_to_srgb(lightness.max(0.0), a, b),
+    ColorFunction::Oklab | ColorFunction::Oklch => oklab_to_srgb(lightness.max(0.0), a, b),
+  };
+  Ok(Color::RGBA(RGBA::from_floats(red, green, blue, alpha)))
+}
+
+/// A `<number>`, a `<percentage>` of the `reference` or `none`
+fn parse_component<'i>(
+  arguments: &mut Parser<'i, '_>,
+  reference: f32,
+) -> Result<f32, BasicParseError<'i>> {
+  let location = arguments.current_source_location();
+  match arguments.next()? {
+    Token::Number { value, .. } => Ok(*value),
+    Token::Percentage { unit_value, .. } => Ok(unit_value * reference),
+    Token::Ident(ident) if ident.eq_ignore_ascii_case("none") => Ok(0.0),
+    token => Err(location.new_basic_unexpected_token_error(token.clone())),
+  }
+}
+
+/// A hue in degrees, from a `<number>`, an `<angle>` or `none`
+fn parse_hue<'i>(arguments: &mut Parser<'i, '_>) -> Result<f32, BasicParseError<'i>> {
+  let location = arguments.current_source_location();
+  let token = arguments.next()?;
+  let degrees = match token {
+    Token::Number { value, .. } => Some(*value),
+    Token::Dimension { value, unit, .. } => match unit.to_ascii_lowercase().as_str() {
+      "deg" => Some(*value),
+      "grad" => Some(value * 0.9),
+      "rad" => Some(value.to_degrees()),
+      "turn" => Some(value * 360.0),
+      _ => None,
+    },
+    Token::Ident(ident) if ident.eq_ignore_ascii_case("none") => Some(0.0),
+    _ => None,
+  };
+  degrees.ok_or_else(|| location.new_basic_unexpected_token_error(token.clone()))
+}
+
+/// CIE Lab with the D50 white point to gamma encoded sRGB
+fn lab_to_srgb(lightness: f32, a: f32, b: f32) -> [f32; 3] {
+  const KAPPA: f32 = 24389.0 / 27.0;
+  const EPSILON: f32 = 216.0 / 24389.0;
+  const D50: [f32; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+  let fy = (lightness + 16.0) / 116.0;
+  let fx = a / 500.0 + fy;
+  let fz = fy - b / 200.0;
+  let component = |f: f32| {
+    if f.powi(3) > EPSILON {
+      f.powi(3)
+    } else {
+      (116.0 * f - 16.0) / KAPPA
+    }
+  };
+  let y = if lightness > KAPPA * EPSILON {
+    fy.powi(3)
+  } else {
+    lightness / KAPPA
+  };
+  let xyz_d50 = [component(fx) * D50[0], y * D50[1], component(fz) * D50[2]];
+  // Bradford chromatic adaptation from D50 to D65
+  let xyz_d65 = multiply(
+    &[
+      [0.955_473_4, -0.023_098_455, 0.063_259_244],
+      [-0.028_369_71, 1.009_995_4, 0.021_041_44],
+      [0.012_314_015, -0.020_507_65, 1.330_365_9],
+    ],
+    xyz_d50,
+  );
+  let linear = multiply(
+    &[
+      [3.240_97, -1.537_383_2, -0.498_610_76],
+      [-0.969_243_65, 1.875_967_5, 0.041_555_06],
+      [0.055_630_08, -0.203_976_96, 1.056_971_5],
+    ],
+    xyz_d65,
+  );
+  linear.map(gamma_encode)
+}
+
+/// OKLab to gamma encoded sRGB
+fn oklab_to_srgb(lightness: f32, a: f32, b: f32) -> [f32; 3] {
+  let lms = multiply(
+    &[
+      [1.0, 0.396_337_78, 0.215_803_76],
+      [1.0, -0.105_561_346, -0.063_854_17],
+      [1.0, -0.089_484_18, -1.291_485_5],
+    ],
+    [lightness, a, b],
+  )
+  .map(|c| c.powi(3));
+  let linear = multiply(
+    &[
+      [4.076_741_7, -3.307_711_6, 0.230_969_94],
+      [-1.268_438, 2.609_757_4, -0.341_319_38],
+      [-0.004_196_086_3, -0.703_418_6, 1.707_614_7],
+    ],
+    lms,
+  );
+  linear.map(gamma_encode)
+}
+
+fn multiply(matrix: &[[f32; 3]; 3], vector: [f32; 3]) -> [f32; 3] {
+  matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+fn gamma_encode(linear: f32) -> f32 {
+  if linear.abs() > 0.003_130_8 {
+    linear.signum() * (1.055 * linear.abs().powf(1.0 / 2.4) - 0.055)
+  } else {
+    12.92 * linear
+  }
+}
+
+#[cfg(test)]
+fn parse_rgba(input: &str) -> Option<RGBA> {
+  let mut parser_input = cssparser::ParserInput::new(input);
+  let mut parser = Parser::new(&mut parser_input);
+  match parse_color(&mut parser) {
+    Ok(Color::RGBA(rgba)) => parser.expect_exhausted().ok().map(|_| rgba),
+    _ => None,
+  }
+}
+
+#[test]
+fn parse_lab_colors() {
+  // the references of the CSS Color 4 spec, rounded to 8 bits
+  assert_eq!(
+    parse_rgba("lab(100 0 0)"),
+    Some(RGBA::new(255, 255, 255, 255))
+  );
+  assert_eq!(parse_rgba("lab(0% 0 0)"), Some(RGBA::new(0, 0, 0, 255)));
+  assert_eq!(
+    parse_rgba("lab(54.29 80.82 69.88)"),
+    Some(RGBA::new(255, 0, 0, 255))
+  );
+  assert_eq!(
+    parse_rgba("lch(54.29 106.84 40.85deg)"),
+    Some(RGBA::new(255, 0, 0, 255))
+  );
+  assert_eq!(
+    parse_rgba("LCH(54.29% 71.23% 40.85 / 50%)"),
+    Some(RGBA::new(255, 0, 0, 128))
+  );
+}
+
+#[test]
+fn parse_oklab_colors() {
+  assert_eq!(
+    parse_rgba("oklab(0.62796 0.22486 0.12585)"),
+    Some(RGBA::new(255, 0, 0, 255))
+  );
+  assert_eq!(
+    parse_rgba("oklch(62.796% 0.25768 29.2339)"),
+    Some(RGBA::new(255, 0, 0, 255))
+  );
+  assert_eq!(
+    parse_rgba("oklch(0.7 0.15 200)"),
+    Some(RGBA::new(0, 185, 195, 255))
+  );
+  assert_eq!(
+    parse_rgba("oklch(0.7 0.15 0.55555556turn / 0.25)"),
+    parse_rgba("oklch(0.7 0.15 200 / 0.25)")
+  );
+  assert_eq!(
+    parse_rgba("oklab(1 none none)"),
+    Some(RGBA::new(255, 255, 255, 255))
+  );
+  // out of gamut colors are clipped
+  assert_eq!(parse_rgba("oklch(0.9 0.4 140)").map(|c| c.alpha), Some(255));
+}
+
+#[test]
+fn parse_invalid_color_functions() {
+  assert_eq!(parse_rgba("lab(50 0)"), None);
+  assert_eq!(parse_rgba("lab(50 0 0 0)"), None);
+  assert_eq!(parse_rgba("oklch(0.7 0.15 200px)"), None);
+  assert_eq!(parse_rgba("oklab(0.7, 0.1, 0.1)"), None);
+  assert_eq!(parse_rgba("xyz(0.7 0.1 0.1)"), None);
+  // the cssparser colors are still parsed
+  assert_eq!(parse_rgba("red"), Some(RGBA::new(255, 0, 0, 255)));
+  assert_eq!(
+    parse_rgba("rgb(1 2 3 / 50%)"),
+    Some(RGBA::new(1, 2, 3, 128))
+  );
+}
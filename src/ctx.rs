@@ -1,4 +1,7 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::f32::consts::PI;
+use std::iter;
 use std::mem;
 use std::result;
 use std::slice;
@@ -11,18 +14,21 @@ use napi::{bindgen_prelude::*, JsBuffer, JsString, NapiRaw, NapiValue};
 use crate::global_fonts::get_font;
 use crate::{
   avif::Config,
+  color::parse_color,
   error::SkError,
-  filter::css_filter,
-  filter::css_filters_to_image_filter,
+  filter::{css_filters_to_image_filter, filters_to_string, uses_current_color},
+  filter::{parse_filter_string, pixel_with_context, CssFilter, PixelContext, UrlFilterRegistry},
   font::Font,
   gradient::{CanvasGradient, Gradient},
   image::*,
-  path::Path,
+  offscreen::OffscreenCanvas,
+  path::{check_arc_arguments, normalize_round_rect_radii, Path, RoundRectRadii},
   pattern::{CanvasPattern, Pattern},
   sk::{
-    AlphaType, Bitmap, BlendMode, ColorSpace, FillType, ImageFilter, LineMetrics, MaskFilter,
-    Matrix, Paint, PaintStyle, Path as SkPath, PathEffect, SkEncodedImageFormat, SkWMemoryStream,
-    SkiaDataRef, Surface, SurfaceRef, Transform,
+    AlphaType, Antialias, Bitmap, BlendMode, ColorSpace, FillType, FilterQuality, ImageFilter,
+    LineMetrics, MaskFilter, Matrix, Paint, PaintStyle, Path as SkPath, PathEffect,
+    PngEncodeOptions, SkEncodedImageFormat, SkWMemoryStream, SkiaDataRef, StorageFormat, Surface,
+    SurfaceRef, TextAlign, TextDirection, Transform,
   },
   state::Context2dRenderingState,
   CanvasElement, SVGCanvas,
@@ -38,30 +44,103 @@ pub(crate) const MAX_TEXT_WIDTH: f32 = 100_000.0;
 pub(crate) const FILL_STYLE_HIDDEN_NAME: &str = "_fillStyle";
 pub(crate) const STROKE_STYLE_HIDDEN_NAME: &str = "_strokeStyle";
 
+// the filters compiled lately, `ctx.filter` and its `currentColor` variants are usually few at a time
+const FILTER_CACHE_SIZE: usize = 4;
+
+/// A filter string compiled to a Skia image filter, reused while the string and the resolution context are the same
+#[derive(Debug, Clone)]
+struct CompiledFilter {
+  source: String,
+  pixel_context: PixelContext,
+  current_color: Option<RGBA>,
+  filter: Option<ImageFilter>,
+  /// A `drop-shadow` of the source takes its color from `currentColor`
+  uses_current_color: bool,
+}
+
+/// The filters compiled lately, most recently used first
+#[derive(Default)]
+struct FilterCache(RefCell<Vec<CompiledFilter>>);
+
+impl FilterCache {
+  /// The filter compiled from `source` in the same resolution context, it's compiled by `compile` on a cache miss
+  fn get_or_compile(
+    &self,
+    source: &str,
+    pixel_context: PixelContext,
+    current_color: Option<RGBA>,
+    compile: impl FnOnce() -> result::Result<(Option<ImageFilter>, bool), SkError>,
+  ) -> result::Result<(Option<ImageFilter>, bool), SkError> {
+    let mut cache = self.0.borrow_mut();
+    if let Some(index) = cache.iter().position(|c| {
+      c.source == source && c.pixel_context == pixel_context && c.current_color == current_color
+    }) {
+      let compiled = cache.remove(index);
+      let result = (compiled.filter.clone(), compiled.uses_current_color);
+      cache.insert(0, compiled);
+      return Ok(result);
+    }
+    let (filter, uses_current_color) = compile()?;
+    cache.truncate(FILTER_CACHE_SIZE - 1);
+    cache.insert(
+      0,
+      CompiledFilter {
+        source: source.to_owned(),
+        pixel_context,
+        current_color,
+        filter: filter.clone(),
+        uses_current_color,
+      },
+    );
+    Ok((filter, uses_current_color))
+  }
+
+  fn clear(&self) {
+    self.0.borrow_mut().clear();
+  }
+}
+
 pub struct Context {
   pub(crate) surface: Surface,
   path: SkPath,
   pub alpha: bool,
   pub(crate) states: Vec<Context2dRenderingState>,
-  state: Context2dRenderingState,
+  pub(crate) state: Context2dRenderingState,
   pub width: u32,
   pub height: u32,
   pub color_space: ColorSpace,
+  pub(crate) storage_format: StorageFormat,
   pub stream: Option<SkWMemoryStream>,
+  /// Filters referenced by `url(#id)` in `ctx.filter`
+  pub(crate) url_filters: UrlFilterRegistry,
+  /// The surface couldn't be allocated, the drawing goes to a 1x1 stand-in surface
+  pub(crate) lost: bool,
+  /// The drawing goes to a PDF page, there are no pixels to read or encode
+  pdf_page: bool,
+  /// Most recently used first
+  filter_cache: FilterCache,
+  /// The filter of the canvas, applied when `ctx.filter` is `none`
+  pub(crate) default_filter: String,
+  /// `default_filter` references `currentColor`
+  default_filter_current_color: bool,
+  /// Apply `ctx.filter` after `default_filter` instead of in place of it
+  pub(crate) compose_with_default: bool,
+  /// Clamp the `brightness()`, `contrast()` and `saturate()` results to `[0, 1]`, see `set_clamp_filters`
+  pub(crate) clamp_filters: bool,
 }
 
 impl Context {
   pub fn new_svg(
     width: u32,
     height: u32,
-    svg_export_flag: crate::sk::SvgExportFlag,
+    svg_export_flags: u32,
     color_space: ColorSpace,
   ) -> Result<Self> {
     let (surface, stream) = Surface::new_svg(
       width,
       height,
       AlphaType::Premultiplied,
-      svg_export_flag,
+      svg_export_flags,
       color_space,
     )
     .ok_or_else(|| Error::from_reason("Create skia svg surface failed".to_owned()))?;
@@ -74,13 +153,50 @@ impl Context {
       width,
       height,
       color_space,
+      storage_format: StorageFormat::default(),
       stream: Some(stream),
+      url_filters: UrlFilterRegistry::default(),
+      lost: false,
+      pdf_page: false,
+      filter_cache: FilterCache::default(),
+      default_filter: "none".to_owned(),
+      default_filter_current_color: false,
+      compose_with_default: false,
+      clamp_filters: true,
+    })
+  }
+
+  /// Context drawing into a page of a PDF document
+  pub fn new_pdf_page(width: u32, height: u32, canvas: crate::sk::Canvas) -> Result<Self> {
+    let surface = Surface::new_pdf_page(canvas)
+      .ok_or_else(|| Error::from_reason("Create skia pdf page surface failed".to_owned()))?;
+    Ok(Context {
+      surface,
+      alpha: true,
+      path: SkPath::new(),
+      states: vec![],
+      state: Context2dRenderingState::default(),
+      width,
+      height,
+      color_space: ColorSpace::default(),
+      storage_format: StorageFormat::default(),
+      stream: None,
+      url_filters: UrlFilterRegistry::default(),
+      lost: false,
+      pdf_page: true,
+      filter_cache: FilterCache::default(),
+      default_filter: "none".to_owned(),
+      default_filter_current_color: false,
+      compose_with_default: false,
+      clamp_filters: true,
     })
   }
 
   pub fn new(width: u32, height: u32, color_space: ColorSpace) -> Result<Self> {
-    let surface = Surface::new_rgba_premultiplied(width, height, color_space)
-      .ok_or_else(|| Error::from_reason("Create skia surface failed".to_owned()))?;
+    let (surface, lost) = match Surface::new_rgba_premultiplied(width, height, color_space) {
+      Some(surface) => (surface, false),
+      None => Self::lost_surface(width, height, color_space)?,
+    };
     Ok(Context {
       surface,
       alpha: true,
@@ -90,10 +206,75 @@ impl Context {
       width,
       height,
       color_space,
+      storage_format: StorageFormat::default(),
       stream: None,
+      url_filters: UrlFilterRegistry::default(),
+      lost,
+      pdf_page: false,
+      filter_cache: FilterCache::default(),
+      default_filter: "none".to_owned(),
+      default_filter_current_color: false,
+      compose_with_default: false,
+      clamp_filters: true,
     })
   }
 
+  /// The pixels of a lost context are gone, reading or encoding them fails
+  pub(crate) fn ensure_not_lost(&self) -> Result<()> {
+    if self.pdf_page {
+      return Err(Error::new(
+        Status::GenericFailure,
+        "The context draws into a PDF page, it has no pixels to encode".to_owned(),
+      ));
+    }
+    if self.lost {
+      return Err(Error::new(
+        Status::GenericFailure,
+        format!(
+          "The context is lost, a {}x{} surface can't be allocated",
+          self.width, self.height
+        ),
+      ));
+    }
+    Ok(())
+  }
+
+  /// Stand-in for a surface Skia failed to allocate, e.g. for huge dimensions.
+  /// A zero sized canvas has nothing to draw into, but it isn't lost.
+  fn lost_surface(width: u32, height: u32, color_space: ColorSpace) -> Result<(Surface, bool)> {
+    let surface = Surface::new_rgba_premultiplied(1, 1, color_space)
+      .ok_or_else(|| Error::from_reason("Create skia surface failed".to_owned()))?;
+    Ok((surface, width > 0 && height > 0))
+  }
+
+  /// Recreate the raster surface for the `alpha`, `colorSpace` and `colorType` context attributes, the content is discarded
+  pub(crate) fn reset_surface(
+    &mut self,
+    alpha: bool,
+    color_space: ColorSpace,
+    storage_format: StorageFormat,
+  ) -> Result<()> {
+    let surface = match (storage_format, alpha) {
+      (StorageFormat::Float16, _) => {
+        Surface::new_rgba_f16(self.width, self.height, !alpha, color_space)
+      }
+      (StorageFormat::Uint8, true) => {
+        Surface::new_rgba_premultiplied(self.width, self.height, color_space)
+      }
+      (StorageFormat::Uint8, false) => Surface::new_opaque(self.width, self.height, color_space),
+    };
+    (self.surface, self.lost) = match surface {
+      Some(surface) => (surface, false),
+      None => Self::lost_surface(self.width, self.height, color_space)?,
+    };
+    self.pdf_page = false;
+    self.surface.canvas.set_transform(&self.state.transform);
+    self.alpha = alpha;
+    self.color_space = color_space;
+    self.storage_format = storage_format;
+    Ok(())
+  }
+
   pub fn arc(
     &mut self,
     center_x: f32,
@@ -148,21 +329,33 @@ impl Context {
     self.path.quad_to(cpx, cpy, x, y);
   }
 
-  pub fn clip(&mut self, path: Option<&mut SkPath>, fill_rule: FillType) {
-    let clip = match path {
-      Some(path) => path,
-      None => &mut self.path,
-    };
-    clip.set_fill_type(fill_rule);
-    self.surface.canvas.set_clip_path(clip);
+  pub fn clip(&mut self, path: Option<&SkPath>, fill_rule: FillType) {
+    match path {
+      // the fill rule only applies to this clip, the `Path2D` keeps its own
+      Some(path) => {
+        let mut clip = path.clone();
+        clip.set_fill_type(fill_rule);
+        self.surface.canvas.set_clip_path(&clip);
+      }
+      None => {
+        self.path.set_fill_type(fill_rule);
+        self.surface.canvas.set_clip_path(&self.path);
+      }
+    }
   }
 
   pub fn clear_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
     let mut paint = Paint::new();
     paint.set_style(PaintStyle::Fill);
-    paint.set_color(0, 0, 0, 0);
     paint.set_stroke_miter(10.0);
-    paint.set_blend_mode(BlendMode::Clear);
+    if self.alpha {
+      paint.set_color(0, 0, 0, 0);
+      paint.set_blend_mode(BlendMode::Clear);
+    } else {
+      // cleared pixels of an opaque canvas are opaque black
+      paint.set_color(0, 0, 0, 255);
+      paint.set_blend_mode(BlendMode::Source);
+    }
     self.surface.draw_rect(x, y, width, height, &paint);
   }
 
@@ -174,7 +367,7 @@ impl Context {
     self.path.add_rect(x, y, width, height);
   }
 
-  pub fn round_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radii: [f32; 4]) {
+  pub fn round_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radii: [(f32, f32); 4]) {
     self.path.round_rect(x, y, width, height, radii);
   }
 
@@ -183,6 +376,61 @@ impl Context {
     self.states.push(self.state.clone());
   }
 
+  /// Restore the context to its initial state, the bitmap is cleared and the saved states are dropped
+  pub fn reset(&mut self) -> Result<()> {
+    let saved_states = mem::take(&mut self.states);
+    self.state = Context2dRenderingState::default();
+    self.path = SkPath::new();
+    if self.stream.is_some() {
+      // svg surface, the saved canvas states can't be dropped by recreating it
+      for _ in saved_states {
+        self.surface.canvas.restore();
+      }
+      self.surface.canvas.reset_transform();
+      if self.alpha {
+        self.surface.canvas.clear();
+      } else {
+        self.surface.canvas.fill(0, 0, 0, 255);
+      }
+      Ok(())
+    } else {
+      // a fresh surface also drops the clip, which can't be undone on the canvas
+      self.reset_surface(self.alpha, self.color_space, self.storage_format)
+    }
+  }
+
+  /// Resize the surface and reset the state, the drawing is copied to the top left corner of the new surface
+  /// if `preserve_content`, otherwise the new surface is cleared
+  pub(crate) fn resize(&mut self, width: u32, height: u32, preserve_content: bool) -> Result<()> {
+    let content = (preserve_content && !self.lost).then(|| self.surface.get_bitmap());
+    self.width = width;
+    self.height = height;
+    self.states.clear();
+    self.state = Context2dRenderingState::default();
+    self.path = SkPath::new();
+    self.reset_surface(self.alpha, self.color_space, self.storage_format)?;
+    if let Some(content) = content {
+      let mut paint = Paint::default();
+      paint.set_blend_mode(BlendMode::Source);
+      let (content_width, content_height) = (content.0.width as f32, content.0.height as f32);
+      self.surface.canvas.draw_image(
+        content.0.bitmap,
+        0.0,
+        0.0,
+        content_width,
+        content_height,
+        0.0,
+        0.0,
+        content_width,
+        content_height,
+        false,
+        FilterQuality::None,
+        &paint,
+      );
+    }
+    Ok(())
+  }
+
   pub fn restore(&mut self) {
     if let Some(s) = self.states.pop() {
       self.path.transform_self(&self.state.transform);
@@ -316,6 +564,35 @@ impl Context {
     Ok(())
   }
 
+  /// Draw `text` wrapped into lines no wider than `max_width`, `line_height` apart, return the number of lines.
+  /// `x` is the left edge of the `max_width` box, the lines are aligned in it by `textAlign`.
+  pub fn fill_text_wrapped(
+    &mut self,
+    text: &str,
+    x: f32,
+    y: f32,
+    max_width: f32,
+    line_height: f32,
+  ) -> result::Result<u32, SkError> {
+    let lines = wrap_lines(text, max_width, |line| {
+      Ok(self.get_line_metrics(line)?.0.width)
+    })?;
+    let rtl = matches!(self.state.text_direction, TextDirection::Rtl);
+    let anchor_x = match self.state.text_align {
+      TextAlign::Center => x + max_width / 2.0,
+      TextAlign::Right => x + max_width,
+      TextAlign::Start if rtl => x + max_width,
+      TextAlign::End if !rtl => x + max_width,
+      _ => x,
+    };
+    for (i, line) in lines.iter().enumerate() {
+      if !line.is_empty() {
+        self.fill_text(line, anchor_x, y + i as f32 * line_height, MAX_TEXT_WIDTH)?;
+      }
+    }
+    Ok(lines.len() as u32)
+  }
+
   pub fn stroke(&mut self, path: Option<&mut SkPath>) -> Result<()> {
     let last_state = &self.state;
     let p = match path {
@@ -370,6 +647,11 @@ impl Context {
   }
 
   pub fn fill_paint(&self) -> result::Result<Paint, SkError> {
+    let filter = self.style_filter(&self.state.fill_style)?;
+    self.fill_paint_with_filter(filter.as_ref())
+  }
+
+  fn fill_paint_with_filter(&self, filter: Option<&ImageFilter>) -> result::Result<Paint, SkError> {
     let last_state = &self.state;
     let current_paint = &last_state.paint;
     let mut paint = current_paint.clone();
@@ -381,45 +663,192 @@ impl Context {
         paint.set_color(color.red, color.green, color.blue, color.alpha);
       }
       Pattern::Gradient(g) => {
-        let current_transform = &last_state.transform;
-        let shader = g.get_shader(current_transform.get_transform())?;
+        let shader = g.get_shader()?;
         paint.set_color(0, 0, 0, alpha);
         paint.set_shader(&shader);
       }
       Pattern::Image(p) => {
-        if let Some(shader) = p.get_shader() {
+        if let Some(shader) = p.get_shader(last_state.image_filter_quality()) {
           paint.set_color(0, 0, 0, alpha);
           paint.set_shader(&shader);
         }
       }
     };
-    if !last_state.line_dash_list.is_empty() {
-      let path_effect = PathEffect::new_dash_path(
-        last_state.line_dash_list.as_slice(),
-        last_state.line_dash_offset,
-      )
-      .ok_or_else(|| SkError::Generic("Make line dash path effect failed".to_string()))?;
-      paint.set_path_effect(&path_effect);
-    }
-    if let Some(f) = &self.state.filter {
+    if let Some(f) = filter {
       paint.set_image_filter(f);
     }
     Ok(paint)
   }
 
   pub fn set_filter(&mut self, filter_str: &str) -> result::Result<(), SkError> {
-    if filter_str.trim() == "none" {
-      self.state.filters_string = "none".to_owned();
-      self.state.filter = None;
+    (self.state.filter, self.state.filter_current_color) =
+      self.compile_filter_with_flag(filter_str, None, self.state.font_style.size)?;
+    self.state.filters_string = if filter_str.trim() == "none" {
+      "none".to_owned()
     } else {
-      let (_, filters) =
-        css_filter(filter_str).map_err(|e| SkError::StringToFillRuleError(format!("{e}")))?;
-      self.state.filter = css_filters_to_image_filter(filters);
-      self.state.filters_string = filter_str.to_owned();
+      filter_str.to_owned()
+    };
+    Ok(())
+  }
+
+  /// Validate and set the filter of the canvas, see `default_filter`
+  pub(crate) fn set_default_filter(&mut self, filter_str: &str) -> result::Result<(), SkError> {
+    (_, self.default_filter_current_color) =
+      self.compile_filter_with_flag(filter_str, None, self.state.font_style.size)?;
+    self.default_filter = if filter_str.trim() == "none" {
+      "none".to_owned()
+    } else {
+      filter_str.to_owned()
+    };
+    Ok(())
+  }
+
+  /// Keep the filter of the canvas when the context is recreated
+  pub(crate) fn inherit_canvas_filter(&mut self, previous: &Context) {
+    self.default_filter = previous.default_filter.clone();
+    self.default_filter_current_color = previous.default_filter_current_color;
+    self.compose_with_default = previous.compose_with_default;
+  }
+
+  /// Toggle the clamping of the color matrix filters, the unclamped values are only kept by the `float16` surfaces.
+  /// The filters of the current and the saved states are compiled again
+  pub(crate) fn set_clamp_filters(&mut self, clamp: bool) -> result::Result<(), SkError> {
+    if self.clamp_filters == clamp {
+      return Ok(());
+    }
+    self.clamp_filters = clamp;
+    self.recompile_filters()
+  }
+
+  /// Register the `filter_str` as the filter referenced by `url(#id)`, `none` removes the `id`
+  pub(crate) fn register_url_filter(
+    &mut self,
+    id: &str,
+    filter_str: &str,
+  ) -> result::Result<(), SkError> {
+    match self.parse_filter(filter_str)? {
+      Some(filter) => self.url_filters.register(id, filter),
+      None => self.url_filters.unregister(id),
+    };
+    self.recompile_filters()
+  }
+
+  /// Compile the filters of the current and the saved states again, after the compile options changed
+  fn recompile_filters(&mut self) -> result::Result<(), SkError> {
+    self.filter_cache.clear();
+    let filters = iter::once(&self.state)
+      .chain(&self.states)
+      .map(|state| self.compile_filter(&state.filters_string, None, state.font_style.size))
+      .collect::<result::Result<Vec<_>, _>>()?;
+    for (state, filter) in iter::once(&mut self.state)
+      .chain(&mut self.states)
+      .zip(filters)
+    {
+      state.filter = filter;
     }
     Ok(())
   }
 
+  /// `ctx.filter` combined with the filter of the canvas
+  fn effective_filter_string(&self) -> Cow<'_, str> {
+    let filter = &self.state.filters_string;
+    if self.default_filter == "none" {
+      Cow::Borrowed(filter)
+    } else if filter == "none" {
+      Cow::Borrowed(&self.default_filter)
+    } else if self.compose_with_default {
+      Cow::Owned(format!("{} {filter}", self.default_filter))
+    } else {
+      Cow::Borrowed(filter)
+    }
+  }
+
+  /// The filter drawings are made with, `ctx.filter` combined with the filter of the canvas, serialized after parsing
+  pub(crate) fn computed_filter(&self) -> result::Result<String, SkError> {
+    let filters =
+      self.parse_css_filters(&self.effective_filter_string(), self.state.font_style.size)?;
+    Ok(filters_to_string(&filters))
+  }
+
+  /// The filter to draw `style` with, `currentColor` is resolved to the color of `style`
+  fn style_filter(&self, style: &Pattern) -> result::Result<Option<ImageFilter>, SkError> {
+    let filter_str = self.effective_filter_string();
+    let uses_current_color = self.state.filter_current_color || self.default_filter_current_color;
+    let current_color = match style {
+      Pattern::Color(color, _) if uses_current_color => Some(*color),
+      _ => None,
+    };
+    if current_color.is_none() && filter_str == self.state.filters_string {
+      return Ok(self.state.filter.clone());
+    }
+    self.parse_filter_with_color(&filter_str, current_color)
+  }
+
+  fn parse_filter(&self, filter_str: &str) -> result::Result<Option<ImageFilter>, SkError> {
+    self.parse_filter_with_color(filter_str, None)
+  }
+
+  fn parse_filter_with_color(
+    &self,
+    filter_str: &str,
+    current_color: Option<RGBA>,
+  ) -> result::Result<Option<ImageFilter>, SkError> {
+    self.compile_filter(filter_str, current_color, self.state.font_style.size)
+  }
+
+  /// Compile the filter string, the latest results are cached so assigning the same string again doesn't parse it
+  fn compile_filter(
+    &self,
+    filter_str: &str,
+    current_color: Option<RGBA>,
+    font_size_px: f32,
+  ) -> result::Result<Option<ImageFilter>, SkError> {
+    self
+      .compile_filter_with_flag(filter_str, current_color, font_size_px)
+      .map(|(filter, _)| filter)
+  }
+
+  /// `compile_filter`, along with whether the filter takes a color from `currentColor`
+  fn compile_filter_with_flag(
+    &self,
+    filter_str: &str,
+    current_color: Option<RGBA>,
+    font_size_px: f32,
+  ) -> result::Result<(Option<ImageFilter>, bool), SkError> {
+    if filter_str.trim() == "none" {
+      return Ok((None, false));
+    }
+    let pixel_context = self.filter_pixel_context(font_size_px);
+    self
+      .filter_cache
+      .get_or_compile(filter_str, pixel_context, current_color, || {
+        let filters = self.parse_css_filters(filter_str, font_size_px)?;
+        let filter = css_filters_to_image_filter(
+          &filters,
+          current_color,
+          &self.url_filters,
+          self.clamp_filters,
+        );
+        Ok((filter, uses_current_color(&filters)))
+      })
+  }
+
+  fn parse_css_filters(
+    &self,
+    filter_str: &str,
+    font_size_px: f32,
+  ) -> result::Result<Vec<CssFilter>, SkError> {
+    parse_filter_string(filter_str, &self.filter_pixel_context(font_size_px))
+      .map_err(|e| SkError::StringToFilterError(format!("{e}")))
+  }
+
+  fn filter_pixel_context(&self, font_size_px: f32) -> PixelContext {
+    PixelContext {
+      font_size_px,
+      ..PixelContext::new(self.width as f32, self.height as f32)
+    }
+  }
+
   pub fn get_font(&self) -> &str {
     &self.state.font
   }
@@ -443,13 +872,13 @@ impl Context {
   }
 
   pub fn get_global_alpha(&self) -> f64 {
-    self.state.paint.get_alpha() as f64 / 255.0
+    self.state.global_alpha
   }
 
   pub fn set_shadow_color(&mut self, shadow_color: String) -> result::Result<(), SkError> {
     let mut parser_input = ParserInput::new(&shadow_color);
     let mut parser = Parser::new(&mut parser_input);
-    let color = CSSColor::parse(&mut parser)
+    let color = parse_color(&mut parser)
       .map_err(|e| SkError::Generic(format!("Parse color [{}] error: {:?}", &shadow_color, e)))?;
 
     match color {
@@ -467,6 +896,28 @@ impl Context {
     Ok(())
   }
 
+  pub fn set_letter_spacing(&mut self, letter_spacing: String) -> result::Result<(), SkError> {
+    self.state.letter_spacing = self.parse_spacing(&letter_spacing)?;
+    self.state.letter_spacing_string = letter_spacing;
+    Ok(())
+  }
+
+  pub fn set_word_spacing(&mut self, word_spacing: String) -> result::Result<(), SkError> {
+    self.state.word_spacing = self.parse_spacing(&word_spacing)?;
+    self.state.word_spacing_string = word_spacing;
+    Ok(())
+  }
+
+  /// Resolve a CSS `<length>` used by `letterSpacing` and `wordSpacing` to pixels
+  fn parse_spacing(&self, spacing: &str) -> result::Result<f32, SkError> {
+    let pixel_context = PixelContext {
+      font_size_px: self.state.font_style.size,
+      ..PixelContext::new(self.width as f32, self.height as f32)
+    };
+    pixel_with_context(spacing.trim(), &pixel_context)
+      .map_err(|_| SkError::StringToLengthError(spacing.to_owned()))
+  }
+
   pub fn set_text_align(&mut self, text_align: String) -> result::Result<(), SkError> {
     self.state.text_align = text_align.parse()?;
     Ok(())
@@ -477,17 +928,74 @@ impl Context {
     Ok(())
   }
 
+  pub fn set_global_composite_operation(&mut self, mode: &str) -> result::Result<(), SkError> {
+    self.state.paint.set_blend_mode(mode.parse()?);
+    Ok(())
+  }
+
+  /// The pixels of the rect, those outside of the canvas are transparent black
   pub fn get_image_data(
     &mut self,
-    x: f32,
-    y: f32,
-    w: f32,
-    h: f32,
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32,
     color_type: ColorSpace,
-  ) -> Option<Vec<u8>> {
+  ) -> result::Result<Vec<u8>, SkError> {
+    self.ensure_readable()?;
+    if !self.intersects(x, y, w, h) {
+      return Ok(vec![0; w as usize * h as usize * 4]);
+    }
     self
       .surface
-      .read_pixels(x as u32, y as u32, w as u32, h as u32, color_type)
+      .read_pixels(x, y, w, h, color_type)
+      .ok_or_else(|| Self::pixel_read_error(x, y, w, h, color_type))
+  }
+
+  pub fn get_image_data_f32(
+    &mut self,
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32,
+    color_type: ColorSpace,
+  ) -> result::Result<Vec<f32>, SkError> {
+    self.ensure_readable()?;
+    if !self.intersects(x, y, w, h) {
+      return Ok(vec![0.0; w as usize * h as usize * 4]);
+    }
+    self
+      .surface
+      .read_pixels_f32(x, y, w, h, color_type)
+      .ok_or_else(|| Self::pixel_read_error(x, y, w, h, color_type))
+  }
+
+  // the surface of a lost context is a stand-in, its pixels are not the canvas
+  fn ensure_readable(&self) -> result::Result<(), SkError> {
+    if self.pdf_page {
+      return Err(SkError::PixelReadError(
+        "the context draws into a PDF page, it has no pixels to read".to_owned(),
+      ));
+    }
+    if self.lost {
+      return Err(SkError::PixelReadError(format!(
+        "the context is lost, a {}x{} surface can't be allocated",
+        self.width, self.height
+      )));
+    }
+    Ok(())
+  }
+
+  fn pixel_read_error(x: i32, y: i32, w: u32, h: u32, color_type: ColorSpace) -> SkError {
+    SkError::PixelReadError(format!(
+      "can't read the {w}x{h} rect at ({x}, {y}) in {}",
+      color_type.as_str()
+    ))
+  }
+
+  fn intersects(&self, x: i32, y: i32, w: u32, h: u32) -> bool {
+    let (x, y) = (x as i64, y as i64);
+    x < self.width as i64 && y < self.height as i64 && x + w as i64 > 0 && y + h as i64 > 0
   }
 
   pub fn set_line_dash(&mut self, line_dash_list: Vec<f32>) {
@@ -506,13 +1014,12 @@ impl Context {
         paint.set_color(color.red, color.green, color.blue, color.alpha);
       }
       Pattern::Gradient(g) => {
-        let current_transform = &last_state.transform;
-        let shader = g.get_shader(current_transform.get_transform())?;
+        let shader = g.get_shader()?;
         paint.set_color(0, 0, 0, global_alpha);
         paint.set_shader(&shader);
       }
       Pattern::Image(p) => {
-        if let Some(shader) = p.get_shader() {
+        if let Some(shader) = p.get_shader(last_state.image_filter_quality()) {
           paint.set_color(0, 0, 0, current_paint.get_alpha());
           paint.set_shader(&shader);
         }
@@ -526,8 +1033,8 @@ impl Context {
       .ok_or_else(|| SkError::Generic("Make line dash path effect failed".to_string()))?;
       paint.set_path_effect(&path_effect);
     }
-    if let Some(f) = &self.state.filter {
-      paint.set_image_filter(f);
+    if let Some(f) = self.style_filter(&last_state.stroke_style)? {
+      paint.set_image_filter(&f);
     }
     Ok(paint)
   }
@@ -553,11 +1060,19 @@ impl Context {
     let g = shadow_color.green;
     let b = shadow_color.blue;
     let transform = last_state.transform.get_transform();
-    let sigma_x = last_state.shadow_blur / (2f32 * transform.scale_x());
-    let sigma_y = last_state.shadow_blur / (2f32 * transform.scale_y());
+    // the drop shadow filter works in the local coordinates, the offset in canvas pixels is mapped back
+    // through the inverse of the linear part of the transform so it survives rotations and skews
+    let det = transform.a * transform.d - transform.b * transform.c;
+    if det == 0f32 || !det.is_finite() {
+      return None;
+    }
+    let (offset_x, offset_y) = (last_state.shadow_offset_x, last_state.shadow_offset_y);
+    let local_offset_x = (transform.d * offset_x - transform.c * offset_y) / det;
+    let local_offset_y = (transform.a * offset_y - transform.b * offset_x) / det;
+    let (sigma_x, sigma_y) = Self::shadow_sigma(last_state.shadow_blur, &transform);
     let shadow_effect = ImageFilter::make_drop_shadow_only(
-      last_state.shadow_offset_x,
-      last_state.shadow_offset_y,
+      local_offset_x,
+      local_offset_y,
       sigma_x,
       sigma_y,
       (a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32,
@@ -568,6 +1083,14 @@ impl Context {
     Some(drop_shadow_paint)
   }
 
+  /// The local sigmas of `shadow_blur` in canvas pixels, scaled by the length of the transformed axes
+  fn shadow_sigma(shadow_blur: f32, transform: &Transform) -> (f32, f32) {
+    (
+      shadow_blur / (2f32 * transform.a.hypot(transform.b)),
+      shadow_blur / (2f32 * transform.c.hypot(transform.d)),
+    )
+  }
+
   fn shadow_blur_paint(&self, paint: &Paint) -> Option<Paint> {
     let alpha = paint.get_alpha();
     let last_state = &self.state;
@@ -588,8 +1111,7 @@ impl Context {
     let g = shadow_color.green;
     let b = shadow_color.blue;
     let transform = last_state.transform.get_transform();
-    let sigma_x = last_state.shadow_blur / (2f32 * transform.scale_x());
-    let sigma_y = last_state.shadow_blur / (2f32 * transform.scale_y());
+    let (sigma_x, sigma_y) = Self::shadow_sigma(last_state.shadow_blur, &transform);
     let shadow_effect = ImageFilter::make_drop_shadow_only(
       0.0,
       0.0,
@@ -617,8 +1139,58 @@ impl Context {
     d_width: f32,
     d_height: f32,
   ) -> Result<()> {
+    let paint = self.fill_paint()?;
+    self.draw_image_with_paint(
+      bitmap, sx, sy, s_width, s_height, dx, dy, d_width, d_height, paint,
+    )
+  }
+
+  /// Draw the whole image into the destination rect with `filter_str` in place of `ctx.filter`,
+  /// the context state is left untouched
+  pub(crate) fn draw_image_with_filter(
+    &mut self,
+    bitmap: &Bitmap,
+    dx: f32,
+    dy: f32,
+    d_width: f32,
+    d_height: f32,
+    filter_str: &str,
+  ) -> Result<()> {
+    let current_color = match &self.state.fill_style {
+      Pattern::Color(color, _) => Some(*color),
+      _ => None,
+    };
+    let filter = self.parse_filter_with_color(filter_str, current_color)?;
+    let paint = self.fill_paint_with_filter(filter.as_ref())?;
+    let (width, height) = (bitmap.0.width as f32, bitmap.0.height as f32);
+    self.draw_image_with_paint(
+      bitmap, 0.0, 0.0, width, height, dx, dy, d_width, d_height, paint,
+    )
+  }
+
+  fn draw_image_with_paint(
+    &mut self,
+    bitmap: &Bitmap,
+    sx: f32,
+    sy: f32,
+    s_width: f32,
+    s_height: f32,
+    dx: f32,
+    dy: f32,
+    d_width: f32,
+    d_height: f32,
+    mut paint: Paint,
+  ) -> Result<()> {
+    let ([sx, sy, s_width, s_height], [dx, dy, d_width, d_height]) = match clip_image_rects(
+      bitmap.0.width as f32,
+      bitmap.0.height as f32,
+      [sx, sy, s_width, s_height],
+      [dx, dy, d_width, d_height],
+    ) {
+      Some(rects) => rects,
+      None => return Ok(()),
+    };
     let bitmap = bitmap.0.bitmap;
-    let mut paint = self.fill_paint()?;
     paint.set_alpha((self.state.global_alpha * 255.0).round() as u8);
     if let Some(drop_shadow_paint) = self.drop_shadow_paint(&paint) {
       let surface = &mut self.surface;
@@ -681,9 +1253,14 @@ impl Context {
         weight,
         stretch as i32,
         slant,
+        state.font_style.variant,
+        state.font_kerning,
+        state.text_rendering,
         &font,
         state.font_style.size,
         &state.font_style.family,
+        state.letter_spacing,
+        state.word_spacing,
         state.text_baseline,
         state.text_align,
         state.text_direction,
@@ -702,9 +1279,14 @@ impl Context {
       weight,
       stretch as i32,
       slant,
+      state.font_style.variant,
+      state.font_kerning,
+      state.text_rendering,
       &font,
       state.font_style.size,
       &state.font_style.family,
+      state.letter_spacing,
+      state.word_spacing,
       state.text_baseline,
       state.text_align,
       state.text_direction,
@@ -727,7 +1309,11 @@ impl Context {
       weight,
       stretch as i32,
       slant,
+      state.font_style.variant,
+      state.font_kerning,
       &state.font_style.family,
+      state.letter_spacing,
+      state.word_spacing,
       state.text_baseline,
       state.text_align,
       state.text_direction,
@@ -746,9 +1332,10 @@ impl Context {
       .invert()
       .ok_or_else(|| SkError::Generic("Invert matrix failed".to_owned()))?;
     surface.canvas.concat(&invert);
-    let mut shadow_offset = current_transform.clone();
-    shadow_offset.pre_translate(shadow_offset_x, shadow_offset_y);
-    surface.canvas.concat(&shadow_offset);
+    // shadow offsets are in canvas pixels and not affected by the current transform
+    surface
+      .canvas
+      .concat(&Matrix::translated(shadow_offset_x, shadow_offset_y));
     surface.canvas.concat(&current_transform);
     Ok(())
   }
@@ -763,9 +1350,92 @@ impl Context {
   }
 }
 
+/// Clip the `drawImage` source rect to the image and shrink the destination rect in the same proportion,
+/// `None` if there is nothing to draw
+fn clip_image_rects(
+  width: f32,
+  height: f32,
+  src: [f32; 4],
+  dst: [f32; 4],
+) -> Option<([f32; 4], [f32; 4])> {
+  if src.iter().chain(dst.iter()).any(|v| !v.is_finite()) {
+    return None;
+  }
+  // rects with negative sizes are the same rects spanned from the other corner
+  let normalize = |[x, y, w, h]: [f32; 4]| [x.min(x + w), y.min(y + h), w.abs(), h.abs()];
+  let [sx, sy, sw, sh] = normalize(src);
+  let [dx, dy, dw, dh] = normalize(dst);
+  if sw == 0.0 || sh == 0.0 || dw == 0.0 || dh == 0.0 {
+    return None;
+  }
+  let left = sx.max(0.0);
+  let top = sy.max(0.0);
+  let right = (sx + sw).min(width);
+  let bottom = (sy + sh).min(height);
+  if right <= left || bottom <= top {
+    return None;
+  }
+  let scale_x = dw / sw;
+  let scale_y = dh / sh;
+  Some((
+    [left, top, right - left, bottom - top],
+    [
+      dx + (left - sx) * scale_x,
+      dy + (top - sy) * scale_y,
+      (right - left) * scale_x,
+      (bottom - top) * scale_y,
+    ],
+  ))
+}
+
+/// Greedily break `text` into lines fitting `max_width` at the whitespaces, `\n` always breaks.
+/// A word wider than `max_width` is kept whole on its own line.
+fn wrap_lines<F>(text: &str, max_width: f32, mut measure: F) -> result::Result<Vec<String>, SkError>
+where
+  F: FnMut(&str) -> result::Result<f32, SkError>,
+{
+  let mut lines = Vec::new();
+  for paragraph in text.split('\n') {
+    let mut line = String::new();
+    for word in paragraph.split_whitespace() {
+      if line.is_empty() {
+        line.push_str(word);
+        continue;
+      }
+      let candidate = format!("{line} {word}");
+      if measure(&candidate)? <= max_width {
+        line = candidate;
+      } else {
+        lines.push(mem::replace(&mut line, word.to_owned()));
+      }
+    }
+    lines.push(line);
+  }
+  Ok(lines)
+}
+
+/// The `getImageData` rect in whole pixels, the rects with negative sizes are spanned from the other corner.
+/// The arguments are WebIDL `long`s, truncated toward zero before the rect is normalized
+fn image_data_rect(x: f64, y: f64, width: f64, height: f64) -> (i32, i32, u32, u32) {
+  // `ToInt32`, NaN is 0 and the larger values wrap around
+  let long = |value: f64| value.trunc() as i64 as i32 as i64;
+  let (x, width) = match (long(x), long(width)) {
+    (x, width) if width < 0 => (x + width, -width),
+    rect => rect,
+  };
+  let (y, height) = match (long(y), long(height)) {
+    (y, height) if height < 0 => (y + height, -height),
+    rect => rect,
+  };
+  let origin = |value: i64| value.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+  (origin(x), origin(y), width as u32, height as u32)
+}
+
 #[napi(object)]
 pub struct ContextAttributes {
   pub alpha: bool,
+  pub color_space: String,
+  pub color_type: String,
   pub desynchronized: bool,
 }
 
@@ -793,23 +1463,37 @@ pub struct CanvasRenderingContext2D {
 
 impl ObjectFinalize for CanvasRenderingContext2D {
   fn finalize(self, mut env: Env) -> Result<()> {
-    env.adjust_external_memory(-((self.context.width * self.context.height * 4) as i64))?;
+    env.adjust_external_memory(-(self.context.width as i64 * self.context.height as i64 * 4))?;
     Ok(())
   }
 }
 
+impl CanvasRenderingContext2D {
+  /// Wrap into a JavaScript object with the default `fillStyle` and `strokeStyle`, the pixels are reported as external memory
+  pub(crate) fn into_js_instance(self, mut env: Env) -> Result<ClassInstance<Self>> {
+    let (width, height) = (self.context.width, self.context.height);
+    let ctx = self.into_instance(env)?;
+    ctx.as_object(env).define_properties(&[
+      Property::new(FILL_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+      Property::new(STROKE_STYLE_HIDDEN_NAME)?
+        .with_value(&env.create_string("#000")?)
+        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
+    ])?;
+    env.adjust_external_memory(width as i64 * height as i64 * 4)?;
+    Ok(ctx)
+  }
+}
+
 #[napi]
 impl CanvasRenderingContext2D {
   #[napi(constructor)]
-  pub fn new(
-    width: u32,
-    height: u32,
-    color_space: String,
-    flag: Option<SvgExportFlag>,
-  ) -> Result<Self> {
+  pub fn new(width: u32, height: u32, color_space: String, flag: Option<u32>) -> Result<Self> {
     let color_space = ColorSpace::from_str(&color_space)?;
     let context = if let Some(flag) = flag {
-      Context::new_svg(width, height, flag.into(), color_space)?
+      let flags = crate::sk::SvgExportFlag::check_bits(flag)?;
+      Context::new_svg(width, height, flags, color_space)?
     } else {
       Context::new(width, height, color_space)?
     };
@@ -823,8 +1507,9 @@ impl CanvasRenderingContext2D {
 
   #[napi(setter, return_if_invalid)]
   pub fn set_miter_limit(&mut self, miter_limit: f64) {
-    if !miter_limit.is_nan() && !miter_limit.is_infinite() {
-      self.context.set_miter_limit(miter_limit as f32);
+    let miter_limit = miter_limit as f32;
+    if miter_limit.is_finite() && miter_limit > 0.0 {
+      self.context.set_miter_limit(miter_limit);
     }
   }
 
@@ -835,14 +1520,16 @@ impl CanvasRenderingContext2D {
 
   #[napi(setter, return_if_invalid)]
   pub fn set_global_alpha(&mut self, alpha: f64) {
-    let alpha = alpha as f32;
+    // NaN is not in the range either
     if !(0.0..=1.0).contains(&alpha) {
-      #[cfg(debug_assertions)]
-      eprintln!("Alpha value out of range, expected 0.0 - 1.0, but got : {alpha}");
       return;
     }
     self.context.state.global_alpha = alpha;
-    self.context.state.paint.set_alpha((alpha * 255.0) as u8);
+    self
+      .context
+      .state
+      .paint
+      .set_alpha((alpha * 255.0).round() as u8);
   }
 
   #[napi(getter)]
@@ -856,11 +1543,10 @@ impl CanvasRenderingContext2D {
       .to_owned()
   }
 
+  /// The unknown modes are ignored, the current one is kept
   #[napi(setter, return_if_invalid)]
   pub fn set_global_composite_operation(&mut self, mode: String) {
-    if let Ok(blend_mode) = mode.parse() {
-      self.context.state.paint.set_blend_mode(blend_mode);
-    };
+    let _ = self.context.set_global_composite_operation(&mode);
   }
 
   #[napi(getter)]
@@ -890,6 +1576,21 @@ impl CanvasRenderingContext2D {
     };
   }
 
+  #[napi(getter)]
+  pub fn get_antialias(&self) -> String {
+    self.context.state.antialias.as_str().to_owned()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_antialias(&mut self, antialias: String) {
+    if let Ok(antialias) = antialias.parse() {
+      let state = &mut self.context.state;
+      state.antialias = antialias;
+      // every fill, stroke and image paint is cloned from the state paint
+      state.paint.set_anti_alias(antialias != Antialias::None);
+    }
+  }
+
   #[napi(getter)]
   pub fn get_line_cap(&self) -> String {
     self
@@ -915,7 +1616,9 @@ impl CanvasRenderingContext2D {
 
   #[napi(setter, return_if_invalid)]
   pub fn set_line_dash_offset(&mut self, offset: f64) {
-    self.context.state.line_dash_offset = offset as f32;
+    if offset.is_finite() {
+      self.context.state.line_dash_offset = offset as f32;
+    }
   }
 
   #[napi(getter)]
@@ -941,9 +1644,13 @@ impl CanvasRenderingContext2D {
     self.context.state.paint.get_stroke_width() as f64
   }
 
+  /// Zero, negative and non-finite widths are ignored, the current width is kept
   #[napi(setter, return_if_invalid)]
   pub fn set_line_width(&mut self, width: f64) {
-    self.context.state.paint.set_stroke_width(width as f32);
+    let width = width as f32;
+    if width.is_finite() && width > 0.0 {
+      self.context.state.paint.set_stroke_width(width);
+    }
   }
 
   #[napi(getter)]
@@ -970,11 +1677,56 @@ impl CanvasRenderingContext2D {
     Ok(())
   }
 
+  /// `true` when the surface couldn't be allocated, the drawing is discarded and the encoding fails
+  #[napi]
+  pub fn is_context_lost(&self) -> bool {
+    self.context.lost
+  }
+
+  #[napi]
+  pub fn reset(&mut self, mut this: This) -> Result<()> {
+    self.context.reset()?;
+    this.set(FILL_STYLE_HIDDEN_NAME, "#000")?;
+    this.set(STROKE_STYLE_HIDDEN_NAME, "#000")?;
+    Ok(())
+  }
+
   #[napi(getter)]
   pub fn get_filter(&self) -> String {
     self.context.state.filters_string.clone()
   }
 
+  /// The effective filter in canonical CSS, the lengths are resolved to `px` and the colors to `rgba()`
+  #[napi(getter)]
+  pub fn get_computed_filter(&self) -> Result<String> {
+    Ok(self.context.computed_filter()?)
+  }
+
+  #[napi(getter)]
+  pub fn get_clamp_filters(&self) -> bool {
+    self.context.clamp_filters
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_clamp_filters(&mut self, clamp: bool) -> Result<()> {
+    self.context.set_clamp_filters(clamp)?;
+    Ok(())
+  }
+
+  /// Make the `filter` string referenceable as `url(#id)` in `ctx.filter`, `none` removes the `id`
+  #[napi]
+  pub fn register_filter(&mut self, id: String, filter: String) -> Result<()> {
+    let id = id.strip_prefix('#').unwrap_or(&id);
+    if id.is_empty() {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "The id of the filter is empty".to_owned(),
+      ));
+    }
+    self.context.register_url_filter(id, &filter)?;
+    Ok(())
+  }
+
   #[napi(setter, return_if_invalid)]
   pub fn set_filter(&mut self, filter: String) -> Result<()> {
     self.context.set_filter(&filter)?;
@@ -992,18 +1744,30 @@ impl CanvasRenderingContext2D {
     Ok(())
   }
 
+  /// `rtl` shapes the text right to left and aligns `start` to the right
   #[napi(getter)]
-  pub fn get_text_direction(&self) -> String {
+  pub fn get_direction(&self) -> String {
     self.context.state.text_direction.as_str().to_owned()
   }
 
   #[napi(setter, return_if_invalid)]
-  pub fn set_text_direction(&mut self, direction: String) {
+  pub fn set_direction(&mut self, direction: String) {
     if let Ok(d) = direction.parse() {
       self.context.state.text_direction = d;
     };
   }
 
+  /// The old name of `direction`
+  #[napi(getter)]
+  pub fn get_text_direction(&self) -> String {
+    self.get_direction()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_text_direction(&mut self, direction: String) {
+    self.set_direction(direction)
+  }
+
   #[napi(getter)]
   pub fn get_stroke_style(&self, this: This) -> Option<Unknown> {
     this.get(STROKE_STYLE_HIDDEN_NAME).ok().flatten()
@@ -1035,6 +1799,9 @@ impl CanvasRenderingContext2D {
 
   #[napi(setter, return_if_invalid)]
   pub fn set_shadow_blur(&mut self, blur: f64) {
+    if !blur.is_finite() || blur < 0.0 {
+      return;
+    }
     self.context.state.shadow_blur = blur as f32;
   }
 
@@ -1056,6 +1823,9 @@ impl CanvasRenderingContext2D {
 
   #[napi(setter, return_if_invalid)]
   pub fn set_shadow_offset_x(&mut self, offset_x: f64) {
+    if !offset_x.is_finite() {
+      return;
+    }
     self.context.state.shadow_offset_x = offset_x as f32;
   }
 
@@ -1066,9 +1836,70 @@ impl CanvasRenderingContext2D {
 
   #[napi(setter, return_if_invalid)]
   pub fn set_shadow_offset_y(&mut self, offset_y: f64) {
+    if !offset_y.is_finite() {
+      return;
+    }
     self.context.state.shadow_offset_y = offset_y as f32;
   }
 
+  #[napi(getter)]
+  pub fn get_font_kerning(&self) -> String {
+    self.context.state.font_kerning.as_str().to_owned()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_font_kerning(&mut self, kerning: String) {
+    if let Ok(kerning) = kerning.parse() {
+      self.context.state.font_kerning = kerning;
+    }
+  }
+
+  #[napi(getter)]
+  pub fn get_text_rendering(&self) -> String {
+    self.context.state.text_rendering.as_str().to_owned()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_text_rendering(&mut self, text_rendering: String) {
+    if let Ok(text_rendering) = text_rendering.parse() {
+      self.context.state.text_rendering = text_rendering;
+    }
+  }
+
+  #[napi(getter)]
+  pub fn get_font_stretch(&self) -> String {
+    self.context.state.font_style.stretch.as_str().to_owned()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_font_stretch(&mut self, stretch: String) {
+    if let Ok(stretch) = stretch.parse() {
+      self.context.state.font_style.stretch = stretch;
+    }
+  }
+
+  #[napi(getter)]
+  pub fn get_letter_spacing(&self) -> String {
+    self.context.state.letter_spacing_string.clone()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_letter_spacing(&mut self, letter_spacing: String) -> Result<()> {
+    self.context.set_letter_spacing(letter_spacing)?;
+    Ok(())
+  }
+
+  #[napi(getter)]
+  pub fn get_word_spacing(&self) -> String {
+    self.context.state.word_spacing_string.clone()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_word_spacing(&mut self, word_spacing: String) -> Result<()> {
+    self.context.set_word_spacing(word_spacing)?;
+    Ok(())
+  }
+
   #[napi(getter)]
   pub fn get_text_align(&self) -> String {
     self.context.state.text_align.as_str().to_owned()
@@ -1100,7 +1931,10 @@ impl CanvasRenderingContext2D {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !check_arc_arguments(&[x, y, start_angle, end_angle], &[radius])? {
+      return Ok(());
+    }
     self.context.arc(
       x as f32,
       y as f32,
@@ -1109,6 +1943,7 @@ impl CanvasRenderingContext2D {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi]
@@ -1145,22 +1980,20 @@ impl CanvasRenderingContext2D {
   #[napi]
   pub fn clip(
     &mut self,
-    rule_or_path: Option<Either<String, &mut Path>>,
+    rule_or_path: Option<Either<String, &Path>>,
     maybe_rule: Option<String>,
-  ) {
-    let rule = rule_or_path
-      .as_ref()
-      .and_then(|e| match e {
-        Either::A(s) => FillType::from_str(s).ok(),
-        Either::B(_) => None,
-      })
-      .or_else(|| maybe_rule.and_then(|s| FillType::from_str(&s).ok()))
+  ) -> Result<()> {
+    let (path, rule) = match rule_or_path {
+      Some(Either::A(rule)) => (None, Some(rule)),
+      Some(Either::B(path)) => (Some(path), maybe_rule),
+      None => (None, None),
+    };
+    let rule = rule
+      .map(|rule| FillType::from_str(&rule))
+      .transpose()?
       .unwrap_or(FillType::Winding);
-    let path = rule_or_path.and_then(|e| match e {
-      Either::A(_) => None,
-      Either::B(p) => Some(p),
-    });
-    self.context.clip(path.map(|p| &mut p.inner), rule);
+    self.context.clip(path.map(|p| &p.inner), rule);
+    Ok(())
   }
 
   #[napi]
@@ -1179,63 +2012,75 @@ impl CanvasRenderingContext2D {
   pub fn create_image_data(
     &mut self,
     env: Env,
-    width_or_data: Either<u32, Uint8ClampedArray>,
-    width_or_height: u32,
-    height_or_settings: Option<Either<u32, Settings>>,
+    width_or_data: Either3<f64, Uint8ClampedArray, &ImageData>,
+    width_or_height: Option<f64>,
+    height_or_settings: Option<Either<f64, Settings>>,
     maybe_settings: Option<Settings>,
   ) -> Result<ClassInstance<ImageData>> {
-    match width_or_data {
-      Either::A(width) => {
-        let height = width_or_height;
-        let color_space = match height_or_settings {
-          Some(Either::B(settings)) => {
-            ColorSpace::from_str(&settings.color_space).unwrap_or_default()
-          }
-          _ => ColorSpace::default(),
-        };
-        let arraybuffer_length = (width * height * 4) as usize;
-        let mut data_buffer = vec![0; arraybuffer_length];
-        let data_ptr = data_buffer.as_mut_ptr();
-        let data_object = unsafe {
-          Object::from_raw_unchecked(
-            env.raw(),
-            Uint8ClampedArray::to_napi_value(env.raw(), Uint8ClampedArray::new(data_buffer))?,
-          )
+    let (width, height, color_space, storage_format) = match width_or_data {
+      Either3::A(width) => {
+        let height = width_or_height.unwrap_or(f64::NAN);
+        let (width, height) = image_data_size(width, height)?;
+        let (color_space, storage_format) = match height_or_settings {
+          Some(Either::B(settings)) => (
+            settings.color_space().unwrap_or_default(),
+            settings.storage_format()?,
+          ),
+          _ => (ColorSpace::default(), StorageFormat::default()),
         };
-        let instance = ImageData {
-          width: width as usize,
-          height: height as usize,
-          color_space,
-          data: data_ptr,
-        }
-        .into_instance(env)?;
-        let mut image_instance = unsafe { Object::from_raw_unchecked(env.raw(), instance.raw()) };
-        image_instance.set("data", data_object)?;
-        Ok(instance)
+        (width, height, color_space, storage_format)
       }
-      Either::B(mut data_object) => {
+      // copy the dimensions of the `ImageData`, but not the pixels
+      Either3::C(image_data) => (
+        image_data.width as u32,
+        image_data.height as u32,
+        image_data.color_space,
+        image_data.storage_format,
+      ),
+      Either3::B(mut data_object) => {
         let input_data_length = data_object.len();
-        let width = width_or_height;
+        let width = width_or_height
+          .filter(|width| *width >= 1.0)
+          .ok_or_else(|| {
+            Error::new(
+              Status::InvalidArg,
+              "IndexSizeError: The source width must be a positive integer".to_owned(),
+            )
+          })? as u32;
         let height = match &height_or_settings {
-          Some(Either::A(height)) => *height,
+          Some(Either::A(height)) => *height as u32,
           _ => (input_data_length as u32) / 4 / width,
         };
         let data = data_object.as_mut_ptr();
         let color_space = maybe_settings
-          .and_then(|settings| ColorSpace::from_str(&settings.color_space).ok())
+          .and_then(|settings| settings.color_space())
           .unwrap_or_default();
         let instance = ImageData {
           width: width as usize,
           height: height as usize,
           color_space,
+          storage_format: StorageFormat::Uint8,
           data,
         }
         .into_instance(env)?;
         let mut image_instance = unsafe { Object::from_raw_unchecked(env.raw(), instance.raw()) };
         image_instance.set("data", data_object)?;
-        Ok(instance)
+        return Ok(instance);
       }
+    };
+    let (data_object, data_ptr) =
+      ImageDataArray::zeroed(width, height, storage_format)?.into_js(&env)?;
+    let instance = ImageData {
+      width: width as usize,
+      height: height as usize,
+      color_space,
+      storage_format,
+      data: data_ptr,
     }
+    .into_instance(env)?;
+    let mut image_instance = unsafe { Object::from_raw_unchecked(env.raw(), instance.raw()) };
+    image_instance.set("data", data_object)?;
+    Ok(instance)
   }
 
   #[napi]
@@ -1273,11 +2118,11 @@ impl CanvasRenderingContext2D {
   pub fn create_conic_gradient(
     &mut self,
     env: Env,
-    r: f64,
+    start_angle: f64,
     x: f64,
     y: f64,
   ) -> Result<ClassInstance<CanvasGradient>> {
-    let conic_gradient = Gradient::create_conic_gradient(x as f32, y as f32, r as f32);
+    let conic_gradient = Gradient::create_conic_gradient(x as f32, y as f32, start_angle as f32);
     CanvasGradient(conic_gradient).into_instance(env)
   }
 
@@ -1305,38 +2150,14 @@ impl CanvasRenderingContext2D {
     y: f64,
     width: f64,
     height: f64,
-    radii: Either3<f64, Vec<f64>, Undefined>,
-  ) {
-    // https://github.com/chromium/chromium/blob/111.0.5520.1/third_party/blink/renderer/modules/canvas/canvas2d/canvas_path.cc#L579
-    let radii_array: [f32; 4] = match radii {
-      Either3::A(radii) => [radii as f32; 4],
-      Either3::B(radii_vec) => match radii_vec.len() {
-        0 => [0f32; 4],
-        1 => [radii_vec[0] as f32; 4],
-        2 => [
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-        ],
-        3 => [
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-          radii_vec[1] as f32,
-          radii_vec[2] as f32,
-        ],
-        _ => [
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-          radii_vec[2] as f32,
-          radii_vec[3] as f32,
-        ],
-      },
-      Either3::C(_) => [0f32; 4],
-    };
-    self
-      .context
-      .round_rect(x as f32, y as f32, width as f32, height as f32, radii_array);
+    radii: RoundRectRadii,
+  ) -> Result<()> {
+    if let Some(radii) = normalize_round_rect_radii(radii)? {
+      self
+        .context
+        .round_rect(x as f32, y as f32, width as f32, height as f32, radii);
+    }
+    Ok(())
   }
 
   #[napi]
@@ -1373,18 +2194,30 @@ impl CanvasRenderingContext2D {
 
   #[napi(return_if_invalid)]
   pub fn rotate(&mut self, angle: f64) {
+    if !angle.is_finite() {
+      return;
+    }
     self.context.rotate(angle as f32);
   }
 
   #[napi(return_if_invalid)]
   pub fn scale(&mut self, x: f64, y: f64) {
+    if !x.is_finite() || !y.is_finite() {
+      return;
+    }
     self.context.scale(x as f32, y as f32);
   }
 
   #[napi]
   pub fn draw_image(
     &mut self,
-    image: Either3<&mut CanvasElement, &mut SVGCanvas, &mut Image>,
+    image: Either5<
+      &mut CanvasElement,
+      &mut SVGCanvas,
+      &mut Image,
+      &mut ImageBitmap,
+      &mut OffscreenCanvas,
+    >,
     sx: Option<f64>,
     sy: Option<f64>,
     s_width: Option<f64>,
@@ -1394,20 +2227,9 @@ impl CanvasRenderingContext2D {
     d_width: Option<f64>,
     d_height: Option<f64>,
   ) -> Result<()> {
-    let bitmap = match image {
-      Either3::A(canvas) => BitmapRef::Owned(canvas.ctx.as_ref().context.surface.get_bitmap()),
-      Either3::B(svg) => BitmapRef::Owned(svg.ctx.as_ref().context.surface.get_bitmap()),
-      Either3::C(image) => {
-        if !image.complete {
-          return Ok(());
-        }
-        image.regenerate_bitmap_if_need();
-        if let Some(bitmap) = &mut image.bitmap {
-          BitmapRef::Borrowed(bitmap)
-        } else {
-          return Ok(());
-        }
-      }
+    let bitmap = match image_bitmap(image) {
+      Some(bitmap) => bitmap,
+      None => return Ok(()),
     };
     let bitmap_ref = bitmap.as_ref();
     let (sx, sy, s_width, s_height, dx, dy, d_width, d_height) =
@@ -1459,10 +2281,75 @@ impl CanvasRenderingContext2D {
     Ok(())
   }
 
+  /// Draw the image with a one-shot `filter`, `ctx.filter` is ignored and left untouched
+  #[napi]
+  pub fn draw_image_with_filter(
+    &mut self,
+    image: Either5<
+      &mut CanvasElement,
+      &mut SVGCanvas,
+      &mut Image,
+      &mut ImageBitmap,
+      &mut OffscreenCanvas,
+    >,
+    dx: f64,
+    dy: f64,
+    d_width: f64,
+    d_height: f64,
+    filter: String,
+  ) -> Result<()> {
+    let bitmap = match image_bitmap(image) {
+      Some(bitmap) => bitmap,
+      None => return Ok(()),
+    };
+    self.context.draw_image_with_filter(
+      bitmap.as_ref(),
+      dx as f32,
+      dy as f32,
+      d_width as f32,
+      d_height as f32,
+      &filter,
+    )
+  }
+
+  /// Draw the frame at `frame` of an animated image, frames are numbered from `0`
+  #[napi]
+  pub fn draw_image_frame(
+    &mut self,
+    image: &mut Image,
+    frame: u32,
+    dx: f64,
+    dy: f64,
+    d_width: Option<f64>,
+    d_height: Option<f64>,
+  ) -> Result<()> {
+    let bitmap = match image.frame_bitmap(frame)? {
+      Some(bitmap) => bitmap,
+      None => return Ok(()),
+    };
+    let (width, height) = (bitmap.0.width as f32, bitmap.0.height as f32);
+    self.context.draw_image(
+      bitmap,
+      0.0,
+      0.0,
+      width,
+      height,
+      dx as f32,
+      dy as f32,
+      d_width.map(|w| w as f32).unwrap_or(width),
+      d_height.map(|h| h as f32).unwrap_or(height),
+    )
+  }
+
   #[napi]
   pub fn get_context_attributes(&self) -> ContextAttributes {
     ContextAttributes {
       alpha: self.context.alpha,
+      color_space: self.context.color_space.as_str().to_owned(),
+      color_type: match self.context.storage_format {
+        StorageFormat::Uint8 => "unorm8".to_owned(),
+        StorageFormat::Float16 => "float16".to_owned(),
+      },
       desynchronized: false,
     }
   }
@@ -1512,7 +2399,11 @@ impl CanvasRenderingContext2D {
     x_or_y: f64,
     maybe_y: Option<f64>,
   ) -> Result<bool> {
+    let paint = &self.context.state.paint;
+    let cap = paint.get_stroke_cap();
+    let join = paint.get_stroke_join();
     let stroke_w = self.context.get_stroke_width();
+    let miter_limit = self.context.get_miter_limit();
     match x_or_path {
       Either::A(x) => {
         let y = x_or_y;
@@ -1520,13 +2411,17 @@ impl CanvasRenderingContext2D {
           self
             .context
             .path
-            .stroke_hit_test(x as f32, y as f32, stroke_w),
+            .stroke_hit_test(x as f32, y as f32, cap, join, stroke_w, miter_limit),
         )
       }
       Either::B(path) => {
         let x = x_or_y;
         if let Some(y) = maybe_y {
-          Ok(path.inner.stroke_hit_test(x as f32, y as f32, stroke_w))
+          Ok(
+            path
+              .inner
+              .stroke_hit_test(x as f32, y as f32, cap, join, stroke_w, miter_limit),
+          )
         } else {
           Err(Error::new(
             Status::InvalidArg,
@@ -1548,7 +2443,13 @@ impl CanvasRenderingContext2D {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !check_arc_arguments(
+      &[x, y, rotation, start_angle, end_angle],
+      &[radius_x, radius_y],
+    )? {
+      return Ok(());
+    }
     self.context.ellipse(
       x as f32,
       y as f32,
@@ -1559,6 +2460,7 @@ impl CanvasRenderingContext2D {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi(return_if_invalid)]
@@ -1593,8 +2495,8 @@ impl CanvasRenderingContext2D {
       font_bounding_box_ascent: metrics.0.font_ascent as f64,
       font_bounding_box_descent: metrics.0.font_descent as f64,
       alphabetic_baseline: metrics.0.alphabetic_baseline as f64,
-      em_height_ascent: metrics.0.font_ascent as f64,
-      em_height_descent: metrics.0.font_descent as f64,
+      em_height_ascent: metrics.0.em_height_ascent as f64,
+      em_height_descent: metrics.0.em_height_descent as f64,
       width: metrics.0.width as f64,
     })
   }
@@ -1624,9 +2526,36 @@ impl CanvasRenderingContext2D {
     Ok(())
   }
 
+  /// `fillText` breaking the text into lines fitting `maxWidth`, returns the number of lines
+  #[napi]
+  pub fn fill_text_wrapped(
+    &mut self,
+    text: String,
+    x: f64,
+    y: f64,
+    max_width: f64,
+    line_height: f64,
+  ) -> Result<u32> {
+    if [x, y, max_width, line_height]
+      .iter()
+      .any(|v| !v.is_finite())
+      || max_width <= 0.0
+    {
+      return Ok(0);
+    }
+    Ok(self.context.fill_text_wrapped(
+      &text,
+      x as f32,
+      y as f32,
+      max_width as f32,
+      line_height as f32,
+    )?)
+  }
+
   #[napi(return_if_invalid)]
   pub fn fill_text(&mut self, text: String, x: f64, y: f64, max_width: Option<f64>) -> Result<()> {
-    if text.is_empty() {
+    // nothing fits into a zero, negative or NaN `maxWidth`
+    if text.is_empty() || max_width.is_some_and(|w| w.is_nan() || w <= 0.0) {
       return Ok(());
     }
     if !x.is_nan() && !x.is_infinite() && !y.is_nan() && !y.is_infinite() {
@@ -1672,7 +2601,7 @@ impl CanvasRenderingContext2D {
     y: f64,
     max_width: Option<f64>,
   ) -> Result<()> {
-    if text.is_empty() {
+    if text.is_empty() || max_width.is_some_and(|w| w.is_nan() || w <= 0.0) {
       return Ok(());
     }
     if !x.is_nan() && !x.is_infinite() && !y.is_nan() && !y.is_infinite() {
@@ -1694,7 +2623,7 @@ impl CanvasRenderingContext2D {
     y: f64,
     width: f64,
     height: f64,
-    color_space: Option<String>,
+    settings: Option<Either<String, Settings>>,
   ) -> Result<ClassInstance<ImageData>> {
     if !x.is_nan()
       && !x.is_infinite()
@@ -1705,29 +2634,37 @@ impl CanvasRenderingContext2D {
       && !height.is_nan()
       && !height.is_infinite()
     {
-      let color_space = color_space
-        .and_then(|cs| cs.parse().ok())
-        .unwrap_or(ColorSpace::Srgb);
-      let mut image_data = self
-        .context
-        .get_image_data(x as f32, y as f32, width as f32, height as f32, color_space)
-        .ok_or_else(|| {
-          Error::new(
-            Status::GenericFailure,
-            "Read pixels from canvas failed".to_string(),
-          )
-        })?;
-      let data = image_data.as_mut_ptr();
-      let data_object = unsafe {
-        Object::from_raw_unchecked(
-          env.raw(),
-          Uint8ClampedArray::to_napi_value(env.raw(), Uint8ClampedArray::new(image_data))?,
-        )
+      let (color_space, storage_format) = match settings {
+        Some(Either::A(color_space)) => (color_space.parse().ok(), StorageFormat::default()),
+        Some(Either::B(settings)) => (settings.color_space(), settings.storage_format()?),
+        None => (None, StorageFormat::default()),
       };
+      let color_space = color_space.unwrap_or(self.context.color_space);
+      let (x, y, w, h) = image_data_rect(x, y, width, height);
+      if w == 0 || h == 0 {
+        return Err(Error::new(
+          Status::InvalidArg,
+          "IndexSizeError: The source width and height must not be zero".to_owned(),
+        ));
+      }
+      image_data_len(w, h)?;
+      let pixels = match storage_format {
+        StorageFormat::Uint8 => self
+          .context
+          .get_image_data(x, y, w, h, color_space)
+          .map(ImageDataArray::Uint8),
+        StorageFormat::Float16 => self
+          .context
+          .get_image_data_f32(x, y, w, h, color_space)
+          .map(ImageDataArray::Float32),
+      }
+      .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))?;
+      let (data_object, data) = pixels.into_js(&env)?;
       let instance = ImageData {
-        width: width as usize,
-        height: height as usize,
+        width: w as usize,
+        height: h as usize,
         color_space,
+        storage_format,
         data,
       }
       .into_instance(env)?;
@@ -1757,65 +2694,42 @@ impl CanvasRenderingContext2D {
   pub fn put_image_data(
     &mut self,
     image_data: &mut ImageData,
-    dx: u32,
-    dy: u32,
+    dx: i32,
+    dy: i32,
     dirty_x: Option<f64>,
     dirty_y: Option<f64>,
     dirty_width: Option<f64>,
     dirty_height: Option<f64>,
   ) {
     if let Some(dirty_x) = dirty_x {
-      let mut dirty_x = dirty_x as f32;
-      let mut dirty_y = dirty_y.map(|d| d as f32).unwrap_or(0.0);
-      let mut dirty_width = dirty_width
-        .map(|d| d as f32)
-        .unwrap_or(image_data.width as f32);
-      let mut dirty_height = dirty_height
-        .map(|d| d as f32)
-        .unwrap_or(image_data.height as f32);
-      // as per https://html.spec.whatwg.org/multipage/canvas.html#dom-context-2d-putimagedata
-      if dirty_width < 0f32 {
-        dirty_x += dirty_width;
-        dirty_width = dirty_width.abs();
-      }
-      if dirty_height < 0f32 {
-        dirty_y += dirty_height;
-        dirty_height = dirty_height.abs();
-      }
-      if dirty_x < 0f32 {
-        dirty_width += dirty_x;
-        dirty_x = 0f32;
-      }
-      if dirty_y < 0f32 {
-        dirty_height += dirty_y;
-        dirty_y = 0f32;
-      }
-      if dirty_width <= 0f32 || dirty_height <= 0f32 {
-        return;
-      }
-      let inverted = self.context.surface.canvas.get_transform_matrix().invert();
-      self.context.surface.canvas.save();
-      if let Some(inverted) = inverted {
-        self.context.surface.canvas.concat(&inverted);
-      };
-      self.context.surface.canvas.write_pixels_dirty(
-        image_data,
-        dx as f32,
-        dy as f32,
+      let dirty = put_image_data_dirty_rect(
+        image_data.width as u32,
+        image_data.height as u32,
         dirty_x,
-        dirty_y,
-        dirty_width,
-        dirty_height,
-        image_data.color_space,
+        dirty_y.unwrap_or(0.0),
+        dirty_width.unwrap_or(image_data.width as f64),
+        dirty_height.unwrap_or(image_data.height as f64),
       );
-      self.context.surface.canvas.restore();
+      if let Some(dirty) = dirty {
+        self
+          .context
+          .surface
+          .canvas
+          .write_pixels_dirty(image_data, dx, dy, dirty);
+      }
     } else {
       self.context.surface.canvas.write_pixels(image_data, dx, dy);
     }
   }
 
   #[napi]
-  pub fn set_line_dash(&mut self, dash_list: Vec<f64>) {
+  pub fn set_line_dash(&mut self, dash_list: Vec<f64>) -> Result<()> {
+    if let Some(dash) = dash_list.iter().find(|d| !d.is_finite() || **d < 0.0) {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("Line dash segments must be finite and non-negative, got {dash}"),
+      ));
+    }
     let len = dash_list.len();
     let is_odd = len & 1 != 0;
     let mut line_dash_list = if is_odd {
@@ -1830,6 +2744,7 @@ impl CanvasRenderingContext2D {
       }
     }
     self.context.set_line_dash(line_dash_list);
+    Ok(())
   }
 
   #[napi]
@@ -1839,11 +2754,17 @@ impl CanvasRenderingContext2D {
 
   #[napi(return_if_invalid)]
   pub fn translate(&mut self, x: f64, y: f64) {
+    if !x.is_finite() || !y.is_finite() {
+      return;
+    }
     self.context.translate(x as f32, y as f32);
   }
 
   #[napi(return_if_invalid)]
   pub fn transform(&mut self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Result<()> {
+    if [a, b, c, d, e, f].iter().any(|v| !v.is_finite()) {
+      return Ok(());
+    }
     let ts = Matrix::new(a as f32, c as f32, e as f32, b as f32, d as f32, f as f32);
     self.context.transform(ts)?;
     Ok(())
@@ -1870,6 +2791,13 @@ impl CanvasRenderingContext2D {
       ),
       Either::B(transform) => transform.into_context_transform(),
     };
+    // non-finite matrices are ignored, the current transform stays in place
+    if [ts.a, ts.b, ts.c, ts.d, ts.e, ts.f]
+      .iter()
+      .any(|v| !v.is_finite())
+    {
+      return None;
+    }
     self
       .context
       .set_transform(Matrix::new(ts.a, ts.b, ts.c, ts.d, ts.e, ts.f));
@@ -1877,7 +2805,7 @@ impl CanvasRenderingContext2D {
   }
 }
 
-enum BitmapRef<'a> {
+pub(crate) enum BitmapRef<'a> {
   Borrowed(&'a mut Bitmap),
   Owned(Bitmap),
 }
@@ -1891,6 +2819,37 @@ impl AsRef<Bitmap> for BitmapRef<'_> {
   }
 }
 
+/// `None` if the image is not loaded yet or the bitmap is closed
+pub(crate) fn image_bitmap<'a>(
+  image: Either5<
+    &'a mut CanvasElement,
+    &'a mut SVGCanvas,
+    &'a mut Image,
+    &'a mut ImageBitmap,
+    &'a mut OffscreenCanvas,
+  >,
+) -> Option<BitmapRef<'a>> {
+  match image {
+    Either5::A(canvas) => Some(BitmapRef::Owned(
+      canvas.ctx.as_ref().context.surface.get_bitmap(),
+    )),
+    Either5::B(svg) => Some(BitmapRef::Owned(
+      svg.ctx.as_ref().context.surface.get_bitmap(),
+    )),
+    Either5::C(image) => {
+      if !image.complete {
+        return None;
+      }
+      image.regenerate_bitmap_if_need();
+      image.bitmap.as_mut().map(BitmapRef::Borrowed)
+    }
+    Either5::D(image_bitmap) => image_bitmap.bitmap.as_mut().map(BitmapRef::Borrowed),
+    Either5::E(offscreen) => Some(BitmapRef::Owned(
+      offscreen.ctx.as_ref().context.surface.get_bitmap(),
+    )),
+  }
+}
+
 #[napi(object)]
 pub struct TextMetrics {
   pub actual_bounding_box_ascent: f64,
@@ -1955,7 +2914,7 @@ impl From<Transform> for TransformObject {
 }
 
 pub enum ContextData {
-  Png(SurfaceRef),
+  Png(SurfaceRef, PngEncodeOptions),
   Jpeg(SurfaceRef, u8),
   Webp(SurfaceRef, u8),
   Avif(SurfaceRef, Config, u32, u32),
@@ -1975,17 +2934,15 @@ impl Task for ContextData {
 
   fn compute(&mut self) -> Result<Self::Output> {
     match self {
-      ContextData::Png(surface) => {
-        surface
-          .png_data()
-          .map(ContextOutputData::Skia)
-          .ok_or_else(|| {
-            Error::new(
-              Status::GenericFailure,
-              "Get png data from surface failed".to_string(),
-            )
-          })
-      }
+      ContextData::Png(surface, options) => surface
+        .png_data(*options)
+        .map(ContextOutputData::Skia)
+        .ok_or_else(|| {
+          Error::new(
+            Status::GenericFailure,
+            "Get png data from surface failed".to_string(),
+          )
+        }),
       ContextData::Jpeg(surface, quality) => surface
         .encode_data(SkEncodedImageFormat::Jpeg, *quality)
         .map(ContextOutputData::Skia)
@@ -2044,3 +3001,100 @@ impl Task for ContextData {
     }
   }
 }
+
+#[test]
+fn clip_image_rects_to_the_image() {
+  let full = [0.0, 0.0, 10.0, 10.0];
+  assert_eq!(
+    clip_image_rects(10.0, 10.0, full, [5.0, 5.0, 20.0, 20.0]),
+    Some((full, [5.0, 5.0, 20.0, 20.0]))
+  );
+  // half of the source is outside of the image, so is half of the destination
+  assert_eq!(
+    clip_image_rects(10.0, 10.0, [5.0, -5.0, 10.0, 10.0], [0.0, 0.0, 20.0, 20.0]),
+    Some(([5.0, 0.0, 5.0, 5.0], [0.0, 10.0, 10.0, 10.0]))
+  );
+  assert_eq!(
+    clip_image_rects(10.0, 10.0, [10.0, 0.0, -5.0, 10.0], [0.0, 0.0, 5.0, 10.0]),
+    Some(([5.0, 0.0, 5.0, 10.0], [0.0, 0.0, 5.0, 10.0]))
+  );
+  assert_eq!(
+    clip_image_rects(10.0, 10.0, [20.0, 0.0, 5.0, 5.0], full),
+    None
+  );
+  assert_eq!(
+    clip_image_rects(10.0, 10.0, full, [0.0, 0.0, 0.0, 5.0]),
+    None
+  );
+  assert_eq!(
+    clip_image_rects(10.0, 10.0, [f32::NAN, 0.0, 5.0, 5.0], full),
+    None
+  );
+}
+
+#[test]
+fn wrap_lines_at_whitespaces_and_newlines() {
+  // every character is 1 wide
+  let measure = |line: &str| Ok(line.chars().count() as f32);
+  assert_eq!(
+    wrap_lines("the quick brown fox", 10.0, measure).unwrap(),
+    vec!["the quick", "brown fox"]
+  );
+  assert_eq!(
+    wrap_lines("a\nb  c", 10.0, measure).unwrap(),
+    vec!["a", "b c"]
+  );
+  assert_eq!(
+    wrap_lines("a\n\nb", 10.0, measure).unwrap(),
+    vec!["a", "", "b"]
+  );
+  assert_eq!(
+    wrap_lines("extraordinarily long", 5.0, measure).unwrap(),
+    vec!["extraordinarily", "long"]
+  );
+}
+
+#[test]
+fn image_data_rect_is_normalized() {
+  assert_eq!(image_data_rect(1.0, 2.0, 3.0, 4.0), (1, 2, 3, 4));
+  assert_eq!(image_data_rect(-5.0, -5.0, 10.0, 10.0), (-5, -5, 10, 10));
+  assert_eq!(image_data_rect(10.0, 10.0, -4.0, -6.0), (6, 4, 4, 6));
+  // every argument is truncated toward zero before the normalization
+  assert_eq!(image_data_rect(-0.5, -1.5, 2.0, 2.0), (0, -1, 2, 2));
+  assert_eq!(image_data_rect(10.0, 10.0, -4.5, 2.0), (6, 10, 4, 2));
+  assert_eq!(image_data_rect(10.9, 10.9, -4.9, -4.9), (6, 6, 4, 4));
+  assert_eq!(
+    image_data_rect(i32::MIN as f64, 0.0, i32::MIN as f64, 1.0),
+    (i32::MIN, 0, 1 << 31, 1)
+  );
+}
+
+#[test]
+fn filter_cache_compiles_each_source_once() {
+  let cache = FilterCache::default();
+  let compiles = std::cell::Cell::new(0);
+  let compile = || {
+    compiles.set(compiles.get() + 1);
+    Ok((None, true))
+  };
+  let context = PixelContext::new(100.0, 100.0);
+  for _ in 0..3 {
+    let (filter, uses_current_color) = cache
+      .get_or_compile("drop-shadow(1px 1px currentColor)", context, None, compile)
+      .unwrap();
+    assert!(filter.is_none());
+    // the flag is read from the cache entry, not from parsing again
+    assert!(uses_current_color);
+  }
+  assert_eq!(compiles.get(), 1);
+  let red = Some(RGBA::new(255, 0, 0, 255));
+  cache
+    .get_or_compile("drop-shadow(1px 1px currentColor)", context, red, compile)
+    .unwrap();
+  assert_eq!(compiles.get(), 2);
+  cache.clear();
+  cache
+    .get_or_compile("drop-shadow(1px 1px currentColor)", context, None, compile)
+    .unwrap();
+  assert_eq!(compiles.get(), 3);
+}
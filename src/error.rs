@@ -1,4 +1,5 @@
 use std::ffi::NulError;
+use std::io;
 
 use libavif::Error;
 use thiserror::Error;
@@ -9,6 +10,8 @@ use crate::sk::Matrix;
 pub enum SkError {
   #[error("[`{0}`] is not valid ColorSpace value")]
   StringToColorSpaceError(String),
+  #[error("[`{0}`] is not valid StorageFormat value")]
+  StringToStorageFormatError(String),
   #[error("[`{0}`] is not valid Blend value")]
   StringToBlendError(String),
   #[error("[`{0}`] is not valid FillRule value")]
@@ -19,33 +22,71 @@ pub enum SkError {
   StringToTextBaselineError(String),
   #[error("[`{0}`] is not valid TextDirection value")]
   StringToTextDirectionError(String),
+  #[error("[`{0}`] is not valid GradientInterpolation value")]
+  StringToGradientInterpolationError(String),
   #[error("[`{0}`] is not valid FilterQuality value")]
   StringToFilterQualityError(String),
+  #[error("[`{0}`] is not valid Antialias value")]
+  StringToAntialiasError(String),
+  #[error("{0}")]
+  StringToFilterError(String),
+  #[error("[`{0}`] is not valid length")]
+  StringToLengthError(String),
+  #[error("[`{0}`] is not valid PngFilter value")]
+  StringToPngFilterError(String),
   #[error("[`{0}`] is not valid LineCap value")]
   StringToStrokeCapError(String),
   #[error("[`{0}`] is not valid LineJoin value")]
   StringToStrokeJoinError(String),
   #[error("[`{0}`] is not valid SvgExportFlag value")]
   U32ToStrokeJoinError(u32),
+  #[error("[`{0}`] is not a valid combination of SvgExportFlag values")]
+  InvalidSvgExportFlag(u32),
   #[error("[`{0}`] is not valid transform")]
   InvalidTransform(Matrix),
   #[error("Convert String to CString failed")]
-  NulError,
+  NulError(#[from] NulError),
   #[error("[`{0}`] is not valid font style")]
   InvalidFontStyle(String),
+  #[error("[`{0}`] is not valid font weight")]
+  InvalidFontWeight(String),
   #[error("[`{0}`] is not valid font variant")]
   InvalidFontVariant(String),
+  #[error("[`{0}`] is not valid font stretch")]
+  InvalidFontStretch(String),
+  #[error("[`{0}`] is not valid font kerning")]
+  InvalidFontKerning(String),
+  #[error("[`{0}`] is not valid text rendering")]
+  InvalidTextRendering(String),
   #[error("[`{0}`]")]
-  PixelsToRgb(Error),
+  PixelsToRgb(#[source] Error),
   #[error("[`{0}`]")]
-  EncodeAvifError(Error),
+  EncodeAvifError(#[source] Error),
+  #[error("failed to decode {format} image: {reason}")]
+  ImageDecodeError { format: String, reason: String },
+  #[error("failed to load font [`{path}`]: {reason}")]
+  FontLoadError { path: String, reason: String },
+  #[error("failed to read pixels: {0}")]
+  PixelReadError(String),
+  #[error("Convert svg text to path failed: {0}")]
+  SvgConversionError(String),
   #[error("[`{0}`]")]
   Generic(String),
 }
 
-impl From<NulError> for SkError {
-  fn from(_: NulError) -> Self {
-    Self::NulError
+impl SkError {
+  pub fn font_load<P: AsRef<str>>(path: P, err: io::Error) -> Self {
+    Self::FontLoadError {
+      path: path.as_ref().to_owned(),
+      reason: err.to_string(),
+    }
+  }
+}
+
+/// The path is kept with the io error, a bare `io::Error` doesn't tell which font failed
+impl<P: AsRef<str>> From<(P, io::Error)> for SkError {
+  fn from((path, err): (P, io::Error)) -> Self {
+    Self::font_load(path, err)
   }
 }
 
@@ -54,3 +95,49 @@ impl<T> From<std::sync::PoisonError<T>> for SkError {
     Self::Generic(format!("PoisonError {err}"))
   }
 }
+
+#[test]
+fn image_decode_error_display() {
+  let err = SkError::ImageDecodeError {
+    format: "png".to_owned(),
+    reason: "invalid image data".to_owned(),
+  };
+  assert_eq!(
+    err.to_string(),
+    "failed to decode png image: invalid image data"
+  );
+}
+
+#[test]
+fn font_load_error_display() {
+  let err = SkError::font_load(
+    "/fonts/missing.ttf",
+    io::Error::new(io::ErrorKind::NotFound, "No such file or directory"),
+  );
+  let message = err.to_string();
+  assert!(message.contains("/fonts/missing.ttf"));
+  assert!(message.contains("No such file or directory"));
+  let err = SkError::from((
+    "/fonts/missing.ttf",
+    io::Error::new(io::ErrorKind::NotFound, "No such file or directory"),
+  ));
+  assert_eq!(err.to_string(), message);
+}
+
+#[test]
+fn pixel_read_error_display() {
+  let err = SkError::PixelReadError("the context is lost".to_owned());
+  assert_eq!(
+    err.to_string(),
+    "failed to read pixels: the context is lost"
+  );
+}
+
+#[test]
+fn nul_error_source() {
+  use std::error::Error as _;
+  use std::ffi::CString;
+
+  let err: SkError = CString::new("a\0b").unwrap_err().into();
+  assert!(err.source().is_some());
+}
@@ -1,18 +1,36 @@
-use std::{num::ParseFloatError, ptr};
+use std::{collections::HashMap, fmt, num::ParseFloatError, ptr};
 
 use cssparser::{Color, Parser, ParserInput, RGBA};
 use nom::{
-  branch::alt,
-  bytes::complete::{tag, take_till, take_until},
-  character::{complete::char, is_alphabetic},
-  combinator::map_res,
-  error::Error,
+  bytes::complete::{tag, take_till, take_until, take_while1},
+  character::complete::char,
+  combinator::{map_res, opt},
+  error::{Error, ErrorKind},
   number::complete::float,
   Err, IResult,
 };
 use thiserror::Error;
 
-use crate::sk::{degrees_to_radians, ImageFilter, TileMode};
+use crate::{
+  color::parse_color,
+  font::FONT_MEDIUM_PX,
+  sk::{degrees_to_radians, ImageFilter, TileMode},
+};
+
+/// Skia blurs by the standard deviation of the Gaussian while `drop-shadow` takes a blur radius,
+/// which browsers resolve to two standard deviations
+pub(crate) const DROP_SHADOW_RADIUS_TO_SIGMA: f32 = 0.5;
+
+/// Larger `blur()` deviations only cost memory and time, the result is already a flat average of the source
+const MAX_BLUR_SIGMA: f32 = 500.0;
+
+/// Shadow color of a `drop-shadow` without a color, and of `currentColor` without a current color
+const BLACK: RGBA = RGBA {
+  red: 0,
+  green: 0,
+  blue: 0,
+  alpha: 255,
+};
 
 #[derive(Error, Debug)]
 pub enum ParseFilterError<'a> {
@@ -22,6 +40,18 @@ pub enum ParseFilterError<'a> {
   ParseFloatError(ParseFloatError),
   #[error("[`{0}`] is not valid unit")]
   UnitParseError(&'a str),
+  #[error("[`{0}`] is not valid filter")]
+  TrailingInput(&'a str),
+  #[error("[`{0}`] is not a known filter function")]
+  UnknownFunction(&'a str),
+  #[error("drop-shadow doesn't accept {0}, it's not box-shadow")]
+  BoxShadowSyntax(&'static str),
+  #[error("invalid filter at column {}: {reason}", .offset + 1)]
+  At {
+    /// Byte offset of the failure in the filter string
+    offset: usize,
+    reason: Box<ParseFilterError<'a>>,
+  },
 }
 
 impl<'a> From<Err<Error<&'a str>>> for ParseFilterError<'a> {
@@ -36,103 +66,402 @@ impl<'a> From<ParseFloatError> for ParseFilterError<'a> {
   }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum CssFilter {
   Blur(f32),
   Brightness(f32),
   Contrast(f32),
-  DropShadow(f32, f32, f32, RGBA),
+  /// The color is kept as parsed, `currentColor` is resolved when the filter is compiled
+  DropShadow(f32, f32, f32, Color),
   Grayscale(f32),
   HueRotate(f32),
   Invert(f32),
   Opacity(f32),
   Saturate(f32),
   Sepia(f32),
+  /// `url(#id)`, references a filter of the `UrlFilterRegistry`
+  Url(String),
+}
+
+impl fmt::Display for CssFilter {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CssFilter::Blur(blur) => write!(f, "blur({blur}px)"),
+      CssFilter::Brightness(brightness) => write!(f, "brightness({brightness})"),
+      CssFilter::Contrast(contrast) => write!(f, "contrast({contrast})"),
+      CssFilter::DropShadow(offset_x, offset_y, blur_radius, Color::CurrentColor) => write!(
+        f,
+        "drop-shadow({offset_x}px {offset_y}px {blur_radius}px currentcolor)"
+      ),
+      CssFilter::DropShadow(offset_x, offset_y, blur_radius, Color::RGBA(color)) => write!(
+        f,
+        "drop-shadow({offset_x}px {offset_y}px {blur_radius}px rgba({},{},{},{}))",
+        color.red,
+        color.green,
+        color.blue,
+        color.alpha_f32()
+      ),
+      CssFilter::Grayscale(grayscale) => write!(f, "grayscale({grayscale})"),
+      CssFilter::HueRotate(degrees) => write!(f, "hue-rotate({degrees}deg)"),
+      CssFilter::Invert(invert) => write!(f, "invert({invert})"),
+      CssFilter::Opacity(opacity) => write!(f, "opacity({opacity})"),
+      CssFilter::Saturate(saturate) => write!(f, "saturate({saturate})"),
+      CssFilter::Sepia(sepia) => write!(f, "sepia({sepia})"),
+      CssFilter::Url(id) => write!(f, "url(#{id})"),
+    }
+  }
+}
+
+/// Serialize the parsed filters back to the canonical CSS filter string
+pub fn filters_to_string(filters: &[CssFilter]) -> String {
+  if filters.is_empty() {
+    return "none".to_owned();
+  }
+  filters
+    .iter()
+    .map(|filter| filter.to_string())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Whether a `drop-shadow` of the filters takes its color from `currentColor`
+pub(crate) fn uses_current_color(filters: &[CssFilter]) -> bool {
+  filters
+    .iter()
+    .any(|filter| matches!(filter, CssFilter::DropShadow(_, _, _, Color::CurrentColor)))
+}
+
+/// Prebuilt image filters which could be referenced by `url(#id)` in the filter string
+#[derive(Debug, Default, Clone)]
+pub struct UrlFilterRegistry(HashMap<String, ImageFilter>);
+
+impl UrlFilterRegistry {
+  /// Associate `id` with the `filter`, return the filter previously registered with the `id`
+  pub fn register(&mut self, id: &str, filter: ImageFilter) -> Option<ImageFilter> {
+    self.0.insert(id.to_owned(), filter)
+  }
+
+  pub fn unregister(&mut self, id: &str) -> Option<ImageFilter> {
+    self.0.remove(id)
+  }
+
+  pub fn get(&self, id: &str) -> Option<&ImageFilter> {
+    self.0.get(id)
+  }
+}
+
+/// Dimensions used to resolve the relative lengths like `vw` and `em`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelContext {
+  pub viewport_width: f32,
+  pub viewport_height: f32,
+  /// Font size used to resolve `em`
+  pub font_size_px: f32,
+  /// Root font size used to resolve `rem`
+  pub root_font_size_px: f32,
+}
+
+impl Default for PixelContext {
+  fn default() -> Self {
+    Self {
+      viewport_width: 0.0,
+      viewport_height: 0.0,
+      font_size_px: FONT_MEDIUM_PX,
+      root_font_size_px: FONT_MEDIUM_PX,
+    }
+  }
+}
+
+impl PixelContext {
+  pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+    Self {
+      viewport_width,
+      viewport_height,
+      ..Default::default()
+    }
+  }
+
+  fn has_viewport(&self) -> bool {
+    self.viewport_width > 0.0 && self.viewport_height > 0.0
+  }
 }
 
+#[cfg(test)]
 fn pixel(input: &str) -> Result<f32, ParseFilterError> {
-  let (input, size) = take_till(|c| is_alphabetic(c as u8))(input)?;
+  pixel_with_context(input, &PixelContext::default())
+}
+
+pub(crate) fn pixel_with_context<'a>(
+  input: &'a str,
+  context: &PixelContext,
+) -> Result<f32, ParseFilterError<'a>> {
+  if input.trim_start().starts_with("calc(") {
+    return calc(input, context);
+  }
+  let (size, input) = number_token(input.trim_start());
   let (_, unit) = take_till(|c| c == ')')(input)?;
-  let size = size.trim().parse::<f32>()?;
-  let mut size_px = size;
+  let size = size.parse::<f32>()?;
   match unit.trim() {
-    "em" | "rem" | "pc" => {
-      size_px = size * 16.0;
-    }
-    "pt" => {
-      size_px = size * 4.0 / 3.0;
+    "" => {
+      if size != 0f32 {
+        return Err(ParseFilterError::UnitParseError("[No unit assigned]"));
+      }
+      Ok(size)
     }
-    "px" => {
-      size_px = size;
+    unit => length_to_px(size, unit, context),
+  }
+}
+
+/// Convert the `<length>` in the given unit to pixels, `%` is not a valid unit here
+fn length_to_px<'a>(
+  size: f32,
+  unit: &'a str,
+  context: &PixelContext,
+) -> Result<f32, ParseFilterError<'a>> {
+  let size_px = match unit {
+    "vw" | "vh" | "vmin" | "vmax" if !context.has_viewport() => {
+      return Err(ParseFilterError::UnitParseError(unit));
     }
-    "in" => {
-      size_px = size * 96.0;
+    "vw" => size * context.viewport_width / 100.0,
+    "vh" => size * context.viewport_height / 100.0,
+    "vmin" => size * context.viewport_width.min(context.viewport_height) / 100.0,
+    "vmax" => size * context.viewport_width.max(context.viewport_height) / 100.0,
+    "em" => size * context.font_size_px,
+    "rem" => size * context.root_font_size_px,
+    "pc" => size * 16.0,
+    "pt" => size * 4.0 / 3.0,
+    "px" => size,
+    "in" => size * 96.0,
+    "cm" => size * 96.0 / 2.54,
+    "mm" => size * 96.0 / 25.4,
+    "q" => size * 96.0 / 25.4 / 4.0,
+    _ => {
+      return Err(ParseFilterError::UnitParseError(unit));
     }
-    "cm" => {
-      size_px = size * 96.0 / 2.54;
+  };
+  Ok(size_px)
+}
+
+/// Operand of the `calc()` expression, lengths are already converted to pixels
+#[derive(Debug, Clone, Copy)]
+struct CalcValue {
+  value: f32,
+  is_length: bool,
+}
+
+/// Evaluate the `calc()` expression which results in a `<length>` in pixels
+fn calc<'a>(input: &'a str, context: &PixelContext) -> Result<f32, ParseFilterError<'a>> {
+  let (input, _) = tag("calc(")(input.trim())?;
+  let (input, result) = calc_sum(input, context)?;
+  let (input, _) = char(')')(input.trim_start())?;
+  if !input.trim().is_empty() {
+    return Err(ParseFilterError::UnitParseError(input.trim()));
+  }
+  if !result.is_length && result.value != 0f32 {
+    return Err(ParseFilterError::UnitParseError("[No unit assigned]"));
+  }
+  Ok(result.value)
+}
+
+fn calc_sum<'a>(
+  input: &'a str,
+  context: &PixelContext,
+) -> Result<(&'a str, CalcValue), ParseFilterError<'a>> {
+  let (mut input, mut lhs) = calc_product(input, context)?;
+  loop {
+    let trimmed = input.trim_start();
+    let operator = match trimmed.chars().next() {
+      Some(operator @ ('+' | '-')) => operator,
+      _ => break,
+    };
+    let (output, rhs) = calc_product(&trimmed[1..], context)?;
+    // `1px + 2` is invalid
+    if lhs.is_length != rhs.is_length {
+      return Err(ParseFilterError::UnitParseError(trimmed));
     }
-    "mm" => {
-      size_px = size * 96.0 / 25.4;
+    if operator == '+' {
+      lhs.value += rhs.value;
+    } else {
+      lhs.value -= rhs.value;
     }
-    "q" => {
-      size_px = size * 96.0 / 25.4 / 4.0;
+    input = output;
+  }
+  Ok((input, lhs))
+}
+
+fn calc_product<'a>(
+  input: &'a str,
+  context: &PixelContext,
+) -> Result<(&'a str, CalcValue), ParseFilterError<'a>> {
+  let (mut input, mut lhs) = calc_factor(input, context)?;
+  loop {
+    let trimmed = input.trim_start();
+    let operator = match trimmed.chars().next() {
+      Some(operator @ ('*' | '/')) => operator,
+      _ => break,
+    };
+    let (output, rhs) = calc_factor(&trimmed[1..], context)?;
+    if operator == '*' {
+      // `1px * 2px` is invalid
+      if lhs.is_length && rhs.is_length {
+        return Err(ParseFilterError::UnitParseError(trimmed));
+      }
+      lhs.value *= rhs.value;
+      lhs.is_length |= rhs.is_length;
+    } else {
+      // the divisor must be a non-zero `<number>`
+      if rhs.is_length || rhs.value == 0f32 {
+        return Err(ParseFilterError::UnitParseError(trimmed));
+      }
+      lhs.value /= rhs.value;
     }
-    "%" => {
-      size_px = size * 16.0 / 100.0;
+    input = output;
+  }
+  Ok((input, lhs))
+}
+
+fn calc_factor<'a>(
+  input: &'a str,
+  context: &PixelContext,
+) -> Result<(&'a str, CalcValue), ParseFilterError<'a>> {
+  let input = input.trim_start();
+  if let Some(nested) = input
+    .strip_prefix('(')
+    .or_else(|| input.strip_prefix("calc("))
+  {
+    let (output, value) = calc_sum(nested, context)?;
+    let (output, _) = char(')')(output.trim_start())?;
+    return Ok((output, value));
+  }
+  let (size, output) = number_token(input);
+  let size = size.parse::<f32>()?;
+  let unit_len = output
+    .find(|c: char| !(c.is_ascii_alphabetic() || c == '%'))
+    .unwrap_or(output.len());
+  let (unit, output) = output.split_at(unit_len);
+  if unit.is_empty() {
+    return Ok((
+      output,
+      CalcValue {
+        value: size,
+        is_length: false,
+      },
+    ));
+  }
+  Ok((
+    output,
+    CalcValue {
+      value: length_to_px(size, unit, context)?,
+      is_length: true,
+    },
+  ))
+}
+
+/// Split the leading number from the rest of the input, the number could have a sign and an exponent like `+1.5e1`,
+/// the `e` is left to the unit if it's not followed by digits, like `2em`
+fn number_token(input: &str) -> (&str, &str) {
+  let bytes = input.as_bytes();
+  let mut end = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+  while bytes
+    .get(end)
+    .is_some_and(|b| b.is_ascii_digit() || *b == b'.')
+  {
+    end += 1;
+  }
+  if matches!(bytes.get(end), Some(b'e' | b'E')) {
+    let mut exponent_end = end + 1;
+    if matches!(bytes.get(exponent_end), Some(b'+' | b'-')) {
+      exponent_end += 1;
     }
-    "" => {
-      if size_px != 0f32 {
-        return Err(ParseFilterError::UnitParseError("[No unit assigned]"));
+    if bytes.get(exponent_end).is_some_and(u8::is_ascii_digit) {
+      end = exponent_end;
+      while bytes.get(end).is_some_and(u8::is_ascii_digit) {
+        end += 1;
       }
     }
+  }
+  input.split_at(end)
+}
+
+/// Parse a CSS `<angle>` and normalize it to degrees
+fn angle(input: &str) -> Result<f32, ParseFilterError> {
+  let (size, input) = number_token(input.trim_start());
+  let (_, unit) = take_till(|c| c == ')')(input)?;
+  let size = size.parse::<f32>()?;
+  let degrees = match unit.trim() {
+    // unitless angles are treated as degrees for compatibility
+    "deg" | "" => size,
+    "grad" => size * 0.9,
+    "rad" => size.to_degrees(),
+    "turn" => size * 360.0,
     _ => {
       return Err(ParseFilterError::UnitParseError(unit));
     }
   };
 
-  Ok(size_px)
+  Ok(degrees)
 }
 
 #[inline(always)]
-fn pixel_in_tuple(input: &str) -> IResult<&str, f32> {
-  map_res(take_until(")"), pixel)(input)
+fn pixel_in_tuple<'a>(input: &'a str, context: &PixelContext) -> IResult<&'a str, f32> {
+  map_res(function_arguments, |s| pixel_with_context(s, context))(input)
 }
 
+/// `<number> | <percentage>`, which should be followed by the end of the function, a whitespace or the end of input
 fn number_percentage(input: &str) -> IResult<&str, f32> {
   let (input, num) = float(input.trim())?;
-  if let Ok((input, _)) = tag::<&str, &str, Error<&str>>("%")(input.trim()) {
-    Ok((input, num / 100.0f32))
-  } else {
-    Ok((input, num))
+  let (input, num) = match tag::<&str, &str, Error<&str>>("%")(input.trim()) {
+    Ok((input, _)) => (input, num / 100.0f32),
+    Err(_) => (input, num),
+  };
+  match input.chars().next() {
+    None | Some(')') => Ok((input, num)),
+    Some(c) if c.is_whitespace() => Ok((input, num)),
+    Some(_) => Err(Err::Error(Error::new(input, ErrorKind::Verify))),
   }
 }
 
 fn hue_rotate_parser(input: &str) -> IResult<&str, CssFilter> {
   let (rotated_output, _) = tag("hue-rotate(")(input)?;
-  let (rotated_output, angle) = float(rotated_output.trim())?;
-  let output = rotated_output.trim();
-  let (output, filter) = if let Ok((output, _)) = tag::<&str, &str, Error<&str>>("deg")(output) {
-    (output, CssFilter::HueRotate(angle))
-  } else if let Ok((output, _)) = tag::<&str, &str, Error<&str>>("turn")(output) {
-    (output, CssFilter::HueRotate(angle.fract() * 360.0))
-  } else if let Ok((output, _)) = tag::<&str, &str, Error<&str>>("rad")(output) {
-    (output, CssFilter::HueRotate(angle.to_degrees()))
-  } else if let Ok((output, _)) = tag::<&str, &str, Error<&str>>("grad")(output) {
-    (output, CssFilter::HueRotate(angle * 0.9))
-  } else {
-    (output, CssFilter::HueRotate(0.0f32))
-  };
-  let (finished_input, _) = char(')')(output.trim())?;
-  Ok((finished_input.trim(), filter))
+  let (rotated_output, angle) = map_res(take_until(")"), |angle_str: &str| {
+    // `hue-rotate()` is the same as `hue-rotate(0deg)`
+    if angle_str.trim().is_empty() {
+      Ok(0.0)
+    } else {
+      angle(angle_str)
+    }
+  })(rotated_output)?;
+  let (finished_input, _) = char(')')(rotated_output)?;
+  Ok((finished_input.trim(), CssFilter::HueRotate(angle)))
 }
 
 macro_rules! percentage_parser {
   ($filter_name:ident, $filter_rule:expr, $filter_value:ident) => {
+    percentage_parser!(
+      $filter_name,
+      $filter_rule,
+      $filter_value,
+      f32::MIN,
+      f32::MAX
+    );
+  };
+  // negative values are invalid, values greater than `$max_value` are clamped
+  ($filter_name:ident, $filter_rule:expr, $filter_value:ident, $max_value:expr) => {
+    percentage_parser!($filter_name, $filter_rule, $filter_value, 0.0, $max_value);
+  };
+  ($filter_name:ident, $filter_rule:expr, $filter_value:ident, $min_value:expr, $max_value:expr) => {
     fn $filter_name(input: &str) -> IResult<&str, CssFilter> {
       let (input, _) = tag($filter_rule)(input)?;
-      let (input, value) = number_percentage(input)?;
-      let (input, _) = char(')')(input.trim())?;
-      Ok((input.trim(), CssFilter::$filter_value(value)))
+      // the argument is optional, `grayscale()` is the same as `grayscale(1)`
+      let (output, value) = opt(number_percentage)(input)?;
+      if value.is_some_and(|v| v < $min_value) {
+        return Err(Err::Error(Error::new(input, ErrorKind::Verify)));
+      }
+      let (input, _) = char(')')(output.trim())?;
+      Ok((
+        input.trim(),
+        CssFilter::$filter_value(value.unwrap_or(1.0).min($max_value)),
+      ))
     }
 
     mod $filter_name {
@@ -141,7 +470,7 @@ macro_rules! percentage_parser {
         use super::CssFilter;
         assert_eq!(
           super::$filter_name(concat!($filter_rule, "2)")),
-          Ok(("", CssFilter::$filter_value(2.0f32)))
+          Ok(("", CssFilter::$filter_value(2.0f32.min($max_value))))
         );
         assert_eq!(
           super::$filter_name(concat!($filter_rule, "2%)")),
@@ -170,6 +499,11 @@ macro_rules! percentage_parser {
           super::$filter_name(concat!($filter_rule, " 2 % )  ")),
           Ok(("", CssFilter::$filter_value(0.02f32)))
         );
+
+        assert_eq!(
+          super::$filter_name(concat!($filter_rule, ")")),
+          Ok(("", CssFilter::$filter_value(1.0f32)))
+        );
       }
     }
   };
@@ -177,129 +511,319 @@ macro_rules! percentage_parser {
 
 percentage_parser!(brightness_parser, "brightness(", Brightness);
 percentage_parser!(contrast_parser, "contrast(", Contrast);
-percentage_parser!(grayscale_parser, "grayscale(", Grayscale);
-percentage_parser!(invert_parser, "invert(", Invert);
-percentage_parser!(opacity_parser, "opacity(", Opacity);
-percentage_parser!(saturate_parser, "saturate(", Saturate);
-percentage_parser!(sepia_parser, "sepia(", Sepia);
+percentage_parser!(grayscale_parser, "grayscale(", Grayscale, 1.0);
+percentage_parser!(invert_parser, "invert(", Invert, 1.0);
+percentage_parser!(opacity_parser, "opacity(", Opacity, 1.0);
+percentage_parser!(saturate_parser, "saturate(", Saturate, f32::MAX);
+percentage_parser!(sepia_parser, "sepia(", Sepia, 1.0);
 
-fn blur_parser(input: &str) -> IResult<&str, CssFilter> {
+fn blur_parser<'a>(input: &'a str, context: &PixelContext) -> IResult<&'a str, CssFilter> {
   let (blurred_input, _) = tag("blur(")(input)?;
 
-  let (blurred_input, pixel) = pixel_in_tuple(blurred_input)?;
+  let (blurred_input, pixel) = pixel_in_tuple(blurred_input, context)?;
   let (finished_input, _) = char(')')(blurred_input)?;
   Ok((finished_input.trim(), CssFilter::Blur(pixel)))
 }
 
+/// Take everything before the `)` which closes the current function,
+/// parentheses of the nested functions like `hsl(...)` are skipped.
+fn function_arguments(input: &str) -> IResult<&str, &str> {
+  let mut depth = 0usize;
+  for (index, ch) in input.char_indices() {
+    match ch {
+      '(' => depth += 1,
+      ')' if depth == 0 => return Ok((&input[index..], &input[..index])),
+      ')' => depth -= 1,
+      _ => {}
+    }
+  }
+  Err(Err::Error(Error::new(input, ErrorKind::TakeUntil)))
+}
+
+/// Take everything before the next whitespace,
+/// whitespaces inside of the nested functions like `calc(...)` are skipped.
+fn length_token(input: &str) -> IResult<&str, &str> {
+  let mut depth = 0usize;
+  for (index, ch) in input.char_indices() {
+    match ch {
+      '(' => depth += 1,
+      ')' => depth = depth.saturating_sub(1),
+      ch if ch.is_whitespace() && depth == 0 => return Ok((&input[index..], &input[..index])),
+      _ => {}
+    }
+  }
+  Ok(("", input))
+}
+
+/// Parse the `<color>` at the beginning of the input, return the rest of the input and the color
+fn color_prefix(input: &str) -> Option<(&str, Color)> {
+  let mut parser_input = ParserInput::new(input);
+  let mut parser = Parser::new(&mut parser_input);
+  let color = parse_color(&mut parser).ok()?;
+  let position = parser.position().byte_index();
+  Some((&input[position..], color))
+}
+
 #[allow(clippy::unnecessary_lazy_evaluations)]
-fn drop_shadow_parser(input: &str) -> IResult<&str, CssFilter> {
+fn drop_shadow_parser<'a>(input: &'a str, context: &PixelContext) -> IResult<&'a str, CssFilter> {
   let (drop_shadow_input, _) = tag("drop-shadow(")(input)?;
-  let drop_shadow_input = drop_shadow_input.trim();
-  let (offset_x_output, offset_x) = map_res(take_until(" "), pixel)(drop_shadow_input)?;
+  let (drop_shadow_output, drop_shadow_arguments) = function_arguments(drop_shadow_input)?;
+  let (drop_shadow_output, _) = char(')')(drop_shadow_output)?;
+  let drop_shadow_arguments = drop_shadow_arguments.trim();
+  // the color could be placed either before or after the lengths
+  let (drop_shadow_arguments, leading_shadow_color) = match color_prefix(drop_shadow_arguments) {
+    Some((arguments, color)) => (arguments.trim(), Some(color)),
+    None => (drop_shadow_arguments, None),
+  };
+  let (offset_x_output, offset_x) =
+    map_res(length_token, |s| pixel_with_context(s, context))(drop_shadow_arguments)?;
   let offset_x_output = offset_x_output.trim();
   let (offset_y_output, offset_y) =
-    map_res(take_till(|ch| ch == ' ' || ch == ')'), pixel)(offset_x_output)?;
+    map_res(length_token, |s| pixel_with_context(s, context))(offset_x_output)?;
   let offset_y_output = offset_y_output.trim();
   let (blur_radius_output, blur_radius) =
-    map_res(take_till(|ch| ch == ' ' || ch == ')'), pixel)(offset_y_output)
+    map_res(length_token, |s| pixel_with_context(s, context))(offset_y_output)
       .unwrap_or_else(|_: Err<Error<&str>>| (offset_y_output, 0.0f32));
-  let blur_radius_output = blur_radius_output.trim();
-  let is_rgb_fn = blur_radius_output.starts_with("rgb(") || blur_radius_output.starts_with("rgba(");
-  let (shadow_color_output, shadow_color_str) =
-    take_until(if is_rgb_fn { "))" } else { ")" })(blur_radius_output)?;
-  let shadow_color_str = shadow_color_str.trim();
-  static BLACK: RGBA = RGBA {
-    red: 0,
-    green: 0,
-    blue: 0,
-    alpha: 255,
-  };
-  let shadow_color = if !shadow_color_str.is_empty() {
-    let mut parser_input = ParserInput::new(shadow_color_str);
-    let mut parser = Parser::new(&mut parser_input);
-    let color = Color::parse(&mut parser).unwrap_or_else(|_| Color::RGBA(BLACK));
-    if let Color::RGBA(rgba) = color {
-      rgba
-    } else {
-      BLACK
+  // offsets could be negative, but negative blur radius is invalid
+  if blur_radius < 0.0 {
+    return Err(Err::Error(Error::new(offset_y_output, ErrorKind::Verify)));
+  }
+  let shadow_color_str = blur_radius_output.trim();
+  let after_color = color_prefix(shadow_color_str)
+    .map(|(rest, _)| rest.trim())
+    .unwrap_or(shadow_color_str);
+  for rest in [shadow_color_str, after_color] {
+    if box_shadow_syntax(rest, context).is_some() {
+      return Err(Err::Error(Error::new(rest, ErrorKind::Verify)));
     }
+  }
+  // nothing may follow the color, and the color can't be given twice
+  let rest = if leading_shadow_color.is_some() {
+    shadow_color_str
   } else {
-    BLACK
+    after_color
   };
-  let (mut drop_shadow_output, _) = char(')')(shadow_color_output.trim())?;
-  if is_rgb_fn {
-    let (trimmed_drop_shadow_output, _) = char(')')(drop_shadow_output)?;
-    drop_shadow_output = trimmed_drop_shadow_output;
+  if !rest.is_empty() {
+    return Err(Err::Error(Error::new(rest, ErrorKind::Verify)));
   }
+  // a parsed color keeps its alpha, even `transparent`, black is only the default of a missing color
+  let shadow_color = leading_shadow_color
+    .or_else(|| color_prefix(shadow_color_str).map(|(_, color)| color))
+    .unwrap_or(Color::RGBA(BLACK));
   Ok((
     drop_shadow_output.trim(),
     CssFilter::DropShadow(offset_x, offset_y, blur_radius, shadow_color),
   ))
 }
 
-pub fn css_filter(input: &str) -> IResult<&str, Vec<CssFilter>> {
+/// The `box-shadow` arguments `drop-shadow` doesn't take, the `inset` keyword or a spread radius
+fn box_shadow_syntax(input: &str, context: &PixelContext) -> Option<&'static str> {
+  let (_, token) = length_token(input).ok()?;
+  if token.eq_ignore_ascii_case("inset") {
+    Some("the `inset` keyword")
+  } else if !token.is_empty() && pixel_with_context(token, context).is_ok() {
+    Some("a spread radius")
+  } else {
+    None
+  }
+}
+
+/// `url(#id)`, only the references to the filters in the same document are supported
+fn url_parser(input: &str) -> IResult<&str, CssFilter> {
+  let (url_input, _) = tag("url(")(input)?;
+  let (url_output, url) = function_arguments(url_input)?;
+  let (url_output, _) = char(')')(url_output)?;
+  let url = url.trim();
+  // the reference could be quoted
+  let url = url
+    .strip_prefix('"')
+    .and_then(|u| u.strip_suffix('"'))
+    .or_else(|| url.strip_prefix('\'').and_then(|u| u.strip_suffix('\'')))
+    .unwrap_or(url);
+  match url.strip_prefix('#') {
+    Some(id) if !id.is_empty() => Ok((url_output.trim(), CssFilter::Url(id.to_owned()))),
+    _ => Err(Err::Error(Error::new(url_input, ErrorKind::Verify))),
+  }
+}
+
+#[cfg(test)]
+fn css_filter(input: &str) -> IResult<&str, Vec<CssFilter>> {
+  css_filter_with_context(input, &PixelContext::default())
+}
+
+pub fn css_filter_with_context<'a>(
+  input: &'a str,
+  context: &PixelContext,
+) -> IResult<&'a str, Vec<CssFilter>> {
   let mut filters = Vec::with_capacity(10);
   let mut input = input.trim();
-  while let Ok((output, filter)) = alt((
-    blur_parser,
-    brightness_parser,
-    contrast_parser,
-    drop_shadow_parser,
-    grayscale_parser,
-    hue_rotate_parser,
-    invert_parser,
-    opacity_parser,
-    saturate_parser,
-    sepia_parser,
-  ))(input)
-  {
+  if input == "none" {
+    return Ok(("", filters));
+  }
+  while let Ok((output, filter)) = filter_function(input, context) {
     input = output;
     filters.push(filter);
+    // generated strings sometimes separate the functions with commas, a comma is only skipped if a function follows it
+    if let Some(after_comma) = input.trim_start().strip_prefix(',') {
+      if filter_function(after_comma.trim_start(), context).is_ok() {
+        input = after_comma.trim_start();
+      }
+    }
   }
 
   Ok((input, filters))
 }
 
-pub(crate) fn css_filters_to_image_filter(filters: Vec<CssFilter>) -> Option<ImageFilter> {
-  filters.into_iter().fold(
-    Some(ImageFilter(ptr::null_mut())),
-    |image_filter, f| match f {
-      CssFilter::Blur(blur) => {
-        ImageFilter::make_blur(blur, blur, TileMode::Clamp, image_filter.as_ref())
+/// Parse a single filter function,
+/// if all of the parsers failed, the error from the one which went furthest is returned.
+fn filter_function<'a>(input: &'a str, context: &PixelContext) -> IResult<&'a str, CssFilter> {
+  let parsers: [&dyn Fn(&'a str) -> IResult<&'a str, CssFilter>; 11] = [
+    &|input| blur_parser(input, context),
+    &brightness_parser,
+    &contrast_parser,
+    &|input| drop_shadow_parser(input, context),
+    &grayscale_parser,
+    &hue_rotate_parser,
+    &invert_parser,
+    &opacity_parser,
+    &saturate_parser,
+    &sepia_parser,
+    &url_parser,
+  ];
+  let mut furthest = Error::new(input, ErrorKind::Alt);
+  for parser in parsers {
+    match parser(input) {
+      Err(Err::Error(e)) | Err(Err::Failure(e)) => {
+        if e.input.len() < furthest.input.len() {
+          furthest = e;
+        }
       }
-      CssFilter::Brightness(brightness) => {
-        let brightness = brightness.max(0.0);
-        ImageFilter::make_image_filter(
-          brightness,
-          0.0,
-          0.0,
-          0.0,
-          brightness,
-          0.0,
-          0.0,
-          0.0,
-          brightness,
-          1.0,
-          image_filter.as_ref(),
-        )
+      result => return result,
+    }
+  }
+  Err(Err::Error(furthest))
+}
+
+/// The name of a function none of the filter parsers know, like `sharpen` of `sharpen(2)`
+fn unknown_function(input: &str) -> IResult<&str, &str> {
+  let (output, name) =
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_')(input)?;
+  let (output, _) = char('(')(output)?;
+  Ok((output, name))
+}
+
+/// Parse the whole filter string, unlike `css_filter` the unconsumed input is reported as error
+/// The viewport-relative lengths are resolved against the `context`
+pub fn parse_filter_string<'a>(
+  input: &'a str,
+  context: &PixelContext,
+) -> Result<Vec<CssFilter>, ParseFilterError<'a>> {
+  let (remaining, filters) = css_filter_with_context(input, context)?;
+  let remaining = remaining.trim();
+  if !remaining.is_empty() {
+    // report the position inside of the filter function if it's recognized
+    let (failed_at, reason) = match filter_function(remaining, context) {
+      Err(Err::Error(e)) | Err(Err::Failure(e)) if e.input.len() < remaining.len() => {
+        match box_shadow_syntax(e.input, context) {
+          Some(argument) if remaining.starts_with("drop-shadow(") => {
+            (e.input, ParseFilterError::BoxShadowSyntax(argument))
+          }
+          _ => (e.input, ParseFilterError::Nom(Err::Error(e))),
+        }
       }
-      CssFilter::Contrast(contrast) => {
-        let amt = contrast.max(0.0);
-        let mut ramp = [0u8; 256];
-        ramp.iter_mut().take(256).enumerate().for_each(|(i, v)| {
-          let orig = i as f32;
-          *v = (127.0 + amt * (orig - 127.0)) as u8;
-        });
-        let ramp = Some(&ramp);
-        ImageFilter::from_argb(None, ramp, ramp, ramp, image_filter.as_ref())
+      _ => match unknown_function(remaining) {
+        Ok((_, name)) => (remaining, ParseFilterError::UnknownFunction(name)),
+        Err(_) => (remaining, ParseFilterError::TrailingInput(remaining)),
+      },
+    };
+    return Err(ParseFilterError::At {
+      offset: failed_at.as_ptr() as usize - input.as_ptr() as usize,
+      reason: Box::new(reason),
+    });
+  }
+  Ok(filters)
+}
+
+/// `brightness()` scales the color channels, the alpha is left untouched
+fn brightness_matrix(brightness: f32) -> [f32; 20] {
+  let b = brightness.max(0.0);
+  [
+    b, 0.0, 0.0, 0.0, 0.0, //
+    0.0, b, 0.0, 0.0, 0.0, //
+    0.0, 0.0, b, 0.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0, 0.0,
+  ]
+}
+
+/// `contrast()` scales the color channels around the mid gray, `out = (in - 0.5) * c + 0.5`
+fn contrast_matrix(contrast: f32) -> [f32; 20] {
+  let c = contrast.max(0.0);
+  let offset = 0.5 - 0.5 * c;
+  [
+    c, 0.0, 0.0, 0.0, offset, //
+    0.0, c, 0.0, 0.0, offset, //
+    0.0, 0.0, c, 0.0, offset, //
+    0.0, 0.0, 0.0, 1.0, 0.0,
+  ]
+}
+
+/// `saturate()` mixes the color channels with the luminance, the alpha is left untouched
+fn saturate_matrix(saturate: f32) -> [f32; 20] {
+  let s = saturate.max(0.0);
+  // the luminance weights of the channels that are mixed into the others
+  let (r, g, b) = (0.2126 * (1.0 - s), 0.7152 * (1.0 - s), 0.0722 * (1.0 - s));
+  let (red, green, blue) = (r + s, g + s, b + s);
+  [
+    red, g, b, 0.0, 0.0, //
+    r, green, b, 0.0, 0.0, //
+    r, g, blue, 0.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0, 0.0,
+  ]
+}
+
+/// Chain the filters into a single Skia image filter, each filter takes the output of the previous one as input.
+/// The first filter of the list reads the drawn source, so `blur(2px) brightness(2)` brightens the blurred pixels
+/// while `brightness(2) blur(2px)` blurs the brightened ones, like CSS.
+/// The `url(#id)` filters are looked up in `url_filters`, the unregistered ones are skipped.
+/// The `currentColor` shadows are drawn with `current_color`, black if absent.
+/// Without `clamp_color_matrices` the `brightness()`, `contrast()` and `saturate()` results are not clamped to `[0, 1]`
+pub(crate) fn css_filters_to_image_filter(
+  filters: &[CssFilter],
+  current_color: Option<RGBA>,
+  url_filters: &UrlFilterRegistry,
+  clamp_color_matrices: bool,
+) -> Option<ImageFilter> {
+  filters.iter().fold(
+    Some(ImageFilter(ptr::null_mut())),
+    |image_filter, f| match *f {
+      // `blur(0)` is a no-op, keep the filters before it
+      CssFilter::Blur(blur) if blur <= 0.0 => image_filter,
+      CssFilter::Blur(blur) => {
+        let sigma = blur.min(MAX_BLUR_SIGMA);
+        ImageFilter::make_blur(sigma, sigma, TileMode::Clamp, image_filter.as_ref())
       }
+      CssFilter::Brightness(brightness) => ImageFilter::make_color_matrix(
+        &brightness_matrix(brightness),
+        clamp_color_matrices,
+        image_filter.as_ref(),
+      ),
+      CssFilter::Contrast(contrast) => ImageFilter::make_color_matrix(
+        &contrast_matrix(contrast),
+        clamp_color_matrices,
+        image_filter.as_ref(),
+      ),
       CssFilter::DropShadow(offset_x, offset_y, blur_radius, shadow_color) => {
-        let sigma = blur_radius / 2.0;
+        let shadow_color = match shadow_color {
+          Color::RGBA(rgba) => rgba,
+          Color::CurrentColor => current_color.unwrap_or(BLACK),
+        };
+        let sigma = blur_radius * DROP_SHADOW_RADIUS_TO_SIGMA;
+        // an invisible shadow is a no-op, keep the filters before it
         if shadow_color.alpha == 0 {
-          return None;
+          return image_filter;
         }
         if blur_radius == 0f32 && offset_x == 0f32 && offset_y == 0f32 {
-          return None;
+          return image_filter;
         }
         ImageFilter::make_drop_shadow(
           offset_x,
@@ -377,22 +901,15 @@ pub(crate) fn css_filters_to_image_filter(filters: Vec<CssFilter>) -> Option<Ima
           image_filter.as_ref(),
         )
       }
-      CssFilter::Saturate(amt) => {
-        let amt = amt.max(0.0);
-        ImageFilter::make_image_filter(
-          0.2126 + 0.7874 * amt,
-          0.7152 - 0.7152 * amt,
-          0.0722 - 0.0722 * amt,
-          0.2126 - 0.2126 * amt,
-          0.7152 + 0.2848 * amt,
-          0.0722 - 0.0722 * amt,
-          0.2126 - 0.2126 * amt,
-          0.7152 - 0.7152 * amt,
-          0.0722 + 0.9278 * amt,
-          1.0,
-          image_filter.as_ref(),
-        )
-      }
+      CssFilter::Saturate(amt) => ImageFilter::make_color_matrix(
+        &saturate_matrix(amt),
+        clamp_color_matrices,
+        image_filter.as_ref(),
+      ),
+      CssFilter::Url(ref id) => match url_filters.get(id) {
+        Some(url_filter) => ImageFilter::compose(url_filter, image_filter.as_ref()),
+        None => image_filter,
+      },
       CssFilter::Sepia(amt) => {
         let amt = 1.0 - amt.max(0.0).min(1.0);
         ImageFilter::make_image_filter(
@@ -418,6 +935,68 @@ fn parse_empty() {
   assert_eq!(css_filter(""), Ok(("", vec![])));
 }
 
+#[test]
+fn parse_none() {
+  assert_eq!(css_filter("none"), Ok(("", vec![])));
+  assert_eq!(css_filter("  none "), Ok(("", vec![])));
+  assert_eq!(css_filter("garbage"), Ok(("garbage", vec![])));
+  assert_eq!(css_filter("frobnicate(1)"), Ok(("frobnicate(1)", vec![])));
+  assert_eq!(
+    css_filter("blur(2px) none"),
+    Ok(("none", vec![CssFilter::Blur(2.0)]))
+  );
+}
+
+#[test]
+fn parse_filter_string_trailing_input() {
+  assert_eq!(
+    parse_filter_string("blur(2px) invert(1)", &PixelContext::default()).unwrap(),
+    vec![CssFilter::Blur(2.0), CssFilter::Invert(1.0)]
+  );
+  assert_eq!(
+    parse_filter_string("none", &PixelContext::default()).unwrap(),
+    vec![]
+  );
+  assert_eq!(
+    parse_filter_string("  ", &PixelContext::default()).unwrap(),
+    vec![]
+  );
+  assert!(matches!(
+    parse_filter_string("blur(2px) frobnicate(1)", &PixelContext::default()),
+    Err(ParseFilterError::At { offset: 10, reason }) if matches!(*reason, ParseFilterError::UnknownFunction("frobnicate"))
+  ));
+  assert!(matches!(
+    parse_filter_string("garbage", &PixelContext::default()),
+    Err(ParseFilterError::At { offset: 0, reason }) if matches!(*reason, ParseFilterError::TrailingInput("garbage"))
+  ));
+}
+
+#[test]
+fn parse_filter_string_unknown_function() {
+  match parse_filter_string("sharpen(2)", &PixelContext::default()) {
+    Err(ParseFilterError::At { offset: 0, reason }) => {
+      assert!(matches!(
+        *reason,
+        ParseFilterError::UnknownFunction("sharpen")
+      ));
+      assert_eq!(
+        reason.to_string(),
+        "[`sharpen`] is not a known filter function"
+      );
+    }
+    other => panic!("unexpected result {other:?}"),
+  }
+  assert!(matches!(
+    parse_filter_string("invert(1) sharpen (2)", &PixelContext::default()),
+    Err(ParseFilterError::At { offset: 10, reason }) if matches!(*reason, ParseFilterError::TrailingInput("sharpen (2)"))
+  ));
+  // a known function with invalid arguments still reports the arguments
+  assert!(matches!(
+    parse_filter_string("blur(2px", &PixelContext::default()),
+    Err(ParseFilterError::At { reason, .. }) if !matches!(*reason, ParseFilterError::UnknownFunction(_))
+  ));
+}
+
 #[test]
 fn parse_blur() {
   assert_eq!(
@@ -442,117 +1021,917 @@ fn parse_blur() {
 #[test]
 fn drop_shadow_parse() {
   assert_eq!(
-    drop_shadow_parser("drop-shadow(2px 2px)"),
+    drop_shadow_parser("drop-shadow(2px 2px)", &PixelContext::default()),
     Ok((
       "",
-      CssFilter::DropShadow(2.0f32, 2.0f32, 0.0f32, RGBA::new(0, 0, 0, 255))
+      CssFilter::DropShadow(2.0f32, 2.0f32, 0.0f32, Color::RGBA(RGBA::new(0, 0, 0, 255)))
     ))
   );
   assert_eq!(
-    drop_shadow_parser("drop-shadow(2px 2px 5px)"),
+    drop_shadow_parser("drop-shadow(2px 2px 5px)", &PixelContext::default()),
     Ok((
       "",
-      CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, RGBA::new(0, 0, 0, 255))
+      CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, Color::RGBA(RGBA::new(0, 0, 0, 255)))
     ))
   );
 
   assert_eq!(
-    drop_shadow_parser("drop-shadow(2px 2px 5px #2F14DF)"),
+    drop_shadow_parser("drop-shadow(2px 2px 5px #2F14DF)", &PixelContext::default()),
     Ok((
       "",
-      CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, RGBA::new(47, 20, 223, 255))
+      CssFilter::DropShadow(
+        2.0f32,
+        2.0f32,
+        5.0f32,
+        Color::RGBA(RGBA::new(47, 20, 223, 255))
+      )
     ))
   );
 
   assert_eq!(
-    drop_shadow_parser("drop-shadow(2px 2px 5px rgba(47, 20, 223, 255))"),
+    drop_shadow_parser(
+      "drop-shadow(2px 2px 5px rgba(47, 20, 223, 255))",
+      &PixelContext::default()
+    ),
     Ok((
       "",
-      CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, RGBA::new(47, 20, 223, 255))
+      CssFilter::DropShadow(
+        2.0f32,
+        2.0f32,
+        5.0f32,
+        Color::RGBA(RGBA::new(47, 20, 223, 255))
+      )
     ))
   );
 }
 
 #[test]
-fn composite_parse() {
+fn drop_shadow_parse_negative_offsets() {
   assert_eq!(
-    css_filter("blur(1.5rem) brightness(2)"),
+    drop_shadow_parser("drop-shadow(-2px -4px)", &PixelContext::default()),
     Ok((
       "",
-      vec![CssFilter::Blur(24.0), CssFilter::Brightness(2.0f32)]
+      CssFilter::DropShadow(
+        -2.0f32,
+        -4.0f32,
+        0.0f32,
+        Color::RGBA(RGBA::new(0, 0, 0, 255))
+      )
     ))
   );
-
   assert_eq!(
-    css_filter("brightness(2) blur(1.5rem)"),
+    drop_shadow_parser("drop-shadow(-2px 4px 6px #fff)", &PixelContext::default()),
     Ok((
       "",
-      vec![CssFilter::Brightness(2.0f32), CssFilter::Blur(24.0)]
+      CssFilter::DropShadow(
+        -2.0f32,
+        4.0f32,
+        6.0f32,
+        Color::RGBA(RGBA::new(255, 255, 255, 255))
+      )
     ))
   );
-
   assert_eq!(
-    css_filter("drop-shadow(2px 2px 5px rgba(47, 20, 223, 255)) brightness(2) blur(1.5rem)"),
+    drop_shadow_parser("drop-shadow(-2px -4px 3px black)", &PixelContext::default()),
     Ok((
       "",
-      vec![
-        CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, RGBA::new(47, 20, 223, 255)),
-        CssFilter::Brightness(2.0f32),
-        CssFilter::Blur(24.0)
-      ]
+      CssFilter::DropShadow(
+        -2.0f32,
+        -4.0f32,
+        3.0f32,
+        Color::RGBA(RGBA::new(0, 0, 0, 255))
+      )
     ))
   );
+  assert!(drop_shadow_parser("drop-shadow(2px 2px -3px black)", &PixelContext::default()).is_err());
+}
 
+#[test]
+fn drop_shadow_parse_current_color() {
   assert_eq!(
-    css_filter("brightness(2) drop-shadow(2px 2px 5px rgba(47, 20, 223, 255)) blur(1.5rem)"),
+    drop_shadow_parser(
+      "drop-shadow(1px 2px currentColor)",
+      &PixelContext::default()
+    ),
     Ok((
       "",
-      vec![
-        CssFilter::Brightness(2.0f32),
-        CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, RGBA::new(47, 20, 223, 255)),
-        CssFilter::Blur(24.0)
-      ]
+      CssFilter::DropShadow(1.0f32, 2.0f32, 0.0f32, Color::CurrentColor)
     ))
   );
-
   assert_eq!(
-    css_filter("brightness(2) blur(1.5rem) drop-shadow(2px 2px 5px rgba(47, 20, 223, 255))"),
+    drop_shadow_parser(
+      "drop-shadow(currentcolor 1px 2px)",
+      &PixelContext::default()
+    ),
     Ok((
       "",
-      vec![
-        CssFilter::Brightness(2.0f32),
-        CssFilter::Blur(24.0),
-        CssFilter::DropShadow(2.0f32, 2.0f32, 5.0f32, RGBA::new(47, 20, 223, 255)),
-      ]
+      CssFilter::DropShadow(1.0f32, 2.0f32, 0.0f32, Color::CurrentColor)
     ))
   );
 }
 
 #[test]
-fn hue_rotate_parse() {
+fn uses_current_color_parse() {
+  let context = PixelContext::default();
+  let uses = |input| uses_current_color(&parse_filter_string(input, &context).unwrap());
+  assert!(uses("blur(2px) drop-shadow(1px 1px currentColor)"));
+  assert!(!uses("drop-shadow(1px 1px black)"));
+  // only the parsed shadow colors count, not the `url()` references
+  assert!(!uses("url(#currentcolor) drop-shadow(1px 1px)"));
   assert_eq!(
-    hue_rotate_parser("hue-rotate(0)"),
-    Ok(("", CssFilter::HueRotate(0.0f32)))
+    filters_to_string(&[CssFilter::DropShadow(1.0, 2.0, 0.0, Color::CurrentColor)]),
+    "drop-shadow(1px 2px 0px currentcolor)"
+  );
+}
+
+#[test]
+fn drop_shadow_parse_leading_color() {
+  assert_eq!(
+    drop_shadow_parser("drop-shadow(red 1px 2px)", &PixelContext::default()),
+    drop_shadow_parser("drop-shadow(1px 2px red)", &PixelContext::default())
   );
   assert_eq!(
-    hue_rotate_parser("hue-rotate(90deg)"),
-    Ok(("", CssFilter::HueRotate(90.0f32)))
+    drop_shadow_parser("drop-shadow(red 2px 2px 3px)", &PixelContext::default()),
+    Ok((
+      "",
+      CssFilter::DropShadow(
+        2.0f32,
+        2.0f32,
+        3.0f32,
+        Color::RGBA(RGBA::new(255, 0, 0, 255))
+      )
+    ))
   );
   assert_eq!(
-    hue_rotate_parser("hue-rotate(-0.25turn)"),
-    Ok(("", CssFilter::HueRotate(-90.0f32)))
+    drop_shadow_parser(
+      "drop-shadow(rgba(47, 20, 223, 255) 2px 2px 5px)",
+      &PixelContext::default()
+    ),
+    drop_shadow_parser(
+      "drop-shadow(2px 2px 5px rgba(47, 20, 223, 255))",
+      &PixelContext::default()
+    )
   );
   assert_eq!(
-    hue_rotate_parser("hue-rotate(3.141592653rad)"),
-    Ok(("", CssFilter::HueRotate(180.0f32)))
+    drop_shadow_parser("drop-shadow(#2F14DF 2px 2px)", &PixelContext::default()),
+    Ok((
+      "",
+      CssFilter::DropShadow(
+        2.0f32,
+        2.0f32,
+        0.0f32,
+        Color::RGBA(RGBA::new(47, 20, 223, 255))
+      )
+    ))
   );
 }
 
 #[test]
-fn parse_number_or_percentage() {
-  assert_eq!(number_percentage("2"), Ok(("", 2f32)));
-  assert_eq!(number_percentage("1.11"), Ok(("", 1.11f32)));
-  assert_eq!(number_percentage("20%"), Ok(("", 0.2f32)));
-  assert_eq!(number_percentage("-20%"), Ok(("", -0.2f32)));
-  assert_eq!(number_percentage("-0.1"), Ok(("", -0.1f32)));
+fn drop_shadow_parse_hex_alpha() {
+  let context = PixelContext::default();
+  for (input, expected) in [
+    ("drop-shadow(1px 1px #00000080)", RGBA::new(0, 0, 0, 128)),
+    ("drop-shadow(1px 1px #0008)", RGBA::new(0, 0, 0, 0x88)),
+    ("drop-shadow(#ff000001 1px 1px)", RGBA::new(255, 0, 0, 1)),
+    (
+      "drop-shadow(1px 1px 2px transparent)",
+      RGBA::new(0, 0, 0, 0),
+    ),
+    ("drop-shadow(1px 1px)", RGBA::new(0, 0, 0, 255)),
+  ] {
+    match drop_shadow_parser(input, &context) {
+      Ok(("", CssFilter::DropShadow(_, _, _, color))) => {
+        assert_eq!(color, Color::RGBA(expected), "{input}")
+      }
+      result => panic!("{input}: {result:?}"),
+    }
+  }
+}
+
+#[test]
+fn drop_shadow_parse_color_functions() {
+  let context = PixelContext::default();
+  assert_eq!(
+    drop_shadow_parser("drop-shadow(1px 1px oklch(0.7 0.15 200))", &context),
+    Ok((
+      "",
+      CssFilter::DropShadow(1.0, 1.0, 0.0, Color::RGBA(RGBA::new(0, 185, 195, 255)))
+    ))
+  );
+  assert_eq!(
+    drop_shadow_parser(
+      "drop-shadow(lab(54.29 80.82 69.88 / 0.5) 1px 1px 2px)",
+      &context
+    ),
+    Ok((
+      "",
+      CssFilter::DropShadow(1.0, 1.0, 2.0, Color::RGBA(RGBA::new(255, 0, 0, 128)))
+    ))
+  );
+}
+
+#[test]
+fn drop_shadow_parse_color() {
+  assert_eq!(
+    drop_shadow_parser(
+      "drop-shadow(2px 2px 5px hsl(120, 50%, 50%))",
+      &PixelContext::default()
+    ),
+    Ok((
+      "",
+      CssFilter::DropShadow(
+        2.0f32,
+        2.0f32,
+        5.0f32,
+        Color::RGBA(RGBA::new(64, 191, 64, 255))
+      )
+    ))
+  );
+  assert_eq!(
+    drop_shadow_parser(
+      "drop-shadow(2px 2px hsla(120, 50%, 50%, 0.5))",
+      &PixelContext::default()
+    ),
+    Ok((
+      "",
+      CssFilter::DropShadow(
+        2.0f32,
+        2.0f32,
+        0.0f32,
+        Color::RGBA(RGBA::new(64, 191, 64, 128))
+      )
+    ))
+  );
+  assert_eq!(
+    drop_shadow_parser("drop-shadow(1px 1px red)", &PixelContext::default()),
+    Ok((
+      "",
+      CssFilter::DropShadow(
+        1.0f32,
+        1.0f32,
+        0.0f32,
+        Color::RGBA(RGBA::new(255, 0, 0, 255))
+      )
+    ))
+  );
+  assert_eq!(
+    drop_shadow_parser(
+      "drop-shadow(1px 1px 2px rebeccapurple)",
+      &PixelContext::default()
+    ),
+    Ok((
+      "",
+      CssFilter::DropShadow(
+        1.0f32,
+        1.0f32,
+        2.0f32,
+        Color::RGBA(RGBA::new(102, 51, 153, 255))
+      )
+    ))
+  );
+  assert_eq!(
+    drop_shadow_parser(
+      "drop-shadow(1px 1px 2px currentColor)",
+      &PixelContext::default()
+    ),
+    Ok((
+      "",
+      CssFilter::DropShadow(1.0f32, 1.0f32, 2.0f32, Color::CurrentColor)
+    ))
+  );
+  assert_eq!(
+    css_filter("drop-shadow(1px 1px hsl(0, 100%, 50%)) invert(1)"),
+    Ok((
+      "",
+      vec![
+        CssFilter::DropShadow(
+          1.0f32,
+          1.0f32,
+          0.0f32,
+          Color::RGBA(RGBA::new(255, 0, 0, 255))
+        ),
+        CssFilter::Invert(1.0)
+      ]
+    ))
+  );
+}
+
+#[test]
+fn composite_parse() {
+  assert_eq!(
+    css_filter("blur(1.5rem) brightness(2)"),
+    Ok((
+      "",
+      vec![CssFilter::Blur(24.0), CssFilter::Brightness(2.0f32)]
+    ))
+  );
+
+  assert_eq!(
+    css_filter("brightness(2) blur(1.5rem)"),
+    Ok((
+      "",
+      vec![CssFilter::Brightness(2.0f32), CssFilter::Blur(24.0)]
+    ))
+  );
+
+  assert_eq!(
+    css_filter("drop-shadow(2px 2px 5px rgba(47, 20, 223, 255)) brightness(2) blur(1.5rem)"),
+    Ok((
+      "",
+      vec![
+        CssFilter::DropShadow(
+          2.0f32,
+          2.0f32,
+          5.0f32,
+          Color::RGBA(RGBA::new(47, 20, 223, 255))
+        ),
+        CssFilter::Brightness(2.0f32),
+        CssFilter::Blur(24.0)
+      ]
+    ))
+  );
+
+  assert_eq!(
+    css_filter("brightness(2) drop-shadow(2px 2px 5px rgba(47, 20, 223, 255)) blur(1.5rem)"),
+    Ok((
+      "",
+      vec![
+        CssFilter::Brightness(2.0f32),
+        CssFilter::DropShadow(
+          2.0f32,
+          2.0f32,
+          5.0f32,
+          Color::RGBA(RGBA::new(47, 20, 223, 255))
+        ),
+        CssFilter::Blur(24.0)
+      ]
+    ))
+  );
+
+  assert_eq!(
+    css_filter("brightness(2) blur(1.5rem) drop-shadow(2px 2px 5px rgba(47, 20, 223, 255))"),
+    Ok((
+      "",
+      vec![
+        CssFilter::Brightness(2.0f32),
+        CssFilter::Blur(24.0),
+        CssFilter::DropShadow(
+          2.0f32,
+          2.0f32,
+          5.0f32,
+          Color::RGBA(RGBA::new(47, 20, 223, 255))
+        ),
+      ]
+    ))
+  );
+}
+
+#[test]
+fn hue_rotate_parse() {
+  assert_eq!(
+    hue_rotate_parser("hue-rotate(0)"),
+    Ok(("", CssFilter::HueRotate(0.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate(90deg)"),
+    Ok(("", CssFilter::HueRotate(90.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate(-0.25turn)"),
+    Ok(("", CssFilter::HueRotate(-90.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate(3.141592653rad)"),
+    Ok(("", CssFilter::HueRotate(180.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate(100grad)"),
+    Ok(("", CssFilter::HueRotate(90.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate(1.5turn)"),
+    Ok(("", CssFilter::HueRotate(540.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate(-45deg)"),
+    Ok(("", CssFilter::HueRotate(-45.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate( 30 )"),
+    Ok(("", CssFilter::HueRotate(30.0f32)))
+  );
+  assert_eq!(
+    hue_rotate_parser("hue-rotate()"),
+    Ok(("", CssFilter::HueRotate(0.0f32)))
+  );
+  assert!(hue_rotate_parser("hue-rotate(30px)").is_err());
+  assert_eq!(
+    css_filter("hue-rotate(90deg) invert(1)"),
+    Ok((
+      "",
+      vec![CssFilter::HueRotate(90.0f32), CssFilter::Invert(1.0)]
+    ))
+  );
+}
+
+#[test]
+fn parse_number_or_percentage() {
+  assert_eq!(number_percentage("2"), Ok(("", 2f32)));
+  assert_eq!(number_percentage("1.11"), Ok(("", 1.11f32)));
+  assert_eq!(number_percentage("20%"), Ok(("", 0.2f32)));
+  assert_eq!(number_percentage("-20%"), Ok(("", -0.2f32)));
+  assert_eq!(number_percentage("-0.1"), Ok(("", -0.1f32)));
+  assert_eq!(number_percentage("2 )"), Ok((" )", 2f32)));
+  assert_eq!(
+    number_percentage("2xyz)"),
+    Err(Err::Error(Error::new("xyz)", ErrorKind::Verify)))
+  );
+  assert_eq!(
+    number_percentage("20%xyz"),
+    Err(Err::Error(Error::new("xyz", ErrorKind::Verify)))
+  );
+}
+
+#[test]
+fn number_percentage_trailing_garbage() {
+  assert_eq!(
+    css_filter("brightness(2xyz)"),
+    Ok(("brightness(2xyz)", vec![]))
+  );
+  assert!(parse_filter_string("brightness(2xyz)", &PixelContext::default()).is_err());
+  assert_eq!(
+    css_filter("brightness(2 )"),
+    Ok(("", vec![CssFilter::Brightness(2.0)]))
+  );
+}
+
+#[test]
+fn grayscale_parse() {
+  assert_eq!(
+    css_filter("grayscale(0.5)"),
+    Ok(("", vec![CssFilter::Grayscale(0.5)]))
+  );
+  assert_eq!(
+    css_filter("grayscale(50%)"),
+    Ok(("", vec![CssFilter::Grayscale(0.5)]))
+  );
+  assert_eq!(
+    css_filter("  grayscale( 50% )  "),
+    Ok(("", vec![CssFilter::Grayscale(0.5)]))
+  );
+  assert_eq!(
+    css_filter("grayscale(150%)"),
+    Ok(("", vec![CssFilter::Grayscale(1.0)]))
+  );
+  assert_eq!(
+    css_filter("grayscale()"),
+    Ok(("", vec![CssFilter::Grayscale(1.0)]))
+  );
+  assert_eq!(
+    css_filter("grayscale( )"),
+    Ok(("", vec![CssFilter::Grayscale(1.0)]))
+  );
+}
+
+#[test]
+fn sepia_parse() {
+  assert_eq!(
+    css_filter("sepia(1)"),
+    Ok(("", vec![CssFilter::Sepia(1.0)]))
+  );
+  assert_eq!(
+    css_filter("sepia(100%)"),
+    Ok(("", vec![CssFilter::Sepia(1.0)]))
+  );
+  assert_eq!(
+    css_filter("sepia( 0.3 )"),
+    Ok(("", vec![CssFilter::Sepia(0.3)]))
+  );
+  assert_eq!(
+    css_filter("sepia(3)"),
+    Ok(("", vec![CssFilter::Sepia(1.0)]))
+  );
+  assert_eq!(css_filter("sepia()"), Ok(("", vec![CssFilter::Sepia(1.0)])));
+  assert!(sepia_parser("sepia(-0.3)").is_err());
+  assert!(sepia_parser("sepia(-30%)").is_err());
+}
+
+#[test]
+fn invert_parse() {
+  assert_eq!(
+    css_filter("invert(1)"),
+    Ok(("", vec![CssFilter::Invert(1.0)]))
+  );
+  assert_eq!(
+    css_filter("invert(50%)"),
+    Ok(("", vec![CssFilter::Invert(0.5)]))
+  );
+  assert_eq!(
+    css_filter("invert()"),
+    Ok(("", vec![CssFilter::Invert(1.0)]))
+  );
+  assert_eq!(
+    css_filter("invert(200%)"),
+    Ok(("", vec![CssFilter::Invert(1.0)]))
+  );
+  assert_eq!(
+    css_filter("invert(100%) blur(2px)"),
+    Ok(("", vec![CssFilter::Invert(1.0), CssFilter::Blur(2.0)]))
+  );
+}
+
+#[test]
+fn saturate_parse() {
+  assert_eq!(
+    css_filter("saturate(200%)"),
+    Ok(("", vec![CssFilter::Saturate(2.0)]))
+  );
+  assert_eq!(
+    css_filter("saturate()"),
+    Ok(("", vec![CssFilter::Saturate(1.0)]))
+  );
+  assert!(saturate_parser("saturate(-1)").is_err());
+  assert_eq!(
+    css_filter("saturate(3) contrast(50%)"),
+    Ok(("", vec![CssFilter::Saturate(3.0), CssFilter::Contrast(0.5)]))
+  );
+  assert_eq!(
+    css_filter("contrast(50%) saturate(3)"),
+    Ok(("", vec![CssFilter::Contrast(0.5), CssFilter::Saturate(3.0)]))
+  );
+}
+
+#[test]
+fn opacity_parse() {
+  assert_eq!(
+    css_filter("opacity(25%)"),
+    Ok(("", vec![CssFilter::Opacity(0.25)]))
+  );
+  assert_eq!(
+    css_filter("opacity(0.25)"),
+    Ok(("", vec![CssFilter::Opacity(0.25)]))
+  );
+  assert_eq!(
+    css_filter("opacity()"),
+    Ok(("", vec![CssFilter::Opacity(1.0)]))
+  );
+  assert_eq!(
+    css_filter("opacity(2)"),
+    Ok(("", vec![CssFilter::Opacity(1.0)]))
+  );
+  assert_eq!(
+    css_filter("brightness(2) opacity(50%)"),
+    Ok((
+      "",
+      vec![CssFilter::Brightness(2.0), CssFilter::Opacity(0.5)]
+    ))
+  );
+}
+
+#[test]
+fn calc_parse() {
+  assert_eq!(pixel("calc(1rem + 2px)").unwrap(), 18.0);
+  assert_eq!(pixel("calc(2px * 3)").unwrap(), 6.0);
+  assert_eq!(pixel("calc((1px + 2px) * 2 - 1px / 2)").unwrap(), 5.5);
+  assert_eq!(pixel("calc(2 * calc(4px - 1px))").unwrap(), 6.0);
+  assert!(pixel("calc(").is_err());
+  assert!(pixel("calc(1px + )").is_err());
+  assert!(pixel("calc(1px + 2)").is_err());
+  assert!(pixel("calc(1px * 2px)").is_err());
+  assert!(pixel("calc(1px / 0)").is_err());
+  assert_eq!(
+    css_filter("blur(calc(1rem + 2px))"),
+    Ok(("", vec![CssFilter::Blur(18.0)]))
+  );
+  assert_eq!(
+    css_filter("drop-shadow(calc(2px * 3) -2px calc(1px + 1px) red)"),
+    Ok((
+      "",
+      vec![CssFilter::DropShadow(
+        6.0,
+        -2.0,
+        2.0,
+        Color::RGBA(RGBA {
+          red: 255,
+          green: 0,
+          blue: 0,
+          alpha: 255
+        })
+      )]
+    ))
+  );
+  assert!(parse_filter_string("blur(calc(1px +)", &PixelContext::default()).is_err());
+}
+
+#[test]
+fn viewport_units_parse() {
+  let context = PixelContext::new(500.0, 200.0);
+  assert_eq!(pixel_with_context("2vw", &context).unwrap(), 10.0);
+  assert_eq!(pixel_with_context("5vh", &context).unwrap(), 10.0);
+  assert_eq!(pixel_with_context("10vmin", &context).unwrap(), 20.0);
+  assert_eq!(pixel_with_context("10vmax", &context).unwrap(), 50.0);
+  assert_eq!(
+    pixel_with_context("calc(2vw + 1px)", &context).unwrap(),
+    11.0
+  );
+  assert_eq!(
+    parse_filter_string("blur(2vw)", &context).unwrap(),
+    vec![CssFilter::Blur(10.0)]
+  );
+  // viewport units are unresolvable without the viewport
+  assert!(pixel("2vw").is_err());
+  assert!(parse_filter_string("blur(2vw)", &PixelContext::default()).is_err());
+}
+
+#[test]
+fn font_relative_units_parse() {
+  let context = PixelContext {
+    font_size_px: 20.0,
+    ..Default::default()
+  };
+  assert_eq!(pixel_with_context("2em", &context).unwrap(), 40.0);
+  assert_eq!(pixel_with_context("2rem", &context).unwrap(), 32.0);
+  assert_eq!(pixel_with_context("2pc", &context).unwrap(), 32.0);
+  assert_eq!(
+    pixel_with_context("calc(1em + 1rem)", &context).unwrap(),
+    36.0
+  );
+  assert_eq!(
+    parse_filter_string("blur(1em)", &context).unwrap(),
+    vec![CssFilter::Blur(20.0)]
+  );
+  // unknown font size falls back to the medium font size
+  assert_eq!(pixel("2em").unwrap(), 32.0);
+}
+
+#[test]
+fn percentage_length_parse() {
+  assert!(matches!(
+    pixel("50%"),
+    Err(ParseFilterError::UnitParseError("%"))
+  ));
+  assert!(css_filter("blur(50%)").unwrap().1.is_empty());
+  assert!(parse_filter_string("blur(50%)", &PixelContext::default()).is_err());
+}
+
+#[test]
+fn parse_filter_string_error_offset() {
+  let input = "blur(2px) contrast(abc)";
+  match parse_filter_string(input, &PixelContext::default()) {
+    Err(ref err @ ParseFilterError::At { offset, .. }) => {
+      assert_eq!(offset, input.find("abc").unwrap());
+      assert!(err.to_string().starts_with("invalid filter at column 20"));
+    }
+    other => panic!("unexpected result {other:?}"),
+  }
+  assert!(matches!(
+    parse_filter_string("invert(1) blur(calc(1px * 2px))", &PixelContext::default()),
+    Err(ParseFilterError::At { offset: 15, .. })
+  ));
+}
+
+#[test]
+fn filters_to_string_round_trip() {
+  let context = PixelContext::default();
+  let filters = parse_filter_string(
+    "blur(1.25rem) brightness(200%) contrast(50%) drop-shadow(2px -2px 5px rgba(47, 20, 223, 0.5)) grayscale(0.3) hue-rotate(0.5turn) invert() opacity(25%) saturate(3) sepia(1)",
+    &context,
+  )
+  .unwrap();
+  let serialized = filters_to_string(&filters);
+  assert_eq!(
+    serialized,
+    "blur(20px) brightness(2) contrast(0.5) drop-shadow(2px -2px 5px rgba(47,20,223,0.5019608)) grayscale(0.3) hue-rotate(180deg) invert(1) opacity(0.25) saturate(3) sepia(1)"
+  );
+  assert_eq!(parse_filter_string(&serialized, &context).unwrap(), filters);
+  assert_eq!(filters_to_string(&[]), "none");
+}
+
+#[test]
+fn multiple_drop_shadows_parse() {
+  assert_eq!(
+    css_filter("drop-shadow(1px 1px rgb(0,0,0)) drop-shadow(2px 2px rgb(255,0,0))"),
+    Ok((
+      "",
+      vec![
+        CssFilter::DropShadow(
+          1.0,
+          1.0,
+          0.0,
+          Color::RGBA(RGBA {
+            red: 0,
+            green: 0,
+            blue: 0,
+            alpha: 255
+          })
+        ),
+        CssFilter::DropShadow(
+          2.0,
+          2.0,
+          0.0,
+          Color::RGBA(RGBA {
+            red: 255,
+            green: 0,
+            blue: 0,
+            alpha: 255
+          })
+        )
+      ]
+    ))
+  );
+}
+
+#[test]
+fn url_parse() {
+  assert_eq!(
+    css_filter("url(#foo)"),
+    Ok(("", vec![CssFilter::Url("foo".to_owned())]))
+  );
+  assert_eq!(
+    css_filter("url( \"#foo\" )"),
+    Ok(("", vec![CssFilter::Url("foo".to_owned())]))
+  );
+  assert_eq!(
+    css_filter("url(#foo) blur(2px)"),
+    Ok((
+      "",
+      vec![CssFilter::Url("foo".to_owned()), CssFilter::Blur(2.0)]
+    ))
+  );
+  // only the references to the same document are supported
+  assert!(parse_filter_string("url(filters.svg#foo)", &PixelContext::default()).is_err());
+  assert!(parse_filter_string("url(#)", &PixelContext::default()).is_err());
+  assert_eq!(
+    filters_to_string(&[CssFilter::Url("foo".to_owned())]),
+    "url(#foo)"
+  );
+}
+
+#[test]
+fn scientific_notation_and_plus_sign_parse() {
+  assert_eq!(
+    css_filter("brightness(1e0)"),
+    Ok(("", vec![CssFilter::Brightness(1.0)]))
+  );
+  assert_eq!(
+    css_filter("contrast(+1.5)"),
+    Ok(("", vec![CssFilter::Contrast(1.5)]))
+  );
+  assert_eq!(
+    css_filter("blur(1.5e1px)"),
+    Ok(("", vec![CssFilter::Blur(15.0)]))
+  );
+  assert_eq!(pixel("+2px").unwrap(), 2.0);
+  assert_eq!(pixel("1E-1px").unwrap(), 0.1);
+  // `e` without digits belongs to the unit
+  assert_eq!(pixel("2em").unwrap(), 32.0);
+  assert_eq!(angle("1e1deg").unwrap(), 10.0);
+  assert_eq!(
+    css_filter("blur(calc(1e1px + 5px))"),
+    Ok(("", vec![CssFilter::Blur(15.0)]))
+  );
+}
+
+#[test]
+fn drop_shadow_rejects_box_shadow_syntax() {
+  let context = PixelContext::default();
+  for (input, argument, column) in [
+    ("drop-shadow(1px 1px 2px 3px black)", "a spread radius", 25),
+    ("drop-shadow(1px 1px 2px black 3px)", "a spread radius", 31),
+    (
+      "drop-shadow(inset 1px 1px black)",
+      "the `inset` keyword",
+      13,
+    ),
+    (
+      "drop-shadow(1px 1px black inset)",
+      "the `inset` keyword",
+      27,
+    ),
+  ] {
+    match parse_filter_string(input, &context) {
+      Err(ParseFilterError::At { offset, reason }) => {
+        assert_eq!(offset + 1, column, "{input}");
+        assert!(
+          matches!(*reason, ParseFilterError::BoxShadowSyntax(a) if a == argument),
+          "{input}: {reason}"
+        );
+      }
+      result => panic!("{input}: {result:?}"),
+    }
+  }
+  assert!(parse_filter_string("drop-shadow(1px 1px 2px black)", &context).is_ok());
+  assert!(parse_filter_string("drop-shadow(black 1px 1px)", &context).is_ok());
+}
+
+#[test]
+fn drop_shadow_rejects_extra_arguments() {
+  let context = PixelContext::default();
+  for (input, column) in [
+    ("drop-shadow(1px 1px red blue)", 25),
+    ("drop-shadow(1px 1px 2px red blue)", 29),
+    ("drop-shadow(red 1px 1px blue)", 25),
+    ("drop-shadow(1px 1px 2px red 3px 4px)", 29),
+  ] {
+    match parse_filter_string(input, &context) {
+      Err(ParseFilterError::At { offset, .. }) => assert_eq!(offset + 1, column, "{input}"),
+      result => panic!("{input}: {result:?}"),
+    }
+    assert_eq!(css_filter(input), Ok((input, vec![])), "{input}");
+  }
+}
+
+#[cfg(test)]
+fn apply_color_matrix(matrix: &[f32; 20], rgba: [f32; 4]) -> [f32; 4] {
+  apply_unclamped_color_matrix(matrix, rgba).map(|c| c.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+fn apply_unclamped_color_matrix(matrix: &[f32; 20], rgba: [f32; 4]) -> [f32; 4] {
+  let mut out = [0.0; 4];
+  for (row, channel) in out.iter_mut().enumerate() {
+    let m = &matrix[row * 5..row * 5 + 5];
+    *channel = m[0] * rgba[0] + m[1] * rgba[1] + m[2] * rgba[2] + m[3] * rgba[3] + m[4];
+  }
+  out
+}
+
+#[test]
+fn brightness_and_contrast_matrices() {
+  assert_eq!(
+    apply_color_matrix(&brightness_matrix(0.5), [0.8, 0.4, 0.2, 0.6]),
+    [0.4, 0.2, 0.1, 0.6]
+  );
+  assert_eq!(
+    apply_color_matrix(&brightness_matrix(-1.0), [0.8, 0.4, 0.2, 0.6]),
+    [0.0, 0.0, 0.0, 0.6]
+  );
+  // contrast(0) is mid gray whatever the color
+  assert_eq!(
+    apply_color_matrix(&contrast_matrix(0.0), [1.0, 0.0, 0.3, 1.0]),
+    [0.5, 0.5, 0.5, 1.0]
+  );
+  assert_eq!(
+    apply_color_matrix(&contrast_matrix(1.0), [1.0, 0.0, 0.3, 1.0]),
+    [1.0, 0.0, 0.3, 1.0]
+  );
+  // contrast(2) moves away from the mid gray on the same side
+  let [light, dark, gray, alpha] = apply_color_matrix(&contrast_matrix(2.0), [0.6, 0.4, 0.5, 0.5]);
+  assert!((light - 0.7).abs() < 1e-6);
+  assert!((dark - 0.3).abs() < 1e-6);
+  assert_eq!(gray, 0.5);
+  assert_eq!(alpha, 0.5);
+}
+
+#[test]
+fn unclamped_color_matrices() {
+  assert_eq!(
+    apply_unclamped_color_matrix(&brightness_matrix(2.0), [1.0, 0.5, 0.0, 1.0]),
+    [2.0, 1.0, 0.0, 1.0]
+  );
+  let [red, green, blue, alpha] =
+    apply_unclamped_color_matrix(&contrast_matrix(3.0), [1.0, 0.0, 0.5, 1.0]);
+  assert_eq!((red, green, blue, alpha), (2.0, -1.0, 0.5, 1.0));
+  // the luminance of a saturated color is kept, the other channels go out of range
+  let saturated = apply_unclamped_color_matrix(&saturate_matrix(3.0), [1.0, 0.0, 0.0, 1.0]);
+  assert!(saturated[0] > 1.0 && saturated[1] < 0.0 && saturated[2] < 0.0);
+  assert_eq!(
+    apply_color_matrix(&saturate_matrix(3.0), [1.0, 0.0, 0.0, 1.0]),
+    [1.0, 0.0, 0.0, 1.0]
+  );
+  let unchanged = apply_unclamped_color_matrix(&saturate_matrix(1.0), [0.8, 0.4, 0.2, 0.6]);
+  for (channel, expected) in unchanged.into_iter().zip([0.8, 0.4, 0.2, 0.6]) {
+    assert!((channel - expected).abs() < 1e-6);
+  }
+}
+
+#[test]
+fn comma_separated_filters() {
+  let expected = vec![CssFilter::Blur(2.0), CssFilter::Brightness(2.0)];
+  assert_eq!(
+    css_filter("blur(2px), brightness(2)"),
+    Ok(("", expected.clone()))
+  );
+  assert_eq!(
+    css_filter("blur(2px),brightness(2)"),
+    Ok(("", expected.clone()))
+  );
+  assert_eq!(css_filter("blur(2px) ,  brightness(2)"), Ok(("", expected)));
+  assert_eq!(
+    css_filter("blur(2px), brightness(2) invert(1)"),
+    Ok((
+      "",
+      vec![
+        CssFilter::Blur(2.0),
+        CssFilter::Brightness(2.0),
+        CssFilter::Invert(1.0)
+      ]
+    ))
+  );
+  let context = PixelContext::default();
+  assert!(parse_filter_string("blur(2px), brightness(2)", &context).is_ok());
+  // the comma has to be followed by a filter function
+  assert!(parse_filter_string("blur(2px),", &context).is_err());
+  assert!(parse_filter_string("blur(2px),, brightness(2)", &context).is_err());
+  assert!(parse_filter_string(", blur(2px)", &context).is_err());
 }
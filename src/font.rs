@@ -165,10 +165,12 @@ impl FromStr for FontStyle {
   }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// `font-variant-caps`, `small-caps` enables the `smcp` OpenType feature
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FontVariant {
-  Normal,
-  SmallCaps,
+  Normal = 0,
+  SmallCaps = 1,
 }
 
 impl FromStr for FontVariant {
@@ -214,6 +216,25 @@ impl From<i32> for FontStretch {
   }
 }
 
+impl FromStr for FontStretch {
+  type Err = SkError;
+
+  fn from_str(s: &str) -> Result<FontStretch, SkError> {
+    match s {
+      "ultra-condensed" => Ok(FontStretch::UltraCondensed),
+      "extra-condensed" => Ok(FontStretch::ExtraCondensed),
+      "condensed" => Ok(FontStretch::Condensed),
+      "semi-condensed" => Ok(FontStretch::SemiCondensed),
+      "normal" => Ok(FontStretch::Normal),
+      "semi-expanded" => Ok(FontStretch::SemiExpanded),
+      "expanded" => Ok(FontStretch::Expanded),
+      "extra-expanded" => Ok(FontStretch::ExtraExpanded),
+      "ultra-expanded" => Ok(FontStretch::UltraExpanded),
+      _ => Err(SkError::InvalidFontStretch(s.to_owned())),
+    }
+  }
+}
+
 impl FontStretch {
   pub fn as_str(&self) -> &str {
     match *self {
@@ -231,7 +252,7 @@ impl FontStretch {
 }
 
 // https://drafts.csswg.org/css-fonts-4/#propdef-font-weight
-fn parse_font_weight(weight: &str) -> Option<u32> {
+pub(crate) fn parse_font_weight(weight: &str) -> Option<u32> {
   match weight {
     "lighter" | "100" => Some(100),
     "200" => Some(200),
@@ -252,16 +273,95 @@ fn parse_font_weight(weight: &str) -> Option<u32> {
 
 fn parse_font_stretch(stretch: &str) -> Option<FontStretch> {
   match stretch {
-    "ultra-condensed" | "50%" => Some(FontStretch::UltraCondensed),
-    "extra-condensed" | "62.5%" => Some(FontStretch::ExtraCondensed),
-    "condensed" | "75%" => Some(FontStretch::Condensed),
-    "semi-condensed" | "87.5%" => Some(FontStretch::SemiCondensed),
-    "normal" | "100%" => Some(FontStretch::Normal),
-    "semi-expanded" | "112.5%" => Some(FontStretch::SemiExpanded),
-    "expanded" | "125%" => Some(FontStretch::Expanded),
-    "extra-expanded" | "150%" => Some(FontStretch::ExtraExpanded),
-    "ultra-expanded" | "200%" => Some(FontStretch::UltraExpanded),
-    _ => None,
+    "50%" => Some(FontStretch::UltraCondensed),
+    "62.5%" => Some(FontStretch::ExtraCondensed),
+    "75%" => Some(FontStretch::Condensed),
+    "87.5%" => Some(FontStretch::SemiCondensed),
+    "100%" => Some(FontStretch::Normal),
+    "112.5%" => Some(FontStretch::SemiExpanded),
+    "125%" => Some(FontStretch::Expanded),
+    "150%" => Some(FontStretch::ExtraExpanded),
+    "200%" => Some(FontStretch::UltraExpanded),
+    _ => FontStretch::from_str(stretch).ok(),
+  }
+}
+
+/// `ctx.fontKerning`, `none` disables the `kern` OpenType feature and `normal` forces it on
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontKerning {
+  Auto = 0,
+  Normal = 1,
+  None = 2,
+}
+
+impl Default for FontKerning {
+  fn default() -> Self {
+    Self::Auto
+  }
+}
+
+impl FontKerning {
+  pub fn as_str(&self) -> &str {
+    match *self {
+      Self::Auto => "auto",
+      Self::Normal => "normal",
+      Self::None => "none",
+    }
+  }
+}
+
+impl FromStr for FontKerning {
+  type Err = SkError;
+
+  fn from_str(s: &str) -> Result<FontKerning, SkError> {
+    match s {
+      "auto" => Ok(Self::Auto),
+      "normal" => Ok(Self::Normal),
+      "none" => Ok(Self::None),
+      _ => Err(SkError::InvalidFontKerning(s.to_owned())),
+    }
+  }
+}
+
+/// `ctx.textRendering`, the hinting and antialiasing of the glyph outlines, `auto` leaves them to the shaper
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextRendering {
+  Auto = 0,
+  OptimizeSpeed = 1,
+  OptimizeLegibility = 2,
+  GeometricPrecision = 3,
+}
+
+impl Default for TextRendering {
+  fn default() -> Self {
+    Self::Auto
+  }
+}
+
+impl TextRendering {
+  pub fn as_str(&self) -> &str {
+    match *self {
+      Self::Auto => "auto",
+      Self::OptimizeSpeed => "optimizeSpeed",
+      Self::OptimizeLegibility => "optimizeLegibility",
+      Self::GeometricPrecision => "geometricPrecision",
+    }
+  }
+}
+
+impl FromStr for TextRendering {
+  type Err = SkError;
+
+  fn from_str(s: &str) -> Result<TextRendering, SkError> {
+    match s {
+      "auto" => Ok(Self::Auto),
+      "optimizeSpeed" => Ok(Self::OptimizeSpeed),
+      "optimizeLegibility" => Ok(Self::OptimizeLegibility),
+      "geometricPrecision" => Ok(Self::GeometricPrecision),
+      _ => Err(SkError::InvalidTextRendering(s.to_owned())),
+    }
   }
 }
 
@@ -533,6 +633,45 @@ fn test_font_new() {
         ..Default::default()
       },
     ),
+    (
+      "small-caps 16px sans-serif",
+      Font {
+        size: 16.0,
+        variant: FontVariant::SmallCaps,
+        family: "sans-serif".to_owned(),
+        ..Default::default()
+      },
+    ),
+    (
+      "condensed 20px serif",
+      Font {
+        size: 20.0,
+        stretch: FontStretch::Condensed,
+        family: "serif".to_owned(),
+        ..Default::default()
+      },
+    ),
+    (
+      "italic small-caps bold ultra-expanded 20px serif",
+      Font {
+        size: 20.0,
+        style: FontStyle::Italic,
+        variant: FontVariant::SmallCaps,
+        weight: 700,
+        stretch: FontStretch::UltraExpanded,
+        family: "serif".to_owned(),
+      },
+    ),
+    // unknown tokens don't break the size and family
+    (
+      "wavy small-caps 16px sans-serif",
+      Font {
+        size: 16.0,
+        variant: FontVariant::SmallCaps,
+        family: "sans-serif".to_owned(),
+        ..Default::default()
+      },
+    ),
   ];
 
   for (rule, expect) in fixtures.into_iter() {
@@ -2,6 +2,7 @@ use std::fs::read_dir;
 use std::path;
 use std::sync::{LockResult, Mutex, MutexGuard, PoisonError};
 
+use napi::bindgen_prelude::Either;
 use once_cell::sync::{Lazy, OnceCell};
 
 use crate::sk::*;
@@ -30,23 +31,65 @@ fn into_napi_error(err: PoisonError<MutexGuard<'_, FontCollection>>) -> napi::Er
   napi::Error::new(napi::Status::GenericFailure, format!("{err}"))
 }
 
+/// Describes a font registered from a buffer, like the descriptors of a `@font-face` rule
+#[napi(object)]
+pub struct FontDescriptor {
+  pub family: String,
+  /// `normal` | `bold` | 1-1000, defaults to the weight in the font file
+  pub weight: Option<Either<u32, String>>,
+  /// `normal` | `italic` | `oblique`, defaults to the style in the font file
+  pub style: Option<String>,
+}
+
 #[napi]
 #[allow(non_snake_case)]
 pub mod GlobalFonts {
+  use std::str::FromStr;
+
   use napi::bindgen_prelude::*;
 
-  use super::{get_font, into_napi_error, FONT_DIR, FONT_PATH};
+  use super::{get_font, into_napi_error, FontDescriptor, FONT_DIR, FONT_PATH};
+  use crate::error::SkError;
+  use crate::font::{parse_font_weight, FontStyle};
+  use crate::sk::FontCollection;
 
   #[napi]
-  pub fn register(font_data: Buffer, name_alias: Option<String>) -> Result<bool> {
-    let maybe_name_alias = name_alias.and_then(|s| if s.is_empty() { None } else { Some(s) });
+  pub fn register(
+    font_data: Buffer,
+    name_alias_or_descriptor: Option<Either<String, FontDescriptor>>,
+  ) -> Result<bool> {
     let font = get_font().map_err(into_napi_error)?;
-    Ok(font.register(font_data.as_ref(), maybe_name_alias))
+    match name_alias_or_descriptor {
+      Some(Either::B(descriptor)) => {
+        let weight = descriptor
+          .weight
+          .map(|weight| {
+            let weight = match weight {
+              Either::A(weight) => weight.to_string(),
+              Either::B(weight) => weight,
+            };
+            parse_font_weight(&weight).ok_or(SkError::InvalidFontWeight(weight))
+          })
+          .transpose()?;
+        let style = descriptor
+          .style
+          .as_deref()
+          .map(FontStyle::from_str)
+          .transpose()?;
+        Ok(font.register_styled(font_data.as_ref(), &descriptor.family, weight, style))
+      }
+      Some(Either::A(name_alias)) if !name_alias.is_empty() => {
+        Ok(font.register(font_data.as_ref(), Some(name_alias)))
+      }
+      _ => Ok(font.register::<String>(font_data.as_ref(), None)),
+    }
   }
 
   #[napi]
   pub fn register_from_path(font_path: String, name_alias: Option<String>) -> Result<bool> {
     let maybe_name_alias = name_alias.and_then(|s| if s.is_empty() { None } else { Some(s) });
+    // surface the missing or unreadable font file instead of returning false
+    std::fs::metadata(&font_path).map_err(|err| SkError::from((&font_path, err)))?;
     let font = get_font().map_err(into_napi_error)?;
     Ok(font.register_from_path(font_path.as_str(), maybe_name_alias))
   }
@@ -69,6 +112,18 @@ pub mod GlobalFonts {
     super::load_fonts_from_dir(dir.as_str())
   }
 
+  /// Drop every registered font, the system fonts are loaded again if they were loaded before
+  #[napi]
+  pub fn deregister_all_fonts() -> Result<()> {
+    let mut font = get_font().map_err(into_napi_error)?;
+    *font = FontCollection::new();
+    drop(font);
+    if FONT_DIR.get().is_some() {
+      super::load_fonts_from_dir(FONT_PATH)?;
+    }
+    Ok(())
+  }
+
   #[napi]
   pub fn set_alias(font_name: String, alias: String) -> Result<()> {
     let font = get_font().map_err(into_napi_error)?;
@@ -1,13 +1,15 @@
 use std::result;
+use std::str::FromStr;
 
 use cssparser::{Color as CSSColor, Parser, ParserInput};
 use napi::bindgen_prelude::*;
 
 use crate::{
+  color::parse_color,
   error::SkError,
   sk::{
-    Color, ConicGradient, Gradient as SkGradient, LinearGradient, RadialGradient, Shader, TileMode,
-    Transform,
+    Color, ConicGradient, Gradient as SkGradient, GradientInterpolation, LinearGradient,
+    RadialGradient, Shader, TileMode, Transform,
   },
 };
 
@@ -26,6 +28,8 @@ impl Gradient {
       base: SkGradient {
         colors: Vec::new(),
         positions: Vec::new(),
+        hints: Vec::new(),
+        interpolation: GradientInterpolation::default(),
         tile_mode: TileMode::Clamp,
         transform: Transform::default(),
       },
@@ -42,6 +46,8 @@ impl Gradient {
       base: SkGradient {
         colors: Vec::new(),
         positions: Vec::new(),
+        hints: Vec::new(),
+        interpolation: GradientInterpolation::default(),
         tile_mode: TileMode::Clamp,
         transform: Transform::default(),
       },
@@ -49,34 +55,40 @@ impl Gradient {
     Self::Radial(radial_gradient)
   }
 
-  pub fn create_conic_gradient(x: f32, y: f32, r: f32) -> Self {
+  pub fn create_conic_gradient(x: f32, y: f32, start_angle: f32) -> Self {
     Self::Conic(ConicGradient {
       center: (x, y),
-      radius: r,
+      start_angle,
       base: SkGradient {
         colors: Vec::new(),
         positions: Vec::new(),
+        hints: Vec::new(),
+        interpolation: GradientInterpolation::default(),
         tile_mode: TileMode::Clamp,
         transform: Transform::default(),
       },
     })
   }
 
+  fn base(&self) -> &SkGradient {
+    match self {
+      Self::Linear(linear_gradient) => &linear_gradient.base,
+      Self::Radial(radial_gradient) => &radial_gradient.base,
+      Self::Conic(conic_gradient) => &conic_gradient.base,
+    }
+  }
+
+  fn base_mut(&mut self) -> &mut SkGradient {
+    match self {
+      Self::Linear(linear_gradient) => &mut linear_gradient.base,
+      Self::Radial(radial_gradient) => &mut radial_gradient.base,
+      Self::Conic(conic_gradient) => &mut conic_gradient.base,
+    }
+  }
+
   pub fn add_color_stop(&mut self, offset: f32, color: Color) {
-    let (stops, colors) = match self {
-      Self::Linear(linear_gradient) => (
-        &mut linear_gradient.base.positions,
-        &mut linear_gradient.base.colors,
-      ),
-      Self::Radial(radial_gradient) => (
-        &mut radial_gradient.base.positions,
-        &mut radial_gradient.base.colors,
-      ),
-      Self::Conic(conic_gradient) => (
-        &mut conic_gradient.base.positions,
-        &mut conic_gradient.base.colors,
-      ),
-    };
+    let base = self.base_mut();
+    let (stops, colors) = (&mut base.positions, &mut base.colors);
     if stops.last().map(|l| l < &offset).unwrap_or(true) {
       stops.push(offset);
       colors.push(color);
@@ -95,6 +107,21 @@ impl Gradient {
     }
   }
 
+  /// The color midpoint between the stops around `offset`, like the CSS `<color-hint>`
+  pub fn add_color_hint(&mut self, offset: f32) {
+    let hints = &mut self.base_mut().hints;
+    let index = hints.partition_point(|hint| *hint < offset);
+    hints.insert(index, offset);
+  }
+
+  pub fn interpolation(&self) -> GradientInterpolation {
+    self.base().interpolation
+  }
+
+  pub fn set_interpolation(&mut self, interpolation: GradientInterpolation) {
+    self.base_mut().interpolation = interpolation;
+  }
+
   /// Transform is [3 x 3] matrix, but stored in 2d array:
   /// | A B C |
   /// | D E F |
@@ -102,13 +129,13 @@ impl Gradient {
   /// [0 -> A, 1 -> B, 2 -> C, 3 -> D, 4 -> E, 5 -> F, 6 -> 0, 7 -> 0, 8 -> 1 ]
   /// [lineargradient.js](skia/modules/canvaskit/htmlcanvas/lineargradient.js)
   /// [radialgradient.js](skia/modules/canvaskit/htmlcanvas/radialgradient.js)
-  pub(crate) fn get_shader(&self, current_transform: Transform) -> result::Result<Shader, SkError> {
+  pub(crate) fn get_shader(&self) -> result::Result<Shader, SkError> {
     match self {
       Self::Linear(ref linear_gradient) => Ok(
         Shader::new_linear_gradient(&LinearGradient {
           start_point: linear_gradient.start_point,
           end_point: linear_gradient.end_point,
-          base: linear_gradient.base.clone(),
+          base: resolve_color_stops(&linear_gradient.base),
         })
         .ok_or_else(|| SkError::Generic("Get shader of linear gradient failed".to_owned()))?,
       ),
@@ -118,7 +145,7 @@ impl Gradient {
       Self::Radial(ref radial_gradient) => {
         // From the spec: "The points in the linear gradient must be transformed
         // as described by the current transformation matrix when rendering."
-        let base = radial_gradient.base.clone();
+        let base = resolve_color_stops(&radial_gradient.base);
         let new_radial_gradient = RadialGradient {
           start: radial_gradient.start,
           end: radial_gradient.end,
@@ -132,26 +159,116 @@ impl Gradient {
             .ok_or_else(|| SkError::Generic("Get shader of radial gradient failed".to_owned()))?,
         )
       }
-      Self::Conic(ref conic_gradient) => {
-        let (x, y) = conic_gradient.center;
-        let r = conic_gradient.radius;
-        let sx = current_transform.c;
-        let sy = current_transform.b;
-        let scale_factor = (f32::abs(sx) + f32::abs(sy)) / 2f32;
-        let sr = r * scale_factor;
-        let new_conic_gradient = ConicGradient {
-          center: (x, y),
-          radius: sr,
-          base: conic_gradient.base.clone(),
-        };
+      Self::Conic(ref conic_gradient) => Ok(
+        Shader::new_conic_gradient(&ConicGradient {
+          center: conic_gradient.center,
+          start_angle: conic_gradient.start_angle,
+          base: resolve_color_stops(&conic_gradient.base),
+        })
+        .ok_or_else(|| SkError::Generic("Get shader of conic gradient failed".to_owned()))?,
+      ),
+    }
+  }
+}
 
-        Ok(
-          Shader::new_conic_gradient(&new_conic_gradient)
-            .ok_or_else(|| SkError::Generic("Get shader of radial gradient failed".to_owned()))?,
-        )
-      }
+/// Stops between each pair of stops for the color hints and the `oklab` interpolation
+const INTERPOLATION_STEPS: usize = 16;
+
+/// Skia interpolates the stops linearly in sRGB, so the color hints and the other color spaces are
+/// approximated with the intermediate stops
+fn resolve_color_stops(base: &SkGradient) -> SkGradient {
+  let mut resolved = base.clone();
+  if (base.hints.is_empty() && base.interpolation == GradientInterpolation::Srgb)
+    || base.positions.len() < 2
+  {
+    return resolved;
+  }
+  resolved.colors = vec![base.colors[0]];
+  resolved.positions = vec![base.positions[0]];
+  for (stops, colors) in base.positions.windows(2).zip(base.colors.windows(2)) {
+    let (start, end) = (stops[0], stops[1]);
+    // hard transition
+    if end - start <= f32::EPSILON {
+      resolved.positions.push(end);
+      resolved.colors.push(colors[1]);
+      continue;
+    }
+    let hint = base
+      .hints
+      .iter()
+      .find(|hint| **hint > start && **hint < end)
+      .map(|hint| (hint - start) / (end - start));
+    for step in 1..=INTERPOLATION_STEPS {
+      let t = step as f32 / INTERPOLATION_STEPS as f32;
+      // https://drafts.csswg.org/css-images-4/#coloring-gradient-line
+      let weight = match hint {
+        Some(hint) => t.powf(0.5f32.ln() / hint.ln()),
+        None => t,
+      };
+      resolved.positions.push(start + (end - start) * t);
+      resolved
+        .colors
+        .push(mix_colors(colors[0], colors[1], weight, base.interpolation));
     }
   }
+  resolved.hints.clear();
+  resolved
+}
+
+fn mix_colors(from: Color, to: Color, weight: f32, interpolation: GradientInterpolation) -> Color {
+  let channels =
+    |color: Color| [16, 8, 0, 24].map(|shift| ((color.0 >> shift) & 0xFF) as f32 / 255.0);
+  let [r0, g0, b0, a0] = channels(from);
+  let [r1, g1, b1, a1] = channels(to);
+  let lerp = |a: f32, b: f32| a + (b - a) * weight;
+  let [r, g, b] = match interpolation {
+    GradientInterpolation::Srgb => [lerp(r0, r1), lerp(g0, g1), lerp(b0, b1)],
+    GradientInterpolation::Oklab => {
+      let [l0, m0, s0] = srgb_to_oklab([r0, g0, b0]);
+      let [l1, m1, s1] = srgb_to_oklab([r1, g1, b1]);
+      oklab_to_srgb([lerp(l0, l1), lerp(m0, m1), lerp(s0, s1)])
+    }
+  };
+  let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+  Color::from_rgba(to_u8(r), to_u8(g), to_u8(b), to_u8(lerp(a0, a1)))
+}
+
+// https://bottosson.github.io/posts/oklab/
+fn srgb_to_oklab(rgb: [f32; 3]) -> [f32; 3] {
+  let [r, g, b] = rgb.map(|c| {
+    if c <= 0.04045 {
+      c / 12.92
+    } else {
+      ((c + 0.055) / 1.055).powf(2.4)
+    }
+  });
+  let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+  let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+  let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+  [
+    0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+    1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+    0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+  ]
+}
+
+fn oklab_to_srgb(lab: [f32; 3]) -> [f32; 3] {
+  let [l, a, b] = lab;
+  let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+  let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+  let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+  [
+    4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+    -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+    -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+  ]
+  .map(|c| {
+    if c <= 0.003_130_8 {
+      c * 12.92
+    } else {
+      1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+  })
 }
 
 #[napi]
@@ -159,15 +276,16 @@ pub struct CanvasGradient(pub(crate) Gradient);
 
 #[napi]
 impl CanvasGradient {
+  /// `hint` is the position of the color midpoint between this stop and the one before it
   #[napi]
-  pub fn add_color_stop(&mut self, index: f64, color: String) -> Result<()> {
+  pub fn add_color_stop(&mut self, index: f64, color: String, hint: Option<f64>) -> Result<()> {
     if color.is_empty() {
       return Ok(());
     }
     let color_str = color.as_str();
     let mut parser_input = ParserInput::new(color_str);
     let mut parser = Parser::new(&mut parser_input);
-    let color = CSSColor::parse(&mut parser).map_err(|e| {
+    let color = parse_color(&mut parser).map_err(|e| {
       Error::new(
         Status::InvalidArg,
         format!("Parse color [{color_str}] error: {e:?}"),
@@ -183,8 +301,24 @@ impl CanvasGradient {
       CSSColor::RGBA(rgba) => Color::from_rgba(rgba.red, rgba.green, rgba.blue, rgba.alpha),
     };
     self.0.add_color_stop(index as f32, skia_color);
+    if let Some(hint) = hint.filter(|hint| hint.is_finite()) {
+      self.0.add_color_hint(hint as f32);
+    }
     Ok(())
   }
+
+  /// `srgb` or `oklab`, the invalid values are ignored
+  #[napi(getter)]
+  pub fn get_interpolation(&self) -> String {
+    self.0.interpolation().as_str().to_owned()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_interpolation(&mut self, interpolation: String) {
+    if let Ok(interpolation) = GradientInterpolation::from_str(&interpolation) {
+      self.0.set_interpolation(interpolation);
+    }
+  }
 }
 
 #[test]
@@ -209,3 +343,34 @@ fn test_add_color_stop() {
     unreachable!();
   }
 }
+
+#[test]
+fn resolve_color_stops_interpolation_and_hints() {
+  let red = Color::from_rgba(255, 0, 0, 255);
+  let blue = Color::from_rgba(0, 0, 255, 255);
+  let mut gradient = Gradient::create_linear_gradient(0.0, 0.0, 100.0, 0.0);
+  gradient.add_color_stop(0.0, red);
+  gradient.add_color_stop(1.0, blue);
+  // nothing to approximate in sRGB without hints
+  let resolved = resolve_color_stops(gradient.base());
+  assert_eq!(resolved.positions, vec![0.0, 1.0]);
+
+  let middle = INTERPOLATION_STEPS / 2;
+  gradient.set_interpolation(GradientInterpolation::Oklab);
+  let resolved = resolve_color_stops(gradient.base());
+  assert_eq!(resolved.positions.len(), INTERPOLATION_STEPS + 1);
+  assert_eq!(resolved.positions[middle], 0.5);
+  assert_eq!(resolved.colors[0], red);
+  assert_eq!(resolved.colors[INTERPOLATION_STEPS], blue);
+  assert_ne!(resolved.colors[middle], Color::from_rgba(128, 0, 128, 255));
+
+  // the midpoint color is reached at the hint
+  gradient.set_interpolation(GradientInterpolation::Srgb);
+  gradient.add_color_hint(0.25);
+  let resolved = resolve_color_stops(gradient.base());
+  let hint_index = resolved.positions.iter().position(|p| *p == 0.25).unwrap();
+  assert_eq!(
+    resolved.colors[hint_index],
+    Color::from_rgba(128, 0, 128, 255)
+  );
+}
@@ -1,23 +1,85 @@
+use std::borrow::Cow;
+use std::ptr;
 use std::str;
 use std::str::FromStr;
 
 use base64::{engine::general_purpose::STANDARD, Engine};
-use napi::{bindgen_prelude::*, NapiValue};
+use napi::{bindgen_prelude::*, check_status, sys, NapiRaw, NapiValue};
 
-use crate::sk::Bitmap;
-use crate::sk::ColorSpace;
+use crate::ctx::{image_bitmap, BitmapRef, Context};
+use crate::error::SkError;
+use crate::offscreen::OffscreenCanvas;
+use crate::sk::{
+  AlphaType, Bitmap, ColorSpace, ColorType, FilterQuality, Matrix, OwnedBitmap, StorageFormat,
+};
+use crate::{CanvasElement, SVGCanvas};
 
 #[napi]
 pub struct ImageData {
   pub(crate) width: usize,
   pub(crate) height: usize,
   pub(crate) color_space: ColorSpace,
+  pub(crate) storage_format: StorageFormat,
   pub(crate) data: *mut u8,
 }
 
 #[napi(object)]
 pub struct Settings {
-  pub color_space: String,
+  pub color_space: Option<String>,
+  pub storage_format: Option<String>,
+}
+
+impl Settings {
+  pub(crate) fn color_space(&self) -> Option<ColorSpace> {
+    self
+      .color_space
+      .as_deref()
+      .and_then(|cs| ColorSpace::from_str(cs).ok())
+  }
+
+  pub(crate) fn storage_format(&self) -> Result<StorageFormat> {
+    match self.storage_format.as_deref() {
+      Some(format) => Ok(StorageFormat::from_str(format)?),
+      None => Ok(StorageFormat::default()),
+    }
+  }
+}
+
+/// Pixels backing `ImageData.data`, `uint8` is a `Uint8ClampedArray` and `float16` a `Float32Array`
+pub(crate) enum ImageDataArray {
+  Uint8(Vec<u8>),
+  Float32(Vec<f32>),
+}
+
+impl ImageDataArray {
+  pub(crate) fn zeroed(width: u32, height: u32, storage_format: StorageFormat) -> Result<Self> {
+    let len = image_data_len(width, height)?;
+    Ok(match storage_format {
+      StorageFormat::Uint8 => Self::Uint8(vec![0; len]),
+      StorageFormat::Float16 => Self::Float32(vec![0.0; len]),
+    })
+  }
+
+  /// Move the pixels into a JS typed array, returns it with the address of the first byte
+  pub(crate) fn into_js(self, env: &Env) -> Result<(Object, *mut u8)> {
+    let (value, data) = match self {
+      Self::Uint8(mut pixels) => {
+        let data = pixels.as_mut_ptr();
+        let value =
+          unsafe { Uint8ClampedArray::to_napi_value(env.raw(), Uint8ClampedArray::new(pixels))? };
+        (value, data)
+      }
+      Self::Float32(mut pixels) => {
+        let data = pixels.as_mut_ptr().cast();
+        let value = unsafe { Float32Array::to_napi_value(env.raw(), Float32Array::new(pixels))? };
+        (value, data)
+      }
+    };
+    Ok((
+      unsafe { Object::from_raw_unchecked(env.raw(), value) },
+      data,
+    ))
+  }
 }
 
 #[napi]
@@ -26,79 +88,58 @@ impl ImageData {
   pub fn new(
     env: Env,
     mut this: This,
-    width_or_data: Either<u32, Uint8ClampedArray>,
+    width_or_data: Either3<u32, Uint8ClampedArray, Float32Array>,
     width_or_height: u32,
     height_or_settings: Option<Either<u32, Settings>>,
     maybe_settings: Option<Settings>,
   ) -> Result<Self> {
-    match width_or_data {
-      Either::A(width) => {
+    let (width, height, color_space, storage_format, pixels) = match width_or_data {
+      Either3::A(width) => {
         let height = width_or_height;
-        let color_space = match height_or_settings {
-          Some(Either::B(settings)) => {
-            ColorSpace::from_str(&settings.color_space).unwrap_or_default()
-          }
-          _ => ColorSpace::default(),
+        let (color_space, storage_format) = match height_or_settings {
+          Some(Either::B(settings)) => (
+            settings.color_space().unwrap_or_default(),
+            settings.storage_format()?,
+          ),
+          _ => (ColorSpace::default(), StorageFormat::default()),
         };
-        let arraybuffer_length = (width * height * 4) as usize;
-        let mut data_buffer = vec![0; arraybuffer_length];
-        let data_ptr = data_buffer.as_mut_ptr();
-        let data_object = unsafe {
-          Object::from_raw_unchecked(
-            env.raw(),
-            Uint8ClampedArray::to_napi_value(env.raw(), Uint8ClampedArray::new(data_buffer))?,
-          )
-        };
-        this.define_properties(&[Property::new("data")?
-          .with_value(&data_object)
-          .with_property_attributes(
-            PropertyAttributes::Enumerable | PropertyAttributes::Configurable,
-          )])?;
-        Ok(ImageData {
-          width: width as usize,
-          height: height as usize,
-          color_space,
-          data: data_ptr,
-        })
+        let pixels = ImageDataArray::zeroed(width, height, storage_format)?;
+        (width, height, color_space, storage_format, pixels)
       }
-      Either::B(data_object) => {
-        let input_data_length = data_object.len();
+      Either3::B(data_object) => {
         let width = width_or_height;
-        let height = match &height_or_settings {
-          Some(Either::A(height)) => *height,
-          _ => (input_data_length as u32) / 4 / width,
-        };
-        if height * width * 4 != data_object.len() as u32 {
-          return Err(Error::new(
-            Status::InvalidArg,
-            "Index or size is negative or greater than the allowed amount".to_owned(),
-          ));
-        }
+        let height = image_data_height(data_object.len(), width, &height_or_settings)?;
         // https://developer.mozilla.org/en-US/docs/Web/API/CanvasRenderingContext2D/createImageData
         // An existing ImageData object from which to copy the width and height.
-        let mut cloned_data = Uint8ClampedArray::new(data_object.to_vec());
-        let data = cloned_data.as_mut_ptr();
-        this.define_properties(&[Property::new("data")?
-          .with_value(&unsafe {
-            Object::from_raw_unchecked(
-              env.raw(),
-              Uint8ClampedArray::to_napi_value(env.raw(), cloned_data)?,
-            )
-          })
-          .with_property_attributes(
-            PropertyAttributes::Enumerable | PropertyAttributes::Configurable,
-          )])?;
+        let pixels = ImageDataArray::Uint8(data_object.to_vec());
         let color_space = maybe_settings
-          .and_then(|settings| ColorSpace::from_str(&settings.color_space).ok())
+          .and_then(|settings| settings.color_space())
           .unwrap_or_default();
-        Ok(ImageData {
-          width: width as usize,
-          height: height as usize,
-          color_space,
-          data,
-        })
+        (width, height, color_space, StorageFormat::Uint8, pixels)
       }
-    }
+      Either3::C(data_object) => {
+        let width = width_or_height;
+        let height = image_data_height(data_object.len(), width, &height_or_settings)?;
+        let pixels = ImageDataArray::Float32(data_object.to_vec());
+        let color_space = maybe_settings
+          .and_then(|settings| settings.color_space())
+          .unwrap_or_default();
+        (width, height, color_space, StorageFormat::Float16, pixels)
+      }
+    };
+    let (data_object, data) = pixels.into_js(&env)?;
+    this.define_properties(&[Property::new("data")?
+      .with_value(&data_object)
+      .with_property_attributes(
+        PropertyAttributes::Enumerable | PropertyAttributes::Configurable,
+      )])?;
+    Ok(ImageData {
+      width: width as usize,
+      height: height as usize,
+      color_space,
+      storage_format,
+      data,
+    })
   }
 
   #[napi(getter)]
@@ -110,6 +151,116 @@ impl ImageData {
   pub fn get_height(&self) -> u32 {
     self.height as u32
   }
+
+  #[napi(getter)]
+  pub fn get_color_space(&self) -> String {
+    self.color_space.as_str().to_owned()
+  }
+
+  #[napi(getter)]
+  pub fn get_storage_format(&self) -> String {
+    self.storage_format.as_str().to_owned()
+  }
+}
+
+/// `width` and `height` of a new `ImageData`, both must be positive after the WebIDL `long` truncation
+pub(crate) fn image_data_size(width: f64, height: f64) -> Result<(u32, u32)> {
+  let width = if width.is_finite() {
+    width.trunc()
+  } else {
+    0.0
+  };
+  let height = if height.is_finite() {
+    height.trunc()
+  } else {
+    0.0
+  };
+  if width < 1.0 || height < 1.0 || width > u32::MAX as f64 || height > u32::MAX as f64 {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!("IndexSizeError: The source width ({width}) and height ({height}) must be positive integers"),
+    ));
+  }
+  Ok((width as u32, height as u32))
+}
+
+/// Normalize the dirty rect of `putImageData` and intersect it with the image,
+/// as per https://html.spec.whatwg.org/multipage/canvas.html#dom-context-2d-putimagedata.
+/// `None` if nothing is left to copy.
+pub(crate) fn put_image_data_dirty_rect(
+  width: u32,
+  height: u32,
+  dirty_x: f64,
+  dirty_y: f64,
+  dirty_width: f64,
+  dirty_height: f64,
+) -> Option<(u32, u32, u32, u32)> {
+  // WebIDL `long`, NaN is 0
+  let (mut x, mut y) = (dirty_x.trunc() as i64, dirty_y.trunc() as i64);
+  let (mut w, mut h) = (dirty_width.trunc() as i64, dirty_height.trunc() as i64);
+  if w < 0 {
+    x += w;
+    w = -w;
+  }
+  if h < 0 {
+    y += h;
+    h = -h;
+  }
+  if x < 0 {
+    w += x;
+    x = 0;
+  }
+  if y < 0 {
+    h += y;
+    y = 0;
+  }
+  w = w.min(width as i64 - x);
+  h = h.min(height as i64 - y);
+  if w <= 0 || h <= 0 {
+    return None;
+  }
+  Some((x as u32, y as u32, w as u32, h as u32))
+}
+
+/// V8 can't create the typed arrays longer than this on every platform
+const MAX_IMAGE_DATA_LEN: usize = i32::MAX as usize;
+
+/// Number of the RGBA components of a `width` x `height` image, a RangeError if there are too many
+pub(crate) fn image_data_len(width: u32, height: u32) -> Result<usize> {
+  (width as usize)
+    .checked_mul(height as usize)
+    .and_then(|len| len.checked_mul(4))
+    .filter(|len| *len <= MAX_IMAGE_DATA_LEN)
+    .ok_or_else(|| {
+      Error::new(
+        Status::InvalidArg,
+        format!("RangeError: the {width}x{height} image data is too large"),
+      )
+    })
+}
+
+fn image_data_height(
+  data_length: usize,
+  width: u32,
+  height_or_settings: &Option<Either<u32, Settings>>,
+) -> Result<u32> {
+  if width == 0 {
+    return Err(Error::new(
+      Status::InvalidArg,
+      "IndexSizeError: The source width must be a positive integer".to_owned(),
+    ));
+  }
+  let height = match height_or_settings {
+    Some(Either::A(height)) => *height,
+    _ => u32::try_from(data_length / 4 / width as usize).unwrap_or(u32::MAX),
+  };
+  if image_data_len(width, height)? != data_length {
+    return Err(Error::new(
+      Status::InvalidArg,
+      "Index or size is negative or greater than the allowed amount".to_owned(),
+    ));
+  }
+  Ok(height)
 }
 
 #[napi]
@@ -123,6 +274,23 @@ pub struct Image {
   pub(crate) is_svg: bool,
   pub(crate) color_space: ColorSpace,
   pub(crate) src: Option<Buffer>,
+  // durations of the decoded frames in milliseconds
+  frames: Vec<u32>,
+  // the last frame other than the first one drawn with `drawImageFrame`
+  frame_cache: Option<(u32, Bitmap)>,
+  // EXIF orientation of the source, from `1` to `8`
+  orientation: u32,
+  apply_orientation: bool,
+  // the format and the reason of the failed decoding of `src`, rejects `decode()`
+  decode_error: Option<(String, String)>,
+  // the `decode()` promises made before `src` is assigned, settled on the next assignment
+  pending_decodes: Vec<sys::napi_deferred>,
+}
+
+#[napi(object)]
+pub struct ImageFrame {
+  /// milliseconds, `0` for still images
+  pub duration: u32,
 }
 
 #[napi]
@@ -144,6 +312,12 @@ impl Image {
       is_svg: false,
       color_space,
       src: None,
+      frames: Vec::new(),
+      frame_cache: None,
+      orientation: 1,
+      apply_orientation: true,
+      decode_error: None,
+      pending_decodes: Vec::new(),
     })
   }
 
@@ -206,22 +380,79 @@ impl Image {
     self.alt = alt;
   }
 
+  /// Frames of an animated image, still images have a single frame
+  #[napi(getter)]
+  pub fn get_frames(&self) -> Vec<ImageFrame> {
+    self
+      .frames
+      .iter()
+      .map(|&duration| ImageFrame { duration })
+      .collect()
+  }
+
+  /// The EXIF orientation of the loaded image, `1` when it has none
+  #[napi(getter)]
+  pub fn get_orientation(&self) -> u32 {
+    self.orientation
+  }
+
+  #[napi(getter)]
+  pub fn get_image_orientation(&self) -> String {
+    if self.apply_orientation {
+      "from-image".to_owned()
+    } else {
+      "none".to_owned()
+    }
+  }
+
+  /// `from-image` rotates the decoded image upright by its EXIF orientation, `none` keeps the stored pixels.
+  /// Takes effect on the next `src` assignment.
+  #[napi(setter)]
+  pub fn set_image_orientation(&mut self, image_orientation: String) {
+    match image_orientation.as_str() {
+      "from-image" => self.apply_orientation = true,
+      "none" => self.apply_orientation = false,
+      _ => {}
+    }
+  }
+
   #[napi(getter)]
   pub fn get_src(&mut self) -> Option<&mut Buffer> {
     self.src.as_mut()
   }
 
+  /// Resolve once `src` is decoded, reject with the decoding error if it couldn't be decoded.
+  /// `src` is decoded synchronously when it's assigned, the Promise only reports the outcome,
+  /// it waits for the assignment if there is no `src` yet.
+  #[napi(ts_return_type = "Promise<void>")]
+  pub fn decode(&mut self, env: Env) -> Result<Object> {
+    let mut deferred = ptr::null_mut();
+    let mut promise = ptr::null_mut();
+    check_status!(unsafe { sys::napi_create_promise(env.raw(), &mut deferred, &mut promise) })?;
+    if self.src.is_some() {
+      self.settle_decode(env, deferred)?;
+    } else {
+      self.pending_decodes.push(deferred);
+    }
+    Ok(unsafe { Object::from_raw_unchecked(env.raw(), promise) })
+  }
+
   #[napi(setter)]
   pub fn set_src(&mut self, env: Env, this: This, data: Buffer) -> Result<()> {
     let length = data.len();
+    self.decode_error = None;
     if length <= 2 {
       self.src = Some(data);
+      self.settle_pending_decodes(env)?;
       self.on_load(&this)?;
       return Ok(());
     }
     let data_ref: &[u8] = &data;
     self.complete = true;
     self.is_svg = false;
+    self.frames.clear();
+    self.frame_cache = None;
+    self.orientation = 1;
     let bitmap = if str::from_utf8(&data_ref[0..10]) == Ok("data:image") {
       let data_str = str::from_utf8(data_ref)
         .map_err(|e| Error::new(Status::InvalidArg, format!("Decode data url failed {e}")))?;
@@ -231,26 +462,25 @@ impl Image {
           .map_err(|e| Error::new(Status::InvalidArg, format!("Decode data url failed {e}")))?;
         if let Some(kind) = infer::get(&image_binary) {
           if kind.matcher_type() == infer::MatcherType::Image {
-            Some(Bitmap::from_buffer(
-              image_binary.as_ptr() as *mut u8,
-              image_binary.len(),
-            ))
+            self.decode_bitmap(env, &this, &image_binary, kind)?
           } else {
-            self.on_error(env, &this)?;
+            self.on_error(env, &this, unsupported_image_type())?;
             None
           }
         } else {
-          self.on_error(env, &this)?;
+          self.on_error(env, &this, unsupported_image_type())?;
           None
         }
       } else {
         None
       }
     } else if let Some(kind) = infer::get(&data) && kind.matcher_type() == infer::MatcherType::Image {
-      Some(Bitmap::from_buffer(data.as_ptr() as *mut u8, length))
+      self.decode_bitmap(env, &this, &data, kind)?
     } else if self.is_svg_image(data_ref, length) {
       self.is_svg = true;
-      if (self.width - -1.0).abs() > f64::EPSILON && (self.height - -1.0).abs() > f64::EPSILON {
+      let bitmap = if (self.width - -1.0).abs() > f64::EPSILON
+        && (self.height - -1.0).abs() > f64::EPSILON
+      {
         Bitmap::from_svg_data_with_custom_size(
           data.as_ptr(),
           length,
@@ -260,12 +490,22 @@ impl Image {
         )
       } else {
         Bitmap::from_svg_data(data.as_ptr(), length, self.color_space)
+      };
+      if bitmap.is_none() {
+        self.decode_error = Some(("svg".to_owned(), "invalid svg data".to_owned()));
       }
+      bitmap
     } else {
-      self.on_error(env, &this)?;
+      self.on_error(env, &this, unsupported_image_type())?;
       None
     };
+    if bitmap.is_none() && self.decode_error.is_none() {
+      self.decode_error = Some(("unknown".to_owned(), "unsupported image type".to_owned()));
+    }
     if let Some(ref b) = bitmap {
+      if self.frames.is_empty() {
+        self.frames.push(0);
+      }
       if (self.width - -1.0).abs() < f64::EPSILON {
         self.width = b.0.width as f64;
       }
@@ -275,6 +515,7 @@ impl Image {
     }
     self.bitmap = bitmap;
     self.src = Some(data);
+    self.settle_pending_decodes(env)?;
     self.on_load(&this)?;
     Ok(())
   }
@@ -294,6 +535,73 @@ impl Image {
     }
   }
 
+  /// The bitmap of the frame at `index`, the first frame is the bitmap decoded on load
+  pub(crate) fn frame_bitmap(&mut self, index: u32) -> Result<Option<&mut Bitmap>> {
+    if !self.complete || self.bitmap.is_none() {
+      return Ok(None);
+    }
+    if index as usize >= self.frames.len() {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!(
+          "IndexSizeError: frame index {index} is out of range, the image has {} frames",
+          self.frames.len()
+        ),
+      ));
+    }
+    if index == 0 {
+      self.regenerate_bitmap_if_need();
+      return Ok(self.bitmap.as_mut());
+    }
+    if !matches!(self.frame_cache, Some((cached, _)) if cached == index) {
+      let src: &[u8] = match self.src.as_ref() {
+        Some(src) => src,
+        None => return Ok(None),
+      };
+      let data = if src.starts_with(b"data:image") {
+        let base64_str = str::from_utf8(src)
+          .ok()
+          .and_then(|s| s.split(',').last())
+          .unwrap_or_default();
+        Cow::Owned(
+          STANDARD
+            .decode(base64_str)
+            .map_err(|e| Error::new(Status::InvalidArg, format!("Decode data url failed {e}")))?,
+        )
+      } else {
+        Cow::Borrowed(src)
+      };
+      self.frame_cache = Bitmap::from_buffer_frame(&data, index).map(|bitmap| (index, bitmap));
+    }
+    Ok(self.frame_cache.as_mut().map(|(_, bitmap)| bitmap))
+  }
+
+  fn settle_decode(&self, env: Env, deferred: sys::napi_deferred) -> Result<()> {
+    let error = match (&self.bitmap, &self.decode_error) {
+      (Some(_), _) => None,
+      (None, Some(error)) => Some(error.clone()),
+      (None, None) => Some(("unknown".to_owned(), "the image has no source".to_owned())),
+    };
+    match error {
+      None => {
+        let undefined = env.get_undefined()?;
+        check_status!(unsafe { sys::napi_resolve_deferred(env.raw(), deferred, undefined.raw()) })
+      }
+      Some((format, reason)) => {
+        let error = JsError::from(Error::from(SkError::ImageDecodeError { format, reason }))
+          .into_unknown(env);
+        check_status!(unsafe { sys::napi_reject_deferred(env.raw(), deferred, error.raw()) })
+      }
+    }
+  }
+
+  fn settle_pending_decodes(&mut self, env: Env) -> Result<()> {
+    for deferred in std::mem::take(&mut self.pending_decodes) {
+      self.settle_decode(env, deferred)?;
+    }
+    Ok(())
+  }
+
   fn on_load(&self, this: &This) -> Result<()> {
     let onload = this.get_named_property_unchecked::<Unknown>("onload")?;
     if onload.get_type()? == ValueType::Function {
@@ -303,9 +611,34 @@ impl Image {
     Ok(())
   }
 
-  fn on_error(&self, env: Env, this: &This) -> Result<()> {
+  fn decode_bitmap(
+    &mut self,
+    env: Env,
+    this: &This,
+    data: &[u8],
+    kind: infer::Type,
+  ) -> Result<Option<Bitmap>> {
+    let bitmap = Bitmap::from_buffer(data.as_ptr() as *mut u8, data.len(), self.apply_orientation);
+    if bitmap.is_some() {
+      self.frames = Bitmap::frame_durations(data);
+      self.orientation = Bitmap::orientation(data);
+    } else {
+      let (format, reason) = (
+        kind.extension().to_owned(),
+        "invalid or corrupted image data".to_owned(),
+      );
+      self.decode_error = Some((format.clone(), reason.clone()));
+      self.on_error(
+        env,
+        this,
+        SkError::ImageDecodeError { format, reason }.into(),
+      )?;
+    }
+    Ok(bitmap)
+  }
+
+  fn on_error(&self, env: Env, this: &This, err: Error) -> Result<()> {
     let onerror = this.get_named_property_unchecked::<Unknown>("onerror")?;
-    let err = Error::new(Status::InvalidArg, "Unsupported image type");
     if onerror.get_type()? == ValueType::Function {
       let onerror_func = unsafe { onerror.cast::<JsFunction>() };
       onerror_func.call(Some(this), &[JsError::from(err).into_unknown(env)])?;
@@ -336,3 +669,302 @@ impl Image {
     is_svg
   }
 }
+
+/// A decoded bitmap ready to be drawn, it holds its own copy of the pixels
+#[napi]
+pub struct ImageBitmap {
+  pub(crate) bitmap: Option<Bitmap>,
+}
+
+#[napi]
+impl ImageBitmap {
+  pub(crate) fn from_bitmap(bitmap: Bitmap) -> Self {
+    Self {
+      bitmap: Some(bitmap),
+    }
+  }
+
+  /// `0` once the bitmap is closed
+  #[napi(getter)]
+  pub fn get_width(&self) -> u32 {
+    self.bitmap.as_ref().map(|b| b.0.width as u32).unwrap_or(0)
+  }
+
+  /// `0` once the bitmap is closed
+  #[napi(getter)]
+  pub fn get_height(&self) -> u32 {
+    self.bitmap.as_ref().map(|b| b.0.height as u32).unwrap_or(0)
+  }
+
+  /// Release the pixels, a closed bitmap draws nothing
+  #[napi]
+  pub fn close(&mut self) {
+    self.bitmap = None;
+  }
+}
+
+#[napi(object)]
+pub struct ImageBitmapOptions {
+  pub resize_width: Option<u32>,
+  pub resize_height: Option<u32>,
+  /// `pixelated` | `low` | `medium` | `high`, default is `low`
+  pub resize_quality: Option<String>,
+  /// `from-image` | `flipY` | `none`, default is `from-image`
+  pub image_orientation: Option<String>,
+}
+
+/// The validated crop rect and resize options of `createImageBitmap`
+struct ImageBitmapRequest {
+  crop: [f64; 4],
+  output_width: u32,
+  output_height: u32,
+  /// `None` for the `pixelated` resize quality
+  smoothing_quality: Option<FilterQuality>,
+  flip_y: bool,
+}
+
+/// Crop `source` to the `sx`, `sy`, `sw`, `sh` rect and scale it to the resize options,
+/// the parts of the rect outside of the source are transparent
+#[napi(js_name = "createImageBitmapSync")]
+pub fn create_image_bitmap(
+  source: Either6<
+    &mut CanvasElement,
+    &mut SVGCanvas,
+    &mut Image,
+    &mut ImageBitmap,
+    &mut OffscreenCanvas,
+    &mut ImageData,
+  >,
+  sx_or_options: Option<Either<f64, ImageBitmapOptions>>,
+  sy: Option<f64>,
+  sw: Option<f64>,
+  sh: Option<f64>,
+  options: Option<ImageBitmapOptions>,
+) -> Result<ImageBitmap> {
+  let (bitmap, request) = image_bitmap_request(source, sx_or_options, sy, sw, sh, options)?;
+  Ok(ImageBitmap::from_bitmap(render_image_bitmap(
+    bitmap.as_ref(),
+    &request,
+  )?))
+}
+
+/// `createImageBitmapSync` cropping and scaling off the main thread, `createImageBitmap` in `index.js` calls it.
+/// The pixels of the source are copied first so the source can change or be closed meanwhile
+#[napi(js_name = "createImageBitmapAsync")]
+pub fn create_image_bitmap_async(
+  source: Either6<
+    &mut CanvasElement,
+    &mut SVGCanvas,
+    &mut Image,
+    &mut ImageBitmap,
+    &mut OffscreenCanvas,
+    &mut ImageData,
+  >,
+  sx_or_options: Option<Either<f64, ImageBitmapOptions>>,
+  sy: Option<f64>,
+  sw: Option<f64>,
+  sh: Option<f64>,
+  options: Option<ImageBitmapOptions>,
+) -> Result<AsyncTask<CreateImageBitmap>> {
+  let (bitmap, request) = image_bitmap_request(source, sx_or_options, sy, sw, sh, options)?;
+  let bitmap = owned_copy(bitmap.as_ref())?;
+  Ok(AsyncTask::new(CreateImageBitmap { bitmap, request }))
+}
+
+pub struct CreateImageBitmap {
+  bitmap: OwnedBitmap,
+  request: ImageBitmapRequest,
+}
+
+#[napi]
+impl Task for CreateImageBitmap {
+  type Output = OwnedBitmap;
+  type JsValue = ImageBitmap;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    owned_copy(&render_image_bitmap(self.bitmap.bitmap(), &self.request)?)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(ImageBitmap::from_bitmap(output.into_inner()))
+  }
+}
+
+/// Only the copies are moved between the threads, the source bitmaps may share their pixels
+fn owned_copy(bitmap: &Bitmap) -> Result<OwnedBitmap> {
+  bitmap.deep_copy().ok_or_else(|| {
+    Error::new(
+      Status::GenericFailure,
+      "Copy the pixels of the ImageBitmap failed".to_owned(),
+    )
+  })
+}
+
+/// Validate the arguments of `createImageBitmap`, the bitmap of an `ImageData` is a copy of its data
+fn image_bitmap_request<'a>(
+  source: Either6<
+    &'a mut CanvasElement,
+    &'a mut SVGCanvas,
+    &'a mut Image,
+    &'a mut ImageBitmap,
+    &'a mut OffscreenCanvas,
+    &'a mut ImageData,
+  >,
+  sx_or_options: Option<Either<f64, ImageBitmapOptions>>,
+  sy: Option<f64>,
+  sw: Option<f64>,
+  sh: Option<f64>,
+  options: Option<ImageBitmapOptions>,
+) -> Result<(BitmapRef<'a>, ImageBitmapRequest)> {
+  let bitmap = match source {
+    Either6::A(canvas) => image_bitmap(Either5::A(canvas)),
+    Either6::B(svg) => image_bitmap(Either5::B(svg)),
+    Either6::C(image) => image_bitmap(Either5::C(image)),
+    Either6::D(image_bitmap_source) => image_bitmap(Either5::D(image_bitmap_source)),
+    Either6::E(offscreen) => image_bitmap(Either5::E(offscreen)),
+    Either6::F(image_data) => {
+      if image_data.storage_format != StorageFormat::Uint8 {
+        return Err(Error::new(
+          Status::InvalidArg,
+          "ImageBitmap from float16 ImageData is not supported".to_owned(),
+        ));
+      }
+      let size = image_data.width * image_data.height * 4;
+      Bitmap::from_image_data(
+        image_data.data,
+        image_data.width,
+        image_data.height,
+        image_data.width * 4,
+        size,
+        ColorType::RGBA8888,
+        AlphaType::Unpremultiplied,
+      )
+      .deep_copy()
+      .map(|bitmap| BitmapRef::Owned(bitmap.into_inner()))
+    }
+  }
+  .ok_or_else(|| {
+    Error::new(
+      Status::InvalidArg,
+      "InvalidStateError: the image source is not decoded or is closed".to_owned(),
+    )
+  })?;
+  let (width, height) = (
+    bitmap.as_ref().0.width as f64,
+    bitmap.as_ref().0.height as f64,
+  );
+  let (crop, options) = match (sx_or_options, sy, sw, sh) {
+    (Some(Either::A(sx)), Some(sy), Some(sw), Some(sh)) => {
+      if sw == 0.0 || sh == 0.0 {
+        return Err(Error::new(
+          Status::InvalidArg,
+          "RangeError: the crop rect of the ImageBitmap is empty".to_owned(),
+        ));
+      }
+      ([sx, sy, sw, sh], options)
+    }
+    (Some(Either::A(_)), ..) => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "TypeError: createImageBitmap takes 1, 2, 5 or 6 arguments".to_owned(),
+      ))
+    }
+    (Some(Either::B(options)), ..) => ([0.0, 0.0, width, height], Some(options)),
+    (None, ..) => ([0.0, 0.0, width, height], options),
+  };
+  let crop_width = crop[2].abs();
+  let crop_height = crop[3].abs();
+  let options = options.unwrap_or(ImageBitmapOptions {
+    resize_width: None,
+    resize_height: None,
+    resize_quality: None,
+    image_orientation: None,
+  });
+  // a single resize dimension keeps the aspect ratio of the crop rect
+  let (output_width, output_height) = match (options.resize_width, options.resize_height) {
+    (Some(w), Some(h)) => (w, h),
+    (Some(w), None) => (w, (crop_height * w as f64 / crop_width).ceil() as u32),
+    (None, Some(h)) => ((crop_width * h as f64 / crop_height).ceil() as u32, h),
+    (None, None) => (crop_width.ceil() as u32, crop_height.ceil() as u32),
+  };
+  if output_width == 0 || output_height == 0 {
+    return Err(Error::new(
+      Status::InvalidArg,
+      "InvalidStateError: the ImageBitmap would be empty".to_owned(),
+    ));
+  }
+  let smoothing_quality = match options.resize_quality.as_deref() {
+    Some("pixelated") => None,
+    Some(quality) => Some(FilterQuality::from_str(quality)?),
+    None => Some(FilterQuality::Low),
+  };
+  let flip_y = match options.image_orientation.as_deref() {
+    None | Some("from-image") | Some("none") => false,
+    Some("flipY") => true,
+    Some(orientation) => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("TypeError: {orientation} is not a valid imageOrientation"),
+      ))
+    }
+  };
+  Ok((
+    bitmap,
+    ImageBitmapRequest {
+      crop,
+      output_width,
+      output_height,
+      smoothing_quality,
+      flip_y,
+    },
+  ))
+}
+
+fn render_image_bitmap(bitmap: &Bitmap, request: &ImageBitmapRequest) -> Result<Bitmap> {
+  let (output_width, output_height) = (request.output_width, request.output_height);
+  let mut context = Context::new(output_width, output_height, ColorSpace::default())?;
+  match request.smoothing_quality {
+    Some(quality) => context.state.image_smoothing_quality = quality,
+    None => context.state.image_smoothing_enabled = false,
+  }
+  if request.flip_y {
+    context.set_transform(Matrix::new(1.0, 0.0, 0.0, 0.0, -1.0, output_height as f32));
+  }
+  let crop = request.crop;
+  context.draw_image(
+    bitmap,
+    crop[0] as f32,
+    crop[1] as f32,
+    crop[2] as f32,
+    crop[3] as f32,
+    0.0,
+    0.0,
+    output_width as f32,
+    output_height as f32,
+  )?;
+  Ok(context.surface.get_bitmap())
+}
+
+fn unsupported_image_type() -> Error {
+  Error::new(Status::InvalidArg, "Unsupported image type")
+}
+
+#[test]
+fn put_image_data_dirty_rect_normalize() {
+  assert_eq!(
+    put_image_data_dirty_rect(10, 10, 3.0, 3.0, 4.0, 4.0),
+    Some((3, 3, 4, 4))
+  );
+  // negative dimensions extend to the left and top
+  assert_eq!(
+    put_image_data_dirty_rect(10, 10, 7.0, 7.0, -4.0, -4.0),
+    Some((3, 3, 4, 4))
+  );
+  // clipped to the image
+  assert_eq!(
+    put_image_data_dirty_rect(10, 10, -2.0, 8.0, 5.0, 5.0),
+    Some((0, 8, 3, 2))
+  );
+  assert_eq!(put_image_data_dirty_rect(10, 10, 10.0, 0.0, 5.0, 5.0), None);
+  assert_eq!(put_image_data_dirty_rect(10, 10, 0.0, 0.0, 0.0, 5.0), None);
+}
@@ -13,17 +13,20 @@ use std::str::FromStr;
 use std::{mem, slice};
 
 use base64::Engine;
-use napi::bindgen_prelude::{AsyncTask, ClassInstance, Either3, This, Unknown};
+use napi::bindgen_prelude::{
+  AsyncTask, ClassInstance, Either3, FromNapiValue, This, TypeName, Unknown, ValidateNapiValue,
+};
 use napi::*;
 
 use ctx::{
-  CanvasRenderingContext2D, Context, ContextData, ContextOutputData, SvgExportFlag,
-  FILL_STYLE_HIDDEN_NAME, STROKE_STYLE_HIDDEN_NAME,
+  CanvasRenderingContext2D, Context, ContextData, ContextOutputData, FILL_STYLE_HIDDEN_NAME,
+  STROKE_STYLE_HIDDEN_NAME,
 };
 use font::{init_font_regexp, FONT_REGEXP};
-use sk::{ColorSpace, SkiaDataRef};
+use sk::{ColorSpace, PngEncodeOptions, SkiaDataRef, StorageFormat};
 
 use avif::AvifConfig;
+use png::{PngConfig, PngStreamEncoder};
 
 #[cfg(all(
   not(all(target_os = "linux", target_env = "musl", target_arch = "aarch64")),
@@ -33,15 +36,19 @@ use avif::AvifConfig;
 static ALLOC: mimalloc_rust::GlobalMiMalloc = mimalloc_rust::GlobalMiMalloc;
 
 mod avif;
+mod color;
 mod ctx;
 mod error;
 mod filter;
 mod font;
 pub mod global_fonts;
 mod gradient;
-mod image;
+pub mod image;
+mod offscreen;
 pub mod path;
 mod pattern;
+mod pdf;
+mod png;
 #[allow(dead_code)]
 mod sk;
 mod state;
@@ -51,6 +58,7 @@ const MIME_WEBP: &str = "image/webp";
 const MIME_PNG: &str = "image/png";
 const MIME_JPEG: &str = "image/jpeg";
 const MIME_AVIF: &str = "image/avif";
+const MIME_RAW: &str = "raw";
 
 // Consistent with the default value of JPEG quality in Blink
 // https://source.chromium.org/chromium/chromium/src/+/main:third_party/blink/renderer/platform/image-encoders/image_encoder.cc;l=85;drc=81c6f843fdfd8ef660d733289a7a32abe68e247a
@@ -70,6 +78,14 @@ fn init() {
 pub struct CanvasRenderingContext2DAttributes {
   pub alpha: Option<bool>,
   pub color_space: Option<String>,
+  /// `unorm8` or `float16`
+  pub color_type: Option<String>,
+}
+
+#[napi(object)]
+pub struct ResizeOptions {
+  /// keep the drawing at the top left corner instead of clearing the canvas, default is `false`
+  pub preserve_content: Option<bool>,
 }
 
 #[napi]
@@ -82,24 +98,14 @@ pub struct CanvasElement {
 #[napi]
 impl CanvasElement {
   fn create_context(
-    mut env: Env,
+    env: Env,
     width: u32,
     height: u32,
   ) -> Result<ClassInstance<CanvasRenderingContext2D>> {
-    let ctx = CanvasRenderingContext2D {
+    CanvasRenderingContext2D {
       context: Context::new(width, height, ColorSpace::default())?,
     }
-    .into_instance(env)?;
-    ctx.as_object(env).define_properties(&[
-      Property::new(FILL_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
-        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
-      Property::new(STROKE_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
-        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
-    ])?;
-    env.adjust_external_memory((width * height * 4) as i64)?;
-    Ok(ctx)
+    .into_js_instance(env)
   }
 
   #[napi(constructor)]
@@ -121,15 +127,8 @@ impl CanvasElement {
   }
 
   #[napi(setter)]
-  pub fn set_width(&mut self, mut env: Env, width: u32) -> Result<()> {
-    self.width = width;
-    let height = self.height;
-    let old_ctx = mem::replace(
-      &mut self.ctx.context,
-      Context::new(width, height, ColorSpace::default())?,
-    );
-    env.adjust_external_memory((width as i64 - old_ctx.width as i64) * 4)?;
-    Ok(())
+  pub fn set_width(&mut self, env: Env, width: u32) -> Result<()> {
+    self.resize(env, width, self.height, None)
   }
 
   #[napi(getter)]
@@ -138,15 +137,8 @@ impl CanvasElement {
   }
 
   #[napi(setter)]
-  pub fn set_height(&mut self, mut env: Env, height: u32) -> Result<()> {
-    self.height = height;
-    let width = self.width;
-    let old_ctx = mem::replace(
-      &mut self.ctx.context,
-      Context::new(width, height, ColorSpace::default())?,
-    );
-    env.adjust_external_memory((height as i64 - old_ctx.height as i64) * 4)?;
-    Ok(())
+  pub fn set_height(&mut self, env: Env, height: u32) -> Result<()> {
+    self.resize(env, self.width, height, None)
   }
 
   #[napi(getter)]
@@ -154,6 +146,52 @@ impl CanvasElement {
     self.height
   }
 
+  /// The filter applied to every drawing of the canvas when `ctx.filter` is `none`, invalid filters are ignored
+  #[napi(getter)]
+  pub fn get_default_filter(&self) -> String {
+    self.ctx.context.default_filter.clone()
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_default_filter(&mut self, filter: String) {
+    let _ = self.ctx.context.set_default_filter(&filter);
+  }
+
+  /// Apply `ctx.filter` on top of `defaultFilter` instead of replacing it, default is `false`
+  #[napi(getter)]
+  pub fn get_compose_with_default(&self) -> bool {
+    self.ctx.context.compose_with_default
+  }
+
+  #[napi(setter, return_if_invalid)]
+  pub fn set_compose_with_default(&mut self, compose: bool) {
+    self.ctx.context.compose_with_default = compose;
+  }
+
+  /// Resize the canvas and reset the state of its context like assigning `width` and `height`,
+  /// the context attributes are kept and the drawing too with `preserveContent`
+  #[napi]
+  pub fn resize(
+    &mut self,
+    mut env: Env,
+    width: u32,
+    height: u32,
+    options: Option<ResizeOptions>,
+  ) -> Result<()> {
+    let preserve_content = options
+      .and_then(|options| options.preserve_content)
+      .unwrap_or(false);
+    let old_size = self.width as i64 * self.height as i64;
+    self.ctx.context.resize(width, height, preserve_content)?;
+    let mut ctx = self.ctx.as_object(env);
+    ctx.set(FILL_STYLE_HIDDEN_NAME, "#000")?;
+    ctx.set(STROKE_STYLE_HIDDEN_NAME, "#000")?;
+    self.width = width;
+    self.height = height;
+    env.adjust_external_memory((width as i64 * height as i64 - old_size) * 4)?;
+    Ok(())
+  }
+
   #[napi]
   pub fn get_context(
     &mut self,
@@ -167,24 +205,7 @@ impl CanvasElement {
         format!("{context_type} is not supported"),
       ));
     }
-    let context_2d = &mut self.ctx.context;
-    if !attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true) {
-      let mut fill_paint = context_2d.fill_paint()?;
-      fill_paint.set_color(255, 255, 255, 255);
-      context_2d.alpha = false;
-      context_2d.surface.draw_rect(
-        0f32,
-        0f32,
-        self.width as f32,
-        self.height as f32,
-        &fill_paint,
-      );
-    }
-    let color_space = attrs
-      .and_then(|a| a.color_space)
-      .and_then(|cs| ColorSpace::from_str(&cs).ok())
-      .unwrap_or_default();
-    context_2d.color_space = color_space;
+    apply_context_attributes(&mut self.ctx.context, attrs)?;
     this.get_named_property("ctx")
   }
 
@@ -192,7 +213,7 @@ impl CanvasElement {
   pub fn encode(
     &self,
     format: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, EncodeConfig, Unknown>,
   ) -> Result<AsyncTask<ContextData>> {
     Ok(AsyncTask::new(
       self.encode_inner(format, quality_or_config)?,
@@ -204,7 +225,7 @@ impl CanvasElement {
     &self,
     env: Env,
     format: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, EncodeConfig, Unknown>,
   ) -> Result<JsBuffer> {
     let mut task = self.encode_inner(format, quality_or_config)?;
     let output = task.compute()?;
@@ -216,9 +237,27 @@ impl CanvasElement {
     &self,
     env: Env,
     mime: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, EncodeConfig, Unknown>,
   ) -> Result<JsBuffer> {
     let mime = mime.as_str();
+    if mime == MIME_RAW {
+      let premultiplied = match &quality_or_config {
+        Either3::B(config) => config.raw.premultiplied.unwrap_or(false),
+        _ => false,
+      };
+      let ctx2d = &self.ctx.context;
+      ctx2d.ensure_not_lost()?;
+      let pixels = ctx2d
+        .surface
+        .read_rgba(ctx2d.color_space, premultiplied)
+        .ok_or_else(|| {
+          Error::new(
+            Status::GenericFailure,
+            "Read pixels from surface failed".to_owned(),
+          )
+        })?;
+      return env.create_buffer_with_data(pixels).map(|b| b.into_raw());
+    }
     let context_data = get_data_ref(&self.ctx.context, mime, &quality_or_config)?;
     match context_data {
       ContextOutputData::Skia(data_ref) => unsafe {
@@ -244,6 +283,7 @@ impl CanvasElement {
   #[napi]
   pub fn data(&self, env: Env) -> Result<JsBuffer> {
     let ctx2d = &self.ctx.context;
+    ctx2d.ensure_not_lost()?;
 
     let surface_ref = ctx2d.surface.reference();
 
@@ -264,7 +304,7 @@ impl CanvasElement {
   pub fn to_data_url_async(
     &self,
     mime: Option<String>,
-    quality_or_config: Either3<f64, AvifConfig, Unknown>,
+    quality_or_config: Either3<f64, EncodeConfig, Unknown>,
   ) -> Result<AsyncTask<AsyncDataUrl>> {
     Ok(AsyncTask::new(
       self.to_data_url_inner(mime.as_deref(), quality_or_config)?,
@@ -275,12 +315,20 @@ impl CanvasElement {
   pub fn to_data_url(
     &self,
     mime: Option<String>,
-    quality_or_config: Either3<f64, AvifConfig, Unknown>,
+    quality_or_config: Either3<f64, EncodeConfig, Unknown>,
   ) -> Result<String> {
     let mut task = self.to_data_url_inner(mime.as_deref(), quality_or_config)?;
     task.compute()
   }
 
+  /// Start encoding a snapshot of the canvas as png, backs `createPNGStream`
+  #[napi(js_name = "pngStreamEncoder")]
+  pub fn png_stream_encoder(&self, config: Option<PngConfig>) -> Result<PngStreamEncoder> {
+    let options = PngEncodeOptions::try_from(&config.unwrap_or_default())?;
+    self.ctx.context.ensure_not_lost()?;
+    PngStreamEncoder::new(&self.ctx.context.surface, options)
+  }
+
   #[napi]
   pub fn save_png(&self, path: String) {
     let ctx2d = &self.ctx.context;
@@ -290,43 +338,26 @@ impl CanvasElement {
   fn encode_inner(
     &self,
     format: String,
-    quality_or_config: Either3<u32, AvifConfig, Unknown>,
+    quality_or_config: Either3<u32, EncodeConfig, Unknown>,
   ) -> Result<ContextData> {
-    let format_str = format.as_str();
-    let quality = quality_or_config.to_quality(format_str);
-    let ctx2d = &self.ctx.context;
-    let surface_ref = ctx2d.surface.reference();
-
-    let task = match format_str {
-      "webp" => ContextData::Webp(surface_ref, quality),
-      "jpeg" => ContextData::Jpeg(surface_ref, quality),
-      "png" => ContextData::Png(surface_ref),
-      "avif" => {
-        let cfg = AvifConfig::from(&quality_or_config);
-        ContextData::Avif(surface_ref, cfg.into(), ctx2d.width, ctx2d.height)
-      }
-      _ => {
-        return Err(Error::new(
-          Status::InvalidArg,
-          format!("{format_str} is not valid format"),
-        ))
-      }
-    };
-
-    Ok(task)
+    encode_context(&self.ctx.context, format, quality_or_config)
   }
 
   fn to_data_url_inner(
     &self,
     mime: Option<&str>,
-    quality_or_config: Either3<f64, AvifConfig, Unknown>,
+    quality_or_config: Either3<f64, EncodeConfig, Unknown>,
   ) -> Result<AsyncDataUrl> {
-    let mime = mime.unwrap_or(MIME_PNG);
+    // the types without an encoder fall back to png, as the browsers do
+    let mime = match mime {
+      Some(mime @ (MIME_WEBP | MIME_JPEG | MIME_AVIF)) => mime,
+      _ => MIME_PNG,
+    };
     let data_ref = get_data_ref(
       &self.ctx.context,
       mime,
       &match quality_or_config {
-        Either3::A(q) => Either3::A((q * 100.0) as u32),
+        Either3::A(q) => Either3::A(data_url_quality(q, mime)),
         Either3::B(s) => Either3::B(s),
         Either3::C(u) => Either3::C(u),
       },
@@ -338,23 +369,138 @@ impl CanvasElement {
   }
 }
 
+pub(crate) fn encode_context(
+  ctx2d: &Context,
+  format: String,
+  quality_or_config: Either3<u32, EncodeConfig, Unknown>,
+) -> Result<ContextData> {
+  ctx2d.ensure_not_lost()?;
+  let format_str = format.as_str();
+  let quality = quality_or_config.to_quality(format_str);
+  let surface_ref = ctx2d.surface.reference();
+
+  let task = match format_str {
+    "webp" => ContextData::Webp(surface_ref, quality),
+    "jpeg" => ContextData::Jpeg(surface_ref, quality),
+    "png" => ContextData::Png(surface_ref, png_options(&quality_or_config)?),
+    "avif" => {
+      let cfg = AvifConfig::from(&quality_or_config);
+      ContextData::Avif(surface_ref, cfg.into(), ctx2d.width, ctx2d.height)
+    }
+    _ => {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("{format_str} is not valid format"),
+      ))
+    }
+  };
+
+  Ok(task)
+}
+
+/// Apply the `getContext` attributes, they are only applied once while the context is still in its default configuration
+pub(crate) fn apply_context_attributes(
+  context_2d: &mut Context,
+  attrs: Option<CanvasRenderingContext2DAttributes>,
+) -> Result<()> {
+  let alpha = attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true);
+  let color_space = attrs
+    .as_ref()
+    .and_then(|a| a.color_space.as_ref())
+    .and_then(|cs| ColorSpace::from_str(cs).ok())
+    .unwrap_or_default();
+  let storage_format = match attrs.as_ref().and_then(|a| a.color_type.as_ref()) {
+    Some(color_type) => StorageFormat::from_str(color_type)?,
+    None => StorageFormat::default(),
+  };
+  let is_default = context_2d.alpha
+    && context_2d.color_space == ColorSpace::default()
+    && context_2d.storage_format == StorageFormat::default();
+  if is_default
+    && (!alpha
+      || color_space != ColorSpace::default()
+      || storage_format != StorageFormat::default())
+  {
+    context_2d.reset_surface(alpha, color_space, storage_format)?;
+  }
+  Ok(())
+}
+
 #[napi(object)]
 pub struct ContextAttr {
   pub alpha: Option<bool>,
 }
 
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct RawConfig {
+  /// premultiply the color channels by alpha, default is `false`
+  pub premultiplied: Option<bool>,
+}
+
+/// The config object passed to the encoders,
+/// it's read as the config of every format and the unrelated fields are ignored.
+#[derive(Default, Clone)]
+pub struct EncodeConfig {
+  avif: AvifConfig,
+  png: PngConfig,
+  raw: RawConfig,
+}
+
+impl TypeName for EncodeConfig {
+  fn type_name() -> &'static str {
+    "EncodeConfig"
+  }
+
+  fn value_type() -> ValueType {
+    ValueType::Object
+  }
+}
+
+impl ValidateNapiValue for EncodeConfig {}
+
+impl FromNapiValue for EncodeConfig {
+  unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+    Ok(EncodeConfig {
+      avif: unsafe { AvifConfig::from_napi_value(env, napi_val) }?,
+      png: unsafe { PngConfig::from_napi_value(env, napi_val) }?,
+      raw: unsafe { RawConfig::from_napi_value(env, napi_val) }?,
+    })
+  }
+}
+
+impl From<&Either3<u32, EncodeConfig, Unknown>> for AvifConfig {
+  fn from(value: &Either3<u32, EncodeConfig, Unknown>) -> Self {
+    if let Either3::B(config) = value {
+      config.avif.clone()
+    } else {
+      Default::default()
+    }
+  }
+}
+
+fn png_options(
+  quality_or_config: &Either3<u32, EncodeConfig, Unknown>,
+) -> Result<PngEncodeOptions> {
+  match quality_or_config {
+    Either3::B(config) => Ok(PngEncodeOptions::try_from(&config.png)?),
+    _ => Ok(PngEncodeOptions::default()),
+  }
+}
+
 fn get_data_ref(
   ctx2d: &Context,
   mime: &str,
-  quality_or_config: &Either3<u32, AvifConfig, Unknown>,
+  quality_or_config: &Either3<u32, EncodeConfig, Unknown>,
 ) -> Result<ContextOutputData> {
+  ctx2d.ensure_not_lost()?;
   let surface_ref = ctx2d.surface.reference();
   let quality = quality_or_config.to_quality(mime);
 
   if let Some(data_ref) = match mime {
     MIME_WEBP => surface_ref.encode_data(sk::SkEncodedImageFormat::Webp, quality),
     MIME_JPEG => surface_ref.encode_data(sk::SkEncodedImageFormat::Jpeg, quality),
-    MIME_PNG => surface_ref.png_data(),
+    MIME_PNG => surface_ref.png_data(png_options(quality_or_config)?),
     MIME_AVIF => {
       let (data, size) = surface_ref.data().ok_or_else(|| {
         Error::new(
@@ -420,20 +566,32 @@ trait ToQuality {
   fn to_quality(&self, mime: &str) -> u8;
 }
 
-impl ToQuality for &Either3<u32, AvifConfig, Unknown> {
+impl ToQuality for &Either3<u32, EncodeConfig, Unknown> {
   fn to_quality(&self, mime_or_format: &str) -> u8 {
     if let Either3::A(q) = &self {
-      *q as u8
+      (*q).min(100) as u8
     } else {
-      match mime_or_format {
-        MIME_WEBP | "webp" => DEFAULT_WEBP_QUALITY,
-        _ => DEFAULT_JPEG_QUALITY, // https://developer.mozilla.org/en-US/docs/Web/API/HTMLCanvasElement/toDataURL
-      }
+      default_quality(mime_or_format)
     }
   }
 }
 
-impl ToQuality for Either3<u32, AvifConfig, Unknown> {
+fn default_quality(mime_or_format: &str) -> u8 {
+  match mime_or_format {
+    MIME_WEBP | "webp" => DEFAULT_WEBP_QUALITY,
+    _ => DEFAULT_JPEG_QUALITY, // https://developer.mozilla.org/en-US/docs/Web/API/HTMLCanvasElement/toDataURL
+  }
+}
+
+/// `toDataURL` takes the quality in 0-1 scale, the encoders in 0-100 scale
+fn data_url_quality(quality: f64, mime: &str) -> u32 {
+  if quality.is_nan() {
+    return default_quality(mime) as u32;
+  }
+  (quality.clamp(0.0, 1.0) * 100.0).round() as u32
+}
+
+impl ToQuality for Either3<u32, EncodeConfig, Unknown> {
   fn to_quality(&self, mime: &str) -> u8 {
     ToQuality::to_quality(&self, mime)
   }
@@ -449,28 +607,13 @@ pub struct SVGCanvas {
 #[napi]
 impl SVGCanvas {
   #[napi(constructor)]
-  pub fn new(
-    mut env: Env,
-    mut this: This,
-    width: u32,
-    height: u32,
-    flag: SvgExportFlag,
-  ) -> Result<Self> {
-    let ctx = CanvasRenderingContext2D::into_instance(
-      CanvasRenderingContext2D {
-        context: Context::new_svg(width, height, flag.into(), ColorSpace::default())?,
-      },
-      env,
-    )?;
-    ctx.as_object(env).define_properties(&[
-      Property::new(FILL_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
-        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
-      Property::new(STROKE_STYLE_HIDDEN_NAME)?
-        .with_value(&env.create_string("#000")?)
-        .with_property_attributes(PropertyAttributes::Writable | PropertyAttributes::Configurable),
-    ])?;
-    env.adjust_external_memory((width * height * 4) as i64)?;
+  pub fn new(env: Env, mut this: This, width: u32, height: u32, flag: Option<u32>) -> Result<Self> {
+    // `SvgExportFlag` values can be combined with `|`
+    let flags = sk::SvgExportFlag::check_bits(flag.unwrap_or(0))?;
+    let ctx = CanvasRenderingContext2D {
+      context: Context::new_svg(width, height, flags, ColorSpace::default())?,
+    }
+    .into_js_instance(env)?;
     this.define_properties(&[Property::new("ctx")?
       .with_value(&ctx)
       .with_property_attributes(PropertyAttributes::Default)])?;
@@ -491,9 +634,10 @@ impl SVGCanvas {
       ));
     }
     let context_2d = &mut self.ctx.context;
-    if !attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true) {
+    if context_2d.alpha && !attrs.as_ref().and_then(|a| a.alpha).unwrap_or(true) {
+      // svg has no opaque surface, paint the opaque black backdrop instead
       let mut fill_paint = context_2d.fill_paint()?;
-      fill_paint.set_color(255, 255, 255, 255);
+      fill_paint.set_color(0, 0, 0, 255);
       context_2d.alpha = false;
       context_2d.surface.draw_rect(
         0f32,
@@ -511,6 +655,17 @@ impl SVGCanvas {
     this.get_named_property("ctx")
   }
 
+  #[napi]
+  pub fn to_buffer(&self, env: Env, mime: String) -> Result<JsBuffer> {
+    if mime != "image/svg+xml" {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("{mime} is not supported by the svg canvas, use image/svg+xml"),
+      ));
+    }
+    self.get_content(env)
+  }
+
   #[napi]
   pub fn get_content(&self, env: Env) -> Result<JsBuffer> {
     let svg_data_stream = self.ctx.context.stream.as_ref().unwrap();
@@ -529,3 +684,14 @@ impl SVGCanvas {
 pub fn clear_all_cache() {
   unsafe { sk::ffi::skiac_clear_all_cache() };
 }
+
+#[test]
+fn data_url_quality_is_clamped() {
+  assert_eq!(data_url_quality(0.5, MIME_JPEG), 50);
+  assert_eq!(data_url_quality(0.29, MIME_JPEG), 29);
+  assert_eq!(data_url_quality(-1.0, MIME_JPEG), 0);
+  assert_eq!(data_url_quality(2.0, MIME_WEBP), 100);
+  assert_eq!(data_url_quality(f64::INFINITY, MIME_WEBP), 100);
+  assert_eq!(data_url_quality(f64::NAN, MIME_WEBP), 80);
+  assert_eq!(data_url_quality(f64::NAN, MIME_JPEG), 92);
+}
@@ -0,0 +1,130 @@
+use std::mem;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, Property, PropertyAttributes};
+
+use crate::ctx::{CanvasRenderingContext2D, Context, ContextData};
+use crate::image::ImageBitmap;
+use crate::sk::{ColorSpace, StorageFormat};
+use crate::{
+  apply_context_attributes, encode_context, CanvasRenderingContext2DAttributes, EncodeConfig,
+};
+
+/// A canvas without any display, `convertToBlob` is added in `index.js`
+#[napi]
+pub struct OffscreenCanvas {
+  width: u32,
+  height: u32,
+  pub(crate) ctx: ClassInstance<CanvasRenderingContext2D>,
+}
+
+#[napi]
+impl OffscreenCanvas {
+  #[napi(constructor)]
+  pub fn new(env: Env, mut this: This, width: u32, height: u32) -> Result<Self> {
+    let ctx = CanvasRenderingContext2D {
+      context: Context::new(width, height, ColorSpace::default())?,
+    }
+    .into_js_instance(env)?;
+    this.define_properties(&[Property::new("ctx")?
+      .with_value(&ctx)
+      .with_property_attributes(PropertyAttributes::Default)])?;
+    ctx
+      .as_object(env)
+      .define_properties(&[Property::new("canvas")?
+        .with_value(&this)
+        .with_property_attributes(
+          PropertyAttributes::Default
+            | PropertyAttributes::Writable
+            | PropertyAttributes::Enumerable,
+        )])?;
+    Ok(Self { width, height, ctx })
+  }
+
+  #[napi(getter)]
+  pub fn get_width(&self) -> u32 {
+    self.width
+  }
+
+  #[napi(setter)]
+  pub fn set_width(&mut self, mut env: Env, width: u32) -> Result<()> {
+    self.width = width;
+    self.resize(&mut env)
+  }
+
+  #[napi(getter)]
+  pub fn get_height(&self) -> u32 {
+    self.height
+  }
+
+  #[napi(setter)]
+  pub fn set_height(&mut self, mut env: Env, height: u32) -> Result<()> {
+    self.height = height;
+    self.resize(&mut env)
+  }
+
+  #[napi]
+  pub fn get_context(
+    &mut self,
+    this: This,
+    context_type: String,
+    attrs: Option<CanvasRenderingContext2DAttributes>,
+  ) -> Result<Unknown> {
+    if context_type != "2d" {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("{context_type} is not supported"),
+      ));
+    }
+    apply_context_attributes(&mut self.ctx.context, attrs)?;
+    this.get_named_property("ctx")
+  }
+
+  #[napi]
+  pub fn encode(
+    &self,
+    format: String,
+    quality_or_config: Either3<u32, EncodeConfig, Unknown>,
+  ) -> Result<AsyncTask<ContextData>> {
+    Ok(AsyncTask::new(encode_context(
+      &self.ctx.context,
+      format,
+      quality_or_config,
+    )?))
+  }
+
+  /// Move the current pixels into an `ImageBitmap` and leave the canvas transparent black
+  #[napi]
+  pub fn transfer_to_image_bitmap(&mut self) -> ImageBitmap {
+    let context = &mut self.ctx.context;
+    let bitmap = context.surface.get_bitmap();
+    if context.alpha {
+      context.surface.canvas.clear();
+    } else {
+      context.surface.canvas.fill(0, 0, 0, 255);
+    }
+    ImageBitmap::from_bitmap(bitmap)
+  }
+}
+
+impl OffscreenCanvas {
+  fn resize(&mut self, env: &mut Env) -> Result<()> {
+    let old_ctx = mem::replace(
+      &mut self.ctx.context,
+      Context::new(self.width, self.height, ColorSpace::default())?,
+    );
+    // the `getContext` attributes and the canvas filter outlive the resize
+    let context = &mut self.ctx.context;
+    if !old_ctx.alpha
+      || old_ctx.color_space != ColorSpace::default()
+      || old_ctx.storage_format != StorageFormat::default()
+    {
+      context.reset_surface(old_ctx.alpha, old_ctx.color_space, old_ctx.storage_format)?;
+    }
+    context.inherit_canvas_filter(&old_ctx);
+    env.adjust_external_memory(
+      (self.width as i64 * self.height as i64 - old_ctx.width as i64 * old_ctx.height as i64) * 4,
+    )?;
+    Ok(())
+  }
+}
@@ -5,14 +5,113 @@ use crate::sk::{
   StrokeCap as SkStrokeCap, StrokeJoin as SkStrokeJoin,
 };
 
+/// `DOMMatrix2DInit`, the missing members are taken from the identity matrix
 #[napi(object)]
 pub struct Matrix {
-  pub a: f64,
-  pub b: f64,
-  pub c: f64,
-  pub d: f64,
-  pub e: f64,
-  pub f: f64,
+  pub a: Option<f64>,
+  pub b: Option<f64>,
+  pub c: Option<f64>,
+  pub d: Option<f64>,
+  pub e: Option<f64>,
+  pub f: Option<f64>,
+}
+
+impl Matrix {
+  fn to_sk_matrix(&self) -> SkMatrix {
+    SkMatrix::new(
+      self.a.unwrap_or(1.0) as f32,
+      self.c.unwrap_or(0.0) as f32,
+      self.e.unwrap_or(0.0) as f32,
+      self.b.unwrap_or(0.0) as f32,
+      self.d.unwrap_or(1.0) as f32,
+      self.f.unwrap_or(0.0) as f32,
+    )
+  }
+}
+
+/// Check the arguments of `arc` and `ellipse`
+///
+/// Returns `false` if any argument is not finite, in which case nothing should be added to the path.
+// https://html.spec.whatwg.org/multipage/canvas.html#dom-context-2d-ellipse
+pub(crate) fn check_arc_arguments(arguments: &[f64], radii: &[f64]) -> Result<bool> {
+  if arguments.iter().chain(radii).any(|v| !v.is_finite()) {
+    return Ok(false);
+  }
+  if let Some(radius) = radii.iter().find(|r| **r < 0.0) {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!("IndexSizeError: The radius provided ({radius}) is negative."),
+    ));
+  }
+  Ok(true)
+}
+
+/// `DOMPointInit` form of a `roundRect` radius, `x` and `y` are the elliptical radii of the corner
+#[napi(object)]
+pub struct RoundRectRadius {
+  pub x: Option<f64>,
+  pub y: Option<f64>,
+}
+
+pub(crate) type RoundRectRadii =
+  Either4<f64, Vec<Either<f64, RoundRectRadius>>, RoundRectRadius, Undefined>;
+
+/// Normalize the `radii` argument of `roundRect` into `[upper_left, upper_right, lower_right, lower_left]`
+///
+/// Returns `None` if any radius is not finite, in which case nothing should be added to the path.
+// https://html.spec.whatwg.org/multipage/canvas.html#dom-context-2d-roundrect
+pub(crate) fn normalize_round_rect_radii(radii: RoundRectRadii) -> Result<Option<[(f32, f32); 4]>> {
+  let radii = match radii {
+    Either4::A(radius) => vec![Either::A(radius)],
+    Either4::B(radii) => radii,
+    Either4::C(radius) => vec![Either::B(radius)],
+    Either4::D(_) => vec![Either::A(0.0)],
+  };
+  if radii.is_empty() || radii.len() > 4 {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!(
+        "{} radii provided. Between one and four radii are necessary.",
+        radii.len()
+      ),
+    ));
+  }
+  let mut corners = Vec::with_capacity(radii.len());
+  for radius in radii {
+    let (x, y) = match radius {
+      Either::A(r) => (r, r),
+      Either::B(point) => (point.x.unwrap_or(0.0), point.y.unwrap_or(0.0)),
+    };
+    if !x.is_finite() || !y.is_finite() {
+      return Ok(None);
+    }
+    if x < 0.0 || y < 0.0 {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("Radius value {} is negative.", x.min(y)),
+      ));
+    }
+    corners.push((x as f32, y as f32));
+  }
+  Ok(Some(match corners.as_slice() {
+    [all] => [*all; 4],
+    [upper_left_lower_right, upper_right_lower_left] => [
+      *upper_left_lower_right,
+      *upper_right_lower_left,
+      *upper_left_lower_right,
+      *upper_right_lower_left,
+    ],
+    [upper_left, upper_right_lower_left, lower_right] => [
+      *upper_left,
+      *upper_right_lower_left,
+      *lower_right,
+      *upper_right_lower_left,
+    ],
+    [upper_left, upper_right, lower_right, lower_left] => {
+      [*upper_left, *upper_right, *lower_right, *lower_left]
+    }
+    _ => unreachable!(),
+  }))
 }
 
 #[napi]
@@ -107,6 +206,7 @@ impl From<StrokeJoin> for SkStrokeJoin {
 }
 
 #[napi(object)]
+#[derive(Default)]
 pub struct StrokeOptions {
   pub width: Option<f64>,
   pub miter_limit: Option<f64>,
@@ -114,6 +214,12 @@ pub struct StrokeOptions {
   pub join: Option<StrokeJoin>,
 }
 
+#[napi(object)]
+pub struct SvgStringOptions {
+  /// Encode the commands relative to the current point, default is `false`
+  pub relative: Option<bool>,
+}
+
 #[napi]
 pub struct Path {
   pub(crate) inner: SkPath,
@@ -145,14 +251,11 @@ impl Path {
     Ok(Path { inner })
   }
 
+  /// Append the sub paths of `sub_path` transformed by `matrix`, `sub_path` is left untouched
   #[napi]
   pub fn add_path(&mut self, sub_path: &Path, matrix: Option<Matrix>) {
     let transform = matrix
-      .map(|m| {
-        SkMatrix::new(
-          m.a as f32, m.c as f32, m.e as f32, m.b as f32, m.d as f32, m.f as f32,
-        )
-      })
+      .map(|m| m.to_sk_matrix())
       .unwrap_or_else(SkMatrix::identity);
     self.inner.add_path(&sub_path.inner, &transform);
   }
@@ -200,7 +303,10 @@ impl Path {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !check_arc_arguments(&[x, y, start_angle, end_angle], &[radius])? {
+      return Ok(());
+    }
     self.inner.arc(
       x as f32,
       y as f32,
@@ -209,6 +315,7 @@ impl Path {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi]
@@ -229,7 +336,13 @@ impl Path {
     start_angle: f64,
     end_angle: f64,
     anticlockwise: Option<bool>,
-  ) {
+  ) -> Result<()> {
+    if !check_arc_arguments(
+      &[x, y, rotation, start_angle, end_angle],
+      &[radius_x, radius_y],
+    )? {
+      return Ok(());
+    }
     self.inner.ellipse(
       x as f32,
       y as f32,
@@ -240,6 +353,7 @@ impl Path {
       end_angle as f32,
       anticlockwise.unwrap_or(false),
     );
+    Ok(())
   }
 
   #[napi]
@@ -256,37 +370,14 @@ impl Path {
     y: f64,
     width: f64,
     height: f64,
-    radii: Either3<f64, Vec<f64>, Undefined>,
-  ) {
-    let radii_array: [f32; 4] = match radii {
-      Either3::A(radii) => [radii as f32; 4],
-      Either3::B(radii_vec) => match radii_vec.len() {
-        0 => [0f32; 4],
-        1 => [radii_vec[0] as f32; 4],
-        2 => [
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-        ],
-        3 => [
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-          radii_vec[1] as f32,
-          radii_vec[2] as f32,
-        ],
-        _ => [
-          radii_vec[0] as f32,
-          radii_vec[1] as f32,
-          radii_vec[2] as f32,
-          radii_vec[3] as f32,
-        ],
-      },
-      Either3::C(_) => [0f32; 4],
-    };
-    self
-      .inner
-      .round_rect(x as f32, y as f32, width as f32, height as f32, radii_array);
+    radii: RoundRectRadii,
+  ) -> Result<()> {
+    if let Some(radii) = normalize_round_rect_radii(radii)? {
+      self
+        .inner
+        .round_rect(x as f32, y as f32, width as f32, height as f32, radii);
+    }
+    Ok(())
   }
 
   #[napi]
@@ -296,8 +387,9 @@ impl Path {
   }
 
   #[napi(js_name = "toSVGString")]
-  pub fn to_svg_string(&self, env: Env) -> Result<JsString> {
-    let sk_string = self.inner.to_svg_string();
+  pub fn to_svg_string(&self, env: Env, options: Option<SvgStringOptions>) -> Result<JsString> {
+    let relative = options.and_then(|o| o.relative).unwrap_or(false);
+    let sk_string = self.inner.to_svg_string(relative);
     unsafe { env.create_string_from_c_char(sk_string.ptr, sk_string.length) }
   }
 
@@ -356,15 +448,7 @@ impl Path {
 
   #[napi]
   pub fn transform(&mut self, matrix: Matrix) -> &Self {
-    let trans = SkMatrix::new(
-      matrix.a as f32,
-      matrix.c as f32,
-      matrix.e as f32,
-      matrix.b as f32,
-      matrix.d as f32,
-      matrix.f as f32,
-    );
-    self.inner.transform_self(&trans);
+    self.inner.transform_self(&matrix.to_sk_matrix());
     self
   }
 
@@ -395,4 +479,17 @@ impl Path {
       fill_type.unwrap_or(FillType::Winding).into(),
     )
   }
+
+  #[napi]
+  pub fn is_point_in_stroke(&self, x: f64, y: f64, options: Option<StrokeOptions>) -> bool {
+    let options = options.unwrap_or_default();
+    self.inner.stroke_hit_test(
+      x as f32,
+      y as f32,
+      options.cap.unwrap_or_default().into(),
+      options.join.unwrap_or_default().into(),
+      options.width.unwrap_or(1.0) as f32,
+      options.miter_limit.unwrap_or(4.0) as f32,
+    )
+  }
 }
@@ -3,11 +3,12 @@ use std::result::Result as StdResult;
 use cssparser::{Color as CSSColor, Parser, ParserInput, RGBA};
 use napi::bindgen_prelude::*;
 
+use crate::color::parse_color;
 use crate::ctx::TransformObject;
 use crate::error::SkError;
 use crate::gradient::Gradient;
 use crate::image::{Image, ImageData};
-use crate::sk::{AlphaType, Bitmap, ColorType, ImagePattern, TileMode, Transform};
+use crate::sk::{AlphaType, Bitmap, ColorType, ImagePattern, StorageFormat, TileMode, Transform};
 use crate::{CanvasElement, SVGCanvas};
 
 #[derive(Debug, Clone)]
@@ -27,7 +28,7 @@ impl Pattern {
   pub fn from_color(color_str: &str) -> StdResult<Self, SkError> {
     let mut parser_input = ParserInput::new(color_str);
     let mut parser = Parser::new(&mut parser_input);
-    let color = CSSColor::parse(&mut parser)
+    let color = parse_color(&mut parser)
       .map_err(|e| SkError::Generic(format!("Parse color [{color_str}] error: {e:?}")))?;
     match color {
       CSSColor::CurrentColor => Err(SkError::Generic(
@@ -61,6 +62,12 @@ impl CanvasPattern {
         .map(|b| b.0.bitmap)
         .ok_or_else(|| Error::new(Status::InvalidArg, "Image is not completed.".to_owned()))?,
       Either4::B(image_data) => {
+        if image_data.storage_format != StorageFormat::Uint8 {
+          return Err(Error::new(
+            Status::InvalidArg,
+            "Pattern from float16 ImageData is not supported".to_owned(),
+          ));
+        }
         let image_data_size = image_data.width * image_data.height * 4;
         let bitmap = Bitmap::from_image_data(
           image_data.data,
@@ -114,10 +121,11 @@ impl CanvasPattern {
     })
   }
 
+  /// Set the local matrix of the pattern shader, omitting `transform` resets it to identity
   #[napi]
-  pub fn set_transform(&mut self, transform: TransformObject) {
+  pub fn set_transform(&mut self, transform: Option<TransformObject>) {
     if let Pattern::Image(image) = &mut self.inner {
-      image.transform = transform.into();
+      image.transform = transform.map(Transform::from).unwrap_or_default();
     }
   }
 }
@@ -0,0 +1,110 @@
+use napi::{bindgen_prelude::*, JsBuffer, Property, PropertyAttributes};
+
+use crate::ctx::{CanvasRenderingContext2D, Context};
+use crate::sk;
+
+// keeps the context of the current page alive while the document draws into it
+const PAGE_HIDDEN_NAME: &str = "_page";
+// keeps the document alive while its page contexts are reachable, the page canvas belongs to the document
+const DOCUMENT_HIDDEN_NAME: &str = "_document";
+
+#[napi]
+pub struct PdfDocument {
+  document: sk::PdfDocument,
+  page: Option<ClassInstance<CanvasRenderingContext2D>>,
+  page_count: u32,
+  data: Option<sk::SkiaDataRef>,
+}
+
+#[napi]
+impl PdfDocument {
+  #[napi(constructor)]
+  pub fn new() -> Result<Self> {
+    let document = sk::PdfDocument::new()
+      .ok_or_else(|| Error::from_reason("Create skia pdf document failed".to_owned()))?;
+    Ok(Self {
+      document,
+      page: None,
+      page_count: 0,
+      data: None,
+    })
+  }
+
+  /// End the current page if any, and return a context drawing into a new page of `width` x `height` points
+  #[napi]
+  pub fn begin_page(
+    &mut self,
+    env: Env,
+    mut this: This,
+    width: u32,
+    height: u32,
+  ) -> Result<Object> {
+    if self.data.is_some() {
+      return Err(Error::new(
+        Status::GenericFailure,
+        "Can not begin a page after the document is finished".to_owned(),
+      ));
+    }
+    self.end_current_page()?;
+    let canvas = self
+      .document
+      .begin_page(width as f32, height as f32)
+      .ok_or_else(|| Error::from_reason("Begin pdf page failed".to_owned()))?;
+    let ctx = CanvasRenderingContext2D {
+      context: Context::new_pdf_page(width, height, canvas)?,
+    }
+    .into_js_instance(env)?;
+    let mut ctx_object = ctx.as_object(env);
+    ctx_object.define_properties(&[Property::new(DOCUMENT_HIDDEN_NAME)?
+      .with_value(&this)
+      .with_property_attributes(PropertyAttributes::Default)])?;
+    this.set_named_property(PAGE_HIDDEN_NAME, ctx.as_object(env))?;
+    self.page = Some(ctx);
+    self.page_count += 1;
+    Ok(ctx_object)
+  }
+
+  #[napi]
+  pub fn end_page(&mut self, mut this: This) -> Result<()> {
+    self.end_current_page()?;
+    this.set_named_property(PAGE_HIDDEN_NAME, ())?;
+    Ok(())
+  }
+
+  #[napi(getter)]
+  pub fn get_page_count(&self) -> u32 {
+    self.page_count
+  }
+
+  /// Finish the document and return the PDF bytes, no page can be added afterwards
+  #[napi(js_name = "toBuffer")]
+  pub fn finish(&mut self, env: Env, mut this: This, mime: Option<String>) -> Result<JsBuffer> {
+    if let Some(mime) = mime.filter(|mime| mime != "application/pdf") {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("{mime} is not supported by the pdf document, use application/pdf"),
+      ));
+    }
+    if self.data.is_none() {
+      self.end_current_page()?;
+      this.set_named_property(PAGE_HIDDEN_NAME, ())?;
+      self.data = Some(self.document.close());
+    }
+    let data = self.data.as_ref().unwrap();
+    env
+      .create_buffer_with_data(data.slice().to_vec())
+      .map(|b| b.into_raw())
+  }
+}
+
+impl PdfDocument {
+  fn end_current_page(&mut self) -> Result<()> {
+    if let Some(mut page) = self.page.take() {
+      self.document.end_page();
+      // the page canvas is gone with the page, draw into a detached raster surface from now on
+      let context = &mut page.context;
+      context.reset_surface(context.alpha, context.color_space, context.storage_format)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,103 @@
+use std::mem;
+use std::str::FromStr;
+
+use napi::{Env, Error, JsBuffer};
+use napi_derive::napi;
+
+use crate::error::SkError;
+use crate::sk::{PngEncodeOptions, PngFilterFlag, PngStream, SkiaDataRef, Surface};
+
+// roughly how many bytes of pixels each `next` call compresses
+const PNG_STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct PngConfig {
+  /// zlib compression level, 0-9 scale, default is 6
+  pub compression_level: Option<u32>,
+  /// `none` | `sub` | `up` | `avg` | `paeth` | `all`, default is `all`
+  pub filters: Option<String>,
+}
+
+impl TryFrom<&PngConfig> for PngEncodeOptions {
+  type Error = SkError;
+
+  fn try_from(config: &PngConfig) -> Result<Self, SkError> {
+    let default_options = PngEncodeOptions::default();
+    Ok(PngEncodeOptions {
+      compression_level: config
+        .compression_level
+        .map(|level| level.min(9) as u8)
+        .unwrap_or(default_options.compression_level),
+      filters: config
+        .filters
+        .as_deref()
+        .map(PngFilterFlag::from_str)
+        .transpose()?
+        .unwrap_or(default_options.filters),
+    })
+  }
+}
+
+/// Encodes a snapshot of the canvas a few rows at a time, so the stream can stop pulling when its consumer is full
+#[napi]
+pub struct PngStreamEncoder {
+  stream: PngStream,
+  rows_per_chunk: i32,
+  finished: bool,
+}
+
+impl PngStreamEncoder {
+  pub(crate) fn new(surface: &Surface, options: PngEncodeOptions) -> napi::Result<Self> {
+    let stream = surface
+      .reference()
+      .png_stream(options)
+      .ok_or_else(|| Error::from_reason("Encode png stream failed".to_owned()))?;
+    let row_bytes = (surface.width() as usize * 4).max(1);
+    Ok(Self {
+      stream,
+      rows_per_chunk: (PNG_STREAM_CHUNK_BYTES / row_bytes).clamp(1, i32::MAX as usize) as i32,
+      finished: false,
+    })
+  }
+}
+
+#[napi]
+impl PngStreamEncoder {
+  /// The next encoded chunk, `null` once the whole image has been returned
+  #[napi]
+  pub fn next(&mut self, env: Env) -> napi::Result<Option<JsBuffer>> {
+    if self.finished {
+      return Ok(None);
+    }
+    let data = self
+      .stream
+      .encode_rows(self.rows_per_chunk)
+      .ok_or_else(|| Error::from_reason("Encode png stream failed".to_owned()))?;
+    self.finished = self.stream.rows_left() == 0;
+    unsafe {
+      env.create_buffer_with_borrowed_data(data.0.ptr, data.0.size, data, |data: SkiaDataRef, _| {
+        mem::drop(data)
+      })
+    }
+    .map(|b| Some(b.into_raw()))
+  }
+}
+
+#[test]
+fn png_config_to_options() {
+  let options = PngEncodeOptions::try_from(&PngConfig::default()).unwrap();
+  assert_eq!(options, PngEncodeOptions::default());
+  let options = PngEncodeOptions::try_from(&PngConfig {
+    compression_level: Some(12),
+    filters: Some("paeth".to_owned()),
+  })
+  .unwrap();
+  assert_eq!(options.compression_level, 9);
+  assert_eq!(options.filters, PngFilterFlag::Paeth);
+  assert!(PngEncodeOptions::try_from(&PngConfig {
+    compression_level: None,
+    filters: Some("median".to_owned()),
+  })
+  .is_err());
+}
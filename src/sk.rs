@@ -9,7 +9,7 @@ use std::slice;
 use std::str::FromStr;
 
 use crate::error::SkError;
-use crate::font::{FontStretch, FontStyle};
+use crate::font::{FontKerning, FontStretch, FontStyle, FontVariant, TextRendering};
 use crate::image::ImageData;
 
 pub mod ffi {
@@ -30,6 +30,18 @@ pub mod ffi {
     _unused: [u8; 0],
   }
 
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_pdf_document {
+    _unused: [u8; 0],
+  }
+
+  #[repr(C)]
+  #[derive(Copy, Clone, Debug)]
+  pub struct skiac_png_stream {
+    _unused: [u8; 0],
+  }
+
   #[repr(C)]
   #[derive(Copy, Clone, Debug)]
   pub struct skiac_svg_surface {
@@ -268,6 +280,15 @@ pub mod ffi {
 
     pub fn skiac_surface_create_rgba(width: i32, height: i32, cs: u8) -> *mut skiac_surface;
 
+    pub fn skiac_surface_create_opaque(width: i32, height: i32, cs: u8) -> *mut skiac_surface;
+
+    pub fn skiac_surface_create_rgba_f16(
+      width: i32,
+      height: i32,
+      opaque: bool,
+      cs: u8,
+    ) -> *mut skiac_surface;
+
     pub fn skiac_surface_destroy(surface: *mut skiac_surface);
 
     pub fn skiac_surface_copy_rgba(
@@ -297,9 +318,32 @@ pub mod ffi {
       w: i32,
       h: i32,
       color_space: u8,
+      f32: bool,
+      premultiplied: bool,
     ) -> bool;
 
-    pub fn skiac_surface_png_data(surface: *mut skiac_surface, data: *mut skiac_sk_data);
+    pub fn skiac_surface_png_data(
+      surface: *mut skiac_surface,
+      data: *mut skiac_sk_data,
+      compression_level: i32,
+      filter_flags: i32,
+    );
+
+    pub fn skiac_surface_png_stream_create(
+      surface: *mut skiac_surface,
+      compression_level: i32,
+      filter_flags: i32,
+    ) -> *mut skiac_png_stream;
+
+    pub fn skiac_png_stream_encode_rows(
+      c_png_stream: *mut skiac_png_stream,
+      rows: i32,
+      data: *mut skiac_sk_data,
+    ) -> bool;
+
+    pub fn skiac_png_stream_rows_left(c_png_stream: *mut skiac_png_stream) -> i32;
+
+    pub fn skiac_png_stream_destroy(c_png_stream: *mut skiac_png_stream);
 
     pub fn skiac_surface_encode_data(
       surface: *mut skiac_surface,
@@ -407,7 +451,12 @@ pub mod ffi {
       weight: i32,
       width: i32,
       slant: i32,
+      variant: i32,
+      kerning: i32,
+      text_rendering: i32,
       font_family: *const c_char,
+      letter_spacing: f32,
+      word_spacing: f32,
       baseline: i32,
       align: i32,
       direction: i32,
@@ -436,22 +485,22 @@ pub mod ffi {
       row_bytes: usize,
       x: i32,
       y: i32,
+      cs: u8,
+      f32: bool,
     );
 
     pub fn skiac_canvas_write_pixels_dirty(
       canvas: *mut skiac_canvas,
-      width: i32,
-      height: i32,
       pixels: *const u8,
       row_bytes: usize,
-      length: usize,
-      x: f32,
-      y: f32,
-      dirty_x: f32,
-      dirty_y: f32,
-      dirty_width: f32,
-      dirty_height: f32,
+      x: i32,
+      y: i32,
+      dirty_x: i32,
+      dirty_y: i32,
+      dirty_width: i32,
+      dirty_height: i32,
       color_space: u8,
+      f32: bool,
     );
 
     pub fn skiac_paint_create() -> *mut skiac_paint;
@@ -509,7 +558,7 @@ pub mod ffi {
 
     pub fn skiac_path_create() -> *mut skiac_path;
 
-    pub fn skiac_path_from_svg(svg_path: *mut std::os::raw::c_char) -> *mut skiac_path;
+    pub fn skiac_path_from_svg(svg_path: *const std::os::raw::c_char) -> *mut skiac_path;
 
     pub fn skiac_path_clone(path: *mut skiac_path) -> *mut skiac_path;
 
@@ -524,7 +573,11 @@ pub mod ffi {
     pub fn skiac_path_op(c_path_one: *mut skiac_path, c_path_two: *mut skiac_path, op: i32)
       -> bool;
 
-    pub fn skiac_path_to_svg_string(c_path: *mut skiac_path, skia_string: *mut SkiaString);
+    pub fn skiac_path_to_svg_string(
+      c_path: *mut skiac_path,
+      relative: bool,
+      skia_string: *mut SkiaString,
+    );
 
     pub fn skiac_path_simplify(c_path: *mut skiac_path) -> bool;
 
@@ -612,8 +665,15 @@ pub mod ffi {
 
     pub fn skiac_path_hit_test(path: *mut skiac_path, x: f32, y: f32, kind: i32) -> bool;
 
-    pub fn skiac_path_stroke_hit_test(path: *mut skiac_path, x: f32, y: f32, stroke_w: f32)
-      -> bool;
+    pub fn skiac_path_stroke_hit_test(
+      path: *mut skiac_path,
+      x: f32,
+      y: f32,
+      cap: i32,
+      join: u8,
+      stroke_w: f32,
+      miter_limit: f32,
+    ) -> bool;
 
     pub fn skiac_path_round_rect(
       path: *mut skiac_path,
@@ -659,7 +719,7 @@ pub mod ffi {
     pub fn skiac_shader_make_conic_gradient(
       cx: f32,
       cy: f32,
-      radius: f32,
+      start_angle: f32,
       colors: *const super::Color,
       positions: *const f32,
       count: i32,
@@ -768,6 +828,12 @@ pub mod ffi {
       chained_filter: *mut skiac_image_filter,
     ) -> *mut skiac_image_filter;
 
+    pub fn skiac_image_filter_color_matrix(
+      matrix: *const f32,
+      clamp: bool,
+      chained_filter: *mut skiac_image_filter,
+    ) -> *mut skiac_image_filter;
+
     pub fn skiac_image_filter_from_argb(
       table_a: *const u8,
       table_r: *const u8,
@@ -776,13 +842,39 @@ pub mod ffi {
       c_image_filter: *mut skiac_image_filter,
     ) -> *mut skiac_image_filter;
 
+    pub fn skiac_image_filter_make_compose(
+      outer: *mut skiac_image_filter,
+      inner: *mut skiac_image_filter,
+    ) -> *mut skiac_image_filter;
+
     pub fn skiac_image_filter_ref(image_filter: *mut skiac_image_filter);
 
     pub fn skiac_image_filter_destroy(image_filter: *mut skiac_image_filter);
 
     pub fn skiac_sk_data_destroy(c_data: *mut skiac_data);
 
-    pub fn skiac_bitmap_make_from_buffer(ptr: *mut u8, size: usize, info: *mut skiac_bitmap_info);
+    pub fn skiac_bitmap_make_from_buffer(
+      ptr: *mut u8,
+      size: usize,
+      apply_orientation: bool,
+      info: *mut skiac_bitmap_info,
+    );
+
+    pub fn skiac_codec_get_orientation(ptr: *const u8, size: usize) -> i32;
+
+    pub fn skiac_bitmap_make_from_buffer_frame(
+      ptr: *const u8,
+      size: usize,
+      frame_index: i32,
+      info: *mut skiac_bitmap_info,
+    );
+
+    pub fn skiac_codec_get_frames(
+      ptr: *const u8,
+      size: usize,
+      durations: *mut i32,
+      capacity: i32,
+    ) -> i32;
 
     pub fn skiac_bitmap_make_from_svg(
       data: *const u8,
@@ -803,6 +895,8 @@ pub mod ffi {
       alpha_type: i32,
     ) -> *mut skiac_bitmap;
 
+    pub fn skiac_bitmap_copy(c_bitmap: *mut skiac_bitmap, bitmap_info: *mut skiac_bitmap_info);
+
     pub fn skiac_bitmap_get_width(c_bitmap: *mut skiac_bitmap) -> usize;
 
     pub fn skiac_bitmap_get_height(c_bitmap: *mut skiac_bitmap) -> usize;
@@ -811,8 +905,7 @@ pub mod ffi {
       c_bitmap: *mut skiac_bitmap,
       repeat_x: i32,
       repeat_y: i32,
-      b: f32,
-      c: f32,
+      filter_quality: i32,
       ts: skiac_transform,
     ) -> *mut skiac_shader;
 
@@ -843,6 +936,15 @@ pub mod ffi {
       maybe_name_alias: *const c_char,
     ) -> usize;
 
+    pub fn skiac_font_collection_register_styled(
+      c_font_collection: *mut skiac_font_collection,
+      font: *const u8,
+      length: usize,
+      family: *const c_char,
+      weight: i32,
+      slant: i32,
+    ) -> usize;
+
     pub fn skiac_font_collection_register_from_path(
       c_font_collection: *mut skiac_font_collection,
       font_path: *const c_char,
@@ -867,13 +969,28 @@ pub mod ffi {
 
     pub fn skiac_sk_w_stream_destroy(c_w_memory_stream: *mut skiac_w_memory_stream);
 
+    pub fn skiac_pdf_document_create() -> *mut skiac_pdf_document;
+
+    pub fn skiac_pdf_document_begin_page(
+      c_document: *mut skiac_pdf_document,
+      width: f32,
+      height: f32,
+    ) -> *mut skiac_canvas;
+
+    pub fn skiac_pdf_document_end_page(c_document: *mut skiac_pdf_document);
+
+    pub fn skiac_pdf_document_close(c_document: *mut skiac_pdf_document, data: *mut skiac_sk_data);
+
+    pub fn skiac_pdf_document_destroy(c_document: *mut skiac_pdf_document);
+
     // SkSVG
     pub fn skiac_svg_text_to_path(
       data: *const u8,
       length: usize,
       font_collection: *mut skiac_font_collection,
+      flag: u32,
       output_data: *mut skiac_sk_data,
-    );
+    ) -> i32;
   }
 }
 
@@ -942,6 +1059,15 @@ impl Default for ColorSpace {
   }
 }
 
+impl ColorSpace {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Srgb => "srgb",
+      Self::DisplayP3 => "display-p3",
+    }
+  }
+}
+
 impl FromStr for ColorSpace {
   type Err = SkError;
 
@@ -954,6 +1080,49 @@ impl FromStr for ColorSpace {
   }
 }
 
+/// Pixel storage of `ImageData`, also picks the surface format for the `colorType` context attribute
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum StorageFormat {
+  Uint8,
+  /// half float surface, `ImageData.data` is exposed as a `Float32Array`
+  Float16,
+}
+
+impl Default for StorageFormat {
+  fn default() -> Self {
+    Self::Uint8
+  }
+}
+
+impl StorageFormat {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Uint8 => "uint8",
+      Self::Float16 => "float16",
+    }
+  }
+
+  /// Size of one RGBA pixel in `ImageData.data`
+  pub fn bytes_per_pixel(&self) -> usize {
+    match self {
+      Self::Uint8 => 4,
+      Self::Float16 => 4 * std::mem::size_of::<f32>(),
+    }
+  }
+}
+
+impl FromStr for StorageFormat {
+  type Err = SkError;
+
+  fn from_str(value: &str) -> Result<Self, SkError> {
+    match value {
+      "uint8" | "unorm8" => Ok(Self::Uint8),
+      "float16" => Ok(Self::Float16),
+      _ => Err(SkError::StringToStorageFormatError(value.to_owned())),
+    }
+  }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum PaintStyle {
   Fill = 0,
@@ -1175,7 +1344,7 @@ impl BlendMode {
       BlendMode::Modulate => "modulate",
       BlendMode::Multiply => "multiply",
       BlendMode::Overlay => "overlay",
-      BlendMode::Plus => "plus",
+      BlendMode::Plus => "lighter",
       BlendMode::Saturation => "saturation",
       BlendMode::Screen => "screen",
       BlendMode::SoftLight => "soft-light",
@@ -1213,7 +1382,8 @@ impl FromStr for BlendMode {
       "modulate" => Ok(BlendMode::Modulate),
       "multiply" => Ok(BlendMode::Multiply),
       "overlay" => Ok(BlendMode::Overlay),
-      "plus" => Ok(BlendMode::Plus),
+      // `plus` is kept for compatibility, `lighter` is the canvas name of it
+      "lighter" | "plus" => Ok(BlendMode::Plus),
       "saturation" => Ok(BlendMode::Saturation),
       "screen" => Ok(BlendMode::Screen),
       "soft-light" => Ok(BlendMode::SoftLight),
@@ -1299,6 +1469,89 @@ impl FromStr for FilterQuality {
   }
 }
 
+/// `ctx.antialias`, `none` draws the fills, strokes and images with hard edges.
+/// Skia has a single antialiasing mode, `gray` and `subpixel` behave as `default`.
+#[repr(i32)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Antialias {
+  #[default]
+  Default = 0,
+  None = 1,
+  Gray = 2,
+  Subpixel = 3,
+}
+
+impl Antialias {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Default => "default",
+      Self::None => "none",
+      Self::Gray => "gray",
+      Self::Subpixel => "subpixel",
+    }
+  }
+}
+
+impl FromStr for Antialias {
+  type Err = SkError;
+
+  fn from_str(s: &str) -> Result<Antialias, SkError> {
+    match s {
+      "default" => Ok(Self::Default),
+      "none" => Ok(Self::None),
+      "gray" => Ok(Self::Gray),
+      "subpixel" => Ok(Self::Subpixel),
+      _ => Err(SkError::StringToAntialiasError(s.to_owned())),
+    }
+  }
+}
+
+/// Row filters tried by the PNG encoder, mirrors `SkPngEncoder::FilterFlag`
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PngFilterFlag {
+  None = 0x08,
+  Sub = 0x10,
+  Up = 0x20,
+  Avg = 0x40,
+  Paeth = 0x80,
+  #[default]
+  All = 0xF8,
+}
+
+impl FromStr for PngFilterFlag {
+  type Err = SkError;
+
+  fn from_str(s: &str) -> Result<PngFilterFlag, SkError> {
+    match s {
+      "none" => Ok(Self::None),
+      "sub" => Ok(Self::Sub),
+      "up" => Ok(Self::Up),
+      "avg" => Ok(Self::Avg),
+      "paeth" => Ok(Self::Paeth),
+      "all" => Ok(Self::All),
+      _ => Err(SkError::StringToPngFilterError(s.to_owned())),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PngEncodeOptions {
+  /// zlib compression level, 0-9
+  pub compression_level: u8,
+  pub filters: PngFilterFlag,
+}
+
+impl Default for PngEncodeOptions {
+  // same as the defaults of `SkPngEncoder::Options`
+  fn default() -> Self {
+    Self {
+      compression_level: 6,
+      filters: PngFilterFlag::All,
+    }
+  }
+}
+
 /// Describes how to interpret the alpha component of a pixel.
 ///
 /// A pixel may be opaque, or alpha, describing multiple levels of transparency.
@@ -1455,7 +1708,8 @@ impl FromStr for TextDirection {
 
   fn from_str(s: &str) -> Result<TextDirection, SkError> {
     match s {
-      "inherit" | "ltr" => Ok(Self::Ltr),
+      "inherit" => Ok(Self::Inherit),
+      "ltr" => Ok(Self::Ltr),
       "rtl" => Ok(Self::Rtl),
       _ => Err(SkError::StringToTextDirectionError(s.to_owned())),
     }
@@ -1465,11 +1719,13 @@ impl FromStr for TextDirection {
 impl TextDirection {
   pub fn as_str(&self) -> &str {
     match self {
-      &Self::Inherit | &Self::Ltr => "ltr",
-      &Self::Rtl => "rtl",
+      Self::Inherit => "inherit",
+      Self::Ltr => "ltr",
+      Self::Rtl => "rtl",
     }
   }
 
+  /// There is no document to inherit from, `inherit` is `ltr`
   pub fn as_sk_direction(&self) -> i32 {
     match self {
       &Self::Inherit | &Self::Ltr => 1,
@@ -1522,6 +1778,19 @@ impl TryFrom<u32> for SvgExportFlag {
   }
 }
 
+impl SvgExportFlag {
+  /// Validate a bitwise OR of the flags, `0` keeps text as `<text>` elements
+  pub fn check_bits(bits: u32) -> Result<u32, SkError> {
+    let all = Self::ConvertTextToPaths as u32
+      | Self::NoPrettyXML as u32
+      | Self::RelativePathEncoding as u32;
+    if bits & !all != 0 {
+      return Err(SkError::InvalidSvgExportFlag(bits));
+    }
+    Ok(bits)
+  }
+}
+
 pub struct Surface {
   ptr: *mut ffi::skiac_surface,
   pub(crate) canvas: Canvas,
@@ -1552,11 +1821,39 @@ impl Surface {
     }
   }
 
+  /// Surface without alpha channel, filled with opaque black
+  pub fn new_opaque(width: u32, height: u32, color_space: ColorSpace) -> Option<Surface> {
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_create_opaque(
+        width as i32,
+        height as i32,
+        color_space as u8,
+      ))
+    }
+  }
+
+  /// Half float surface, values outside of `[0, 1]` survive drawing and `putImageData`
+  pub fn new_rgba_f16(
+    width: u32,
+    height: u32,
+    opaque: bool,
+    color_space: ColorSpace,
+  ) -> Option<Surface> {
+    unsafe {
+      Self::from_ptr(ffi::skiac_surface_create_rgba_f16(
+        width as i32,
+        height as i32,
+        opaque,
+        color_space as u8,
+      ))
+    }
+  }
+
   pub fn new_svg(
     width: u32,
     height: u32,
     alpha_type: AlphaType,
-    flag: SvgExportFlag,
+    flags: u32,
     color_space: ColorSpace,
   ) -> Option<(Surface, SkWMemoryStream)> {
     let mut svg_surface = ffi::skiac_svg_surface {
@@ -1570,7 +1867,7 @@ impl Surface {
         width as i32,
         height as i32,
         alpha_type as i32,
-        flag as u32,
+        flags,
         color_space as u8,
       );
     };
@@ -1586,6 +1883,13 @@ impl Surface {
     ))
   }
 
+  /// Surface drawing into the `canvas` of a PDF page, the 1x1 raster behind it only owns the surface handle
+  pub fn new_pdf_page(canvas: Canvas) -> Option<Surface> {
+    let mut surface = Self::new_rgba_premultiplied(1, 1, ColorSpace::default())?;
+    surface.canvas = canvas;
+    Some(surface)
+  }
+
   unsafe fn from_ptr(ptr: *mut ffi::skiac_surface) -> Option<Surface> {
     if ptr.is_null() {
       None
@@ -1654,10 +1958,11 @@ impl Surface {
     }
   }
 
+  /// Read unpremultiplied RGBA, the pixels of the rect outside of the surface are transparent black
   pub fn read_pixels(
     &self,
-    x: u32,
-    y: u32,
+    x: i32,
+    y: i32,
     width: u32,
     height: u32,
     color_space: ColorSpace,
@@ -1667,11 +1972,70 @@ impl Surface {
       ffi::skiac_surface_read_pixels_rect(
         self.ptr,
         result.as_mut_ptr(),
-        x as i32,
-        y as i32,
+        x,
+        y,
         width as i32,
         height as i32,
         color_space as u8,
+        false,
+        false,
+      )
+    };
+    if status {
+      Some(result)
+    } else {
+      None
+    }
+  }
+
+  /// Read unpremultiplied RGBA as f32 without clamping to `[0, 1]`
+  pub fn read_pixels_f32(
+    &self,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    color_space: ColorSpace,
+  ) -> Option<Vec<f32>> {
+    let len = (width as usize)
+      .checked_mul(height as usize)?
+      .checked_mul(4)?;
+    let mut result = vec![0f32; len];
+    let status = unsafe {
+      ffi::skiac_surface_read_pixels_rect(
+        self.ptr,
+        result.as_mut_ptr().cast(),
+        x,
+        y,
+        width as i32,
+        height as i32,
+        color_space as u8,
+        true,
+        false,
+      )
+    };
+    if status {
+      Some(result)
+    } else {
+      None
+    }
+  }
+
+  /// Read the whole surface as RGBA, the rows are tightly packed with a stride of `width * 4` bytes
+  pub fn read_rgba(&self, color_space: ColorSpace, premultiplied: bool) -> Option<Vec<u8>> {
+    let (width, height) = (self.width(), self.height());
+    let mut result = vec![0; width as usize * height as usize * 4];
+    let status = unsafe {
+      ffi::skiac_surface_read_pixels_rect(
+        self.ptr,
+        result.as_mut_ptr(),
+        0,
+        0,
+        width as i32,
+        height as i32,
+        color_space as u8,
+        false,
+        premultiplied,
       )
     };
     if status {
@@ -1780,14 +2144,19 @@ impl Drop for Surface {
 pub struct SurfaceRef(*mut ffi::skiac_surface);
 
 impl SurfaceRef {
-  pub fn png_data(&self) -> Option<SkiaDataRef> {
+  pub fn png_data(&self, options: PngEncodeOptions) -> Option<SkiaDataRef> {
     unsafe {
       let mut data = ffi::skiac_sk_data {
         ptr: ptr::null_mut(),
         size: 0,
         data: ptr::null_mut(),
       };
-      ffi::skiac_surface_png_data(self.0, &mut data);
+      ffi::skiac_surface_png_data(
+        self.0,
+        &mut data,
+        options.compression_level as i32,
+        options.filters as i32,
+      );
 
       if data.ptr.is_null() {
         None
@@ -1797,6 +2166,22 @@ impl SurfaceRef {
     }
   }
 
+  /// Start a png encoder over a snapshot of the pixels, the rows are encoded on demand by `PngStream::encode_rows`
+  pub fn png_stream(&self, options: PngEncodeOptions) -> Option<PngStream> {
+    let ptr = unsafe {
+      ffi::skiac_surface_png_stream_create(
+        self.0,
+        options.compression_level as i32,
+        options.filters as i32,
+      )
+    };
+    if ptr.is_null() {
+      None
+    } else {
+      Some(PngStream(ptr))
+    }
+  }
+
   pub fn data(&self) -> Option<(*const u8, usize)> {
     let mut data = ffi::skiac_surface_data {
       ptr: ptr::null_mut(),
@@ -2048,9 +2433,14 @@ impl Canvas {
     weight: u32,
     stretch: i32,
     slant: FontStyle,
+    variant: FontVariant,
+    kerning: FontKerning,
+    text_rendering: TextRendering,
     font_collection: &FontCollection,
     font_size: f32,
     font_family: &str,
+    letter_spacing: f32,
+    word_spacing: f32,
     baseline: TextBaseline,
     align: TextAlign,
     direction: TextDirection,
@@ -2072,7 +2462,12 @@ impl Canvas {
         weight as i32,
         stretch,
         slant as i32,
+        variant as i32,
+        kerning as i32,
+        text_rendering as i32,
         c_font_family.as_ptr(),
+        letter_spacing,
+        word_spacing,
         baseline as i32,
         align as i32,
         direction.as_sk_direction(),
@@ -2092,7 +2487,11 @@ impl Canvas {
     weight: u32,
     stretch: i32,
     slant: FontStyle,
+    variant: FontVariant,
+    kerning: FontKerning,
     font_family: &str,
+    letter_spacing: f32,
+    word_spacing: f32,
     baseline: TextBaseline,
     align: TextAlign,
     direction: TextDirection,
@@ -2116,7 +2515,13 @@ impl Canvas {
         weight as i32,
         stretch,
         slant as i32,
+        variant as i32,
+        kerning as i32,
+        // the rendering hints don't change the layout
+        TextRendering::Auto as i32,
         c_font_family.as_ptr(),
+        letter_spacing,
+        word_spacing,
         baseline as i32,
         align as i32,
         direction.as_sk_direction(),
@@ -2210,46 +2615,47 @@ impl Canvas {
     }
   }
 
-  pub fn write_pixels(&mut self, image: &ImageData, x: u32, y: u32) {
+  pub fn write_pixels(&mut self, image: &ImageData, x: i32, y: i32) {
     unsafe {
       ffi::skiac_canvas_write_pixels(
         self.0,
         image.width as i32,
         image.height as i32,
         image.data,
-        image.width * 4,
-        x as i32,
-        y as i32,
+        image.width * image.storage_format.bytes_per_pixel(),
+        x,
+        y,
+        image.color_space as u8,
+        image.storage_format == StorageFormat::Float16,
       );
     }
   }
 
+  /// Copy the `dirty` rect (x, y, width, height) of the image to `(x + dirty.x, y + dirty.y)`, ignoring the transform and clip.
+  /// The rect must be inside of the image.
   pub fn write_pixels_dirty(
     &mut self,
     image: &ImageData,
-    x: f32,
-    y: f32,
-    dirty_x: f32,
-    dirty_y: f32,
-    dirty_width: f32,
-    dirty_height: f32,
-    color_space: ColorSpace,
+    x: i32,
+    y: i32,
+    dirty: (u32, u32, u32, u32),
   ) {
+    let (dirty_x, dirty_y, dirty_width, dirty_height) = dirty;
+    debug_assert!(dirty_x + dirty_width <= image.width as u32);
+    debug_assert!(dirty_y + dirty_height <= image.height as u32);
     unsafe {
       ffi::skiac_canvas_write_pixels_dirty(
         self.0,
-        image.width as i32,
-        image.height as i32,
         image.data,
-        image.width * 4,
-        image.width * image.height * 4,
+        image.width * image.storage_format.bytes_per_pixel(),
         x,
         y,
-        dirty_x,
-        dirty_y,
-        dirty_width,
-        dirty_height,
-        color_space as u8,
+        dirty_x as i32,
+        dirty_y as i32,
+        dirty_width as i32,
+        dirty_height as i32,
+        image.color_space as u8,
+        image.storage_format == StorageFormat::Float16,
       )
     }
   }
@@ -2415,14 +2821,35 @@ impl Path {
     unsafe { ffi::skiac_path_swap(self.0, other.0) }
   }
 
+  /// Build the path from the SVG path data, like the browsers the commands before the first error are kept
+  ///
+  /// Returns `None` if the path data contains a nul byte.
   pub fn from_svg_path(path: &str) -> Option<Path> {
-    let path_str = CString::new(path).ok()?;
-    let p = unsafe { ffi::skiac_path_from_svg(path_str.into_raw()) };
-    if p.is_null() {
-      None
-    } else {
-      Some(Path(p))
+    let parse = |path: &str| {
+      let path_str = CString::new(path).ok()?;
+      let p = unsafe { ffi::skiac_path_from_svg(path_str.as_ptr()) };
+      if p.is_null() {
+        None
+      } else {
+        Some(Path(p))
+      }
+    };
+    if path.contains('\0') {
+      return None;
     }
+    parse(path).or_else(|| {
+      // the prefixes ending before a command are valid up to the error, search the longest one
+      let commands = path
+        .char_indices()
+        .filter(|(_, c)| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E'))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+      let valid = commands.partition_point(|index| parse(&path[..*index]).is_some());
+      match valid {
+        0 => Some(Path::new()),
+        valid => parse(&path[..commands[valid - 1]]),
+      }
+    })
   }
 
   pub fn add_path(&mut self, sub_path: &Path, transform: &Matrix) {
@@ -2632,17 +3059,27 @@ impl Path {
     unsafe { ffi::skiac_path_hit_test(self.0, x, y, kind as i32) }
   }
 
-  pub fn stroke_hit_test(&self, x: f32, y: f32, stroke_w: f32) -> bool {
-    unsafe { ffi::skiac_path_stroke_hit_test(self.0, x, y, stroke_w) }
+  pub fn stroke_hit_test(
+    &self,
+    x: f32,
+    y: f32,
+    cap: StrokeCap,
+    join: StrokeJoin,
+    stroke_w: f32,
+    miter_limit: f32,
+  ) -> bool {
+    unsafe {
+      ffi::skiac_path_stroke_hit_test(self.0, x, y, cap as i32, join as u8, stroke_w, miter_limit)
+    }
   }
 
-  pub fn to_svg_string(&self) -> SkiaString {
+  pub fn to_svg_string(&self, relative: bool) -> SkiaString {
     let mut string = SkiaString {
       ptr: ptr::null_mut(),
       length: 0,
       sk_string: ptr::null_mut(),
     };
-    unsafe { ffi::skiac_path_to_svg_string(self.0, &mut string) };
+    unsafe { ffi::skiac_path_to_svg_string(self.0, relative, &mut string) };
     string
   }
 
@@ -2694,9 +3131,10 @@ impl Path {
     mut y: f32,
     mut width: f32,
     mut height: f32,
-    mut radii: [f32; 4],
+    mut radii: [(f32, f32); 4],
   ) {
     // https://github.com/chromium/chromium/blob/111.0.5520.1/third_party/blink/renderer/modules/canvas/canvas2d/canvas_path.cc#L601
+    // radii are in `[upper_left, upper_right, lower_right, lower_left]` order
     let mut clockwise = true;
     if width < 0f32 {
       clockwise = false;
@@ -2709,9 +3147,11 @@ impl Path {
       clockwise = !clockwise;
       y += height;
       height = -height;
-      radii.swap(0, 2);
-      radii.swap(1, 3);
+      radii.swap(0, 3);
+      radii.swap(1, 2);
     }
+    scale_round_rect_radii(width, height, &mut radii);
+    let radii = radii.map(|(rx, ry)| [rx, ry]).concat();
     unsafe { ffi::skiac_path_round_rect(self.0, x, y, width, height, radii.as_ptr(), clockwise) };
     unsafe { ffi::skiac_path_move_to(self.0, x, y) };
   }
@@ -2766,10 +3206,42 @@ impl Drop for Path {
 pub struct Gradient {
   pub colors: Vec<Color>,
   pub positions: Vec<f32>,
+  /// Sorted positions of the color midpoints, each one applies to the stops around it
+  pub hints: Vec<f32>,
+  pub interpolation: GradientInterpolation,
   pub tile_mode: TileMode,
   pub transform: Transform,
 }
 
+/// Color space the colors between the gradient stops are interpolated in
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum GradientInterpolation {
+  #[default]
+  Srgb,
+  Oklab,
+}
+
+impl GradientInterpolation {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Srgb => "srgb",
+      Self::Oklab => "oklab",
+    }
+  }
+}
+
+impl FromStr for GradientInterpolation {
+  type Err = SkError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "srgb" => Ok(Self::Srgb),
+      "oklab" => Ok(Self::Oklab),
+      _ => Err(SkError::StringToGradientInterpolationError(s.to_owned())),
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct LinearGradient {
   pub start_point: (f32, f32),
@@ -2789,7 +3261,8 @@ pub struct RadialGradient {
 #[derive(Debug, Clone)]
 pub struct ConicGradient {
   pub center: (f32, f32),
-  pub radius: f32,
+  /// in radians, clockwise from the x-axis
+  pub start_angle: f32,
   pub base: Gradient,
 }
 
@@ -2853,7 +3326,7 @@ impl Shader {
       Self::from_ptr(ffi::skiac_shader_make_conic_gradient(
         grad.center.0,
         grad.center.1,
-        grad.radius,
+        grad.start_angle,
         grad.base.colors.as_ptr(),
         grad.base.positions.as_ptr(),
         grad.base.colors.len() as i32,
@@ -2882,13 +3355,17 @@ impl Shader {
     bitmap: *mut ffi::skiac_bitmap,
     repeat_x: TileMode,
     repeat_y: TileMode,
-    b: f32,
-    c: f32,
+    filter_quality: FilterQuality,
     ts: Transform,
   ) -> Option<Shader> {
     unsafe {
-      let shader_ptr =
-        ffi::skiac_bitmap_get_shader(bitmap, repeat_x as i32, repeat_y as i32, b, c, ts.into());
+      let shader_ptr = ffi::skiac_bitmap_get_shader(
+        bitmap,
+        repeat_x as i32,
+        repeat_y as i32,
+        filter_quality as i32,
+        ts.into(),
+      );
       Shader::from_ptr(shader_ptr)
     }
   }
@@ -3330,6 +3807,27 @@ impl ImageFilter {
     }
   }
 
+  /// Apply a row-major 4x5 RGBA color matrix to the unpremultiplied colors, the last column is added in `0-1` scale.
+  /// Without `clamp` the results can exceed `[0, 1]` on the float surfaces
+  pub fn make_color_matrix(
+    matrix: &[f32; 20],
+    clamp: bool,
+    chained_filter: Option<&ImageFilter>,
+  ) -> Option<Self> {
+    let raw_ptr = unsafe {
+      ffi::skiac_image_filter_color_matrix(
+        matrix.as_ptr(),
+        clamp,
+        chained_filter.map(|c| c.0).unwrap_or(ptr::null_mut()),
+      )
+    };
+    if raw_ptr.is_null() {
+      None
+    } else {
+      Some(ImageFilter(raw_ptr))
+    }
+  }
+
   pub fn from_argb(
     a: Option<&[u8; 256]>,
     r: Option<&[u8; 256]>,
@@ -3351,6 +3849,17 @@ impl ImageFilter {
     }
     Some(ImageFilter(raw_ptr))
   }
+
+  /// Apply `outer` to the output of `inner`
+  pub fn compose(outer: &ImageFilter, inner: Option<&ImageFilter>) -> Option<Self> {
+    let raw_ptr = unsafe {
+      ffi::skiac_image_filter_make_compose(outer.0, inner.map_or(ptr::null_mut(), |f| f.0))
+    };
+    if raw_ptr.is_null() {
+      return None;
+    }
+    Some(ImageFilter(raw_ptr))
+  }
 }
 
 impl Drop for ImageFilter {
@@ -3366,18 +3875,66 @@ impl Drop for ImageFilter {
 pub(crate) struct Bitmap(pub(crate) ffi::skiac_bitmap_info);
 
 impl Bitmap {
-  pub fn from_buffer(ptr: *mut u8, size: usize) -> Self {
+  /// Decode the first frame, rotated and flipped upright by the EXIF orientation if `apply_orientation`
+  pub fn from_buffer(ptr: *mut u8, size: usize, apply_orientation: bool) -> Option<Self> {
     let mut bitmap_info = ffi::skiac_bitmap_info {
       bitmap: ptr::null_mut(),
       width: 0,
       height: 0,
     };
     unsafe {
-      ffi::skiac_bitmap_make_from_buffer(ptr, size, &mut bitmap_info);
-      Bitmap(bitmap_info)
+      ffi::skiac_bitmap_make_from_buffer(ptr, size, apply_orientation, &mut bitmap_info);
+
+      if bitmap_info.bitmap.is_null() {
+        return None;
+      }
+      Some(Bitmap(bitmap_info))
     }
   }
 
+  /// Decode the frame at `frame_index` of an animated image, prior frames it depends on are composed in
+  pub fn from_buffer_frame(data: &[u8], frame_index: u32) -> Option<Self> {
+    let mut bitmap_info = ffi::skiac_bitmap_info {
+      bitmap: ptr::null_mut(),
+      width: 0,
+      height: 0,
+    };
+    unsafe {
+      ffi::skiac_bitmap_make_from_buffer_frame(
+        data.as_ptr(),
+        data.len(),
+        frame_index as i32,
+        &mut bitmap_info,
+      );
+
+      if bitmap_info.bitmap.is_null() {
+        return None;
+      }
+      Some(Bitmap(bitmap_info))
+    }
+  }
+
+  /// The EXIF orientation of the encoded image, from `1` (upright) to `8`
+  pub fn orientation(data: &[u8]) -> u32 {
+    unsafe { ffi::skiac_codec_get_orientation(data.as_ptr(), data.len()) as u32 }
+  }
+
+  /// Durations in milliseconds of every frame of the encoded image, still images have a single `0` frame
+  pub fn frame_durations(data: &[u8]) -> Vec<u32> {
+    let count =
+      unsafe { ffi::skiac_codec_get_frames(data.as_ptr(), data.len(), ptr::null_mut(), 0) };
+    let mut durations = vec![0i32; count.max(0) as usize];
+    unsafe {
+      ffi::skiac_codec_get_frames(
+        data.as_ptr(),
+        data.len(),
+        durations.as_mut_ptr(),
+        durations.len() as i32,
+      )
+    };
+    durations.into_iter().map(|d| d.max(0) as u32).collect()
+  }
+
   pub fn from_svg_data(data: *const u8, size: usize, color_space: ColorSpace) -> Option<Self> {
     let mut bitmap_info = ffi::skiac_bitmap_info {
       bitmap: ptr::null_mut(),
@@ -3445,10 +4002,25 @@ impl Bitmap {
     };
     Bitmap(ffi::skiac_bitmap_info {
       bitmap,
-      width: row_bytes as i32,
-      height: (size / row_bytes / 4) as i32,
+      width: width as i32,
+      height: height as i32,
     })
   }
+
+  /// A copy owning its pixels, the bitmaps made `from_image_data` only borrow the `ImageData` memory
+  pub fn deep_copy(&self) -> Option<OwnedBitmap> {
+    let mut bitmap_info = ffi::skiac_bitmap_info {
+      bitmap: ptr::null_mut(),
+      width: 0,
+      height: 0,
+    };
+    unsafe { ffi::skiac_bitmap_copy(self.0.bitmap, &mut bitmap_info) };
+    if bitmap_info.bitmap.is_null() {
+      None
+    } else {
+      Some(OwnedBitmap(Bitmap(bitmap_info)))
+    }
+  }
 }
 
 impl Drop for Bitmap {
@@ -3459,6 +4031,21 @@ impl Drop for Bitmap {
   }
 }
 
+/// A bitmap made by `Bitmap::deep_copy`, nothing else refers to its pixels so it can move to another thread
+pub struct OwnedBitmap(Bitmap);
+
+impl OwnedBitmap {
+  pub(crate) fn bitmap(&self) -> &Bitmap {
+    &self.0
+  }
+
+  pub(crate) fn into_inner(self) -> Bitmap {
+    self.0
+  }
+}
+
+unsafe impl Send for OwnedBitmap {}
+
 #[derive(Debug, Clone)]
 pub struct ImagePattern {
   pub(crate) bitmap: *mut ffi::skiac_bitmap,
@@ -3468,13 +4055,13 @@ pub struct ImagePattern {
 }
 
 impl ImagePattern {
-  pub(crate) fn get_shader(&self) -> Option<Shader> {
+  /// The pattern is sampled with `filter_quality`, which follows the `imageSmoothing` state of the context
+  pub(crate) fn get_shader(&self, filter_quality: FilterQuality) -> Option<Shader> {
     Shader::from_bitmap(
       self.bitmap,
       self.repeat_x,
       self.repeat_y,
-      1.0 / 3.0,
-      1.0 / 3.0,
+      filter_quality,
       self.transform,
     )
   }
@@ -3573,6 +4160,30 @@ impl FontCollection {
     }
   }
 
+  /// Register the font under `family`, matched with `weight` and `style` instead of the ones in the font file when given
+  pub fn register_styled(
+    &self,
+    font: &[u8],
+    family: &str,
+    weight: Option<u32>,
+    style: Option<FontStyle>,
+  ) -> bool {
+    if let Ok(family) = CString::new(family) {
+      unsafe {
+        ffi::skiac_font_collection_register_styled(
+          self.0,
+          font.as_ptr(),
+          font.len(),
+          family.as_ptr(),
+          weight.map(|w| w as i32).unwrap_or(-1),
+          style.map(|s| s as i32).unwrap_or(-1),
+        ) > 0
+      }
+    } else {
+      false
+    }
+  }
+
   pub fn register_from_path<S: AsRef<str>>(
     &self,
     font_path: &str,
@@ -3641,11 +4252,104 @@ impl Drop for SkWMemoryStream {
   }
 }
 
+pub struct PdfDocument(*mut ffi::skiac_pdf_document);
+
+impl PdfDocument {
+  pub fn new() -> Option<PdfDocument> {
+    let ptr = unsafe { ffi::skiac_pdf_document_create() };
+    if ptr.is_null() {
+      None
+    } else {
+      Some(PdfDocument(ptr))
+    }
+  }
+
+  /// The canvas of the new page, it must not be used after `end_page`
+  pub fn begin_page(&mut self, width: f32, height: f32) -> Option<Canvas> {
+    let canvas = unsafe { ffi::skiac_pdf_document_begin_page(self.0, width, height) };
+    if canvas.is_null() {
+      None
+    } else {
+      Some(Canvas(canvas))
+    }
+  }
+
+  pub fn end_page(&mut self) {
+    unsafe { ffi::skiac_pdf_document_end_page(self.0) }
+  }
+
+  /// Finish the document, the pages can't be added anymore
+  pub fn close(&mut self) -> SkiaDataRef {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    unsafe { ffi::skiac_pdf_document_close(self.0, &mut data) };
+    SkiaDataRef(data)
+  }
+}
+
+impl Drop for PdfDocument {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_pdf_document_destroy(self.0) }
+  }
+}
+
+pub struct PngStream(*mut ffi::skiac_png_stream);
+
+impl PngStream {
+  /// Encode up to `rows` more rows and return the bytes written since the last call,
+  /// the png header comes with the first call and the trailer with the last row
+  pub fn encode_rows(&mut self, rows: i32) -> Option<SkiaDataRef> {
+    let mut data = ffi::skiac_sk_data {
+      ptr: ptr::null_mut(),
+      size: 0,
+      data: ptr::null_mut(),
+    };
+    if unsafe { ffi::skiac_png_stream_encode_rows(self.0, rows, &mut data) } {
+      Some(SkiaDataRef(data))
+    } else {
+      None
+    }
+  }
+
+  pub fn rows_left(&self) -> i32 {
+    unsafe { ffi::skiac_png_stream_rows_left(self.0) }
+  }
+}
+
+impl Drop for PngStream {
+  fn drop(&mut self) {
+    unsafe { ffi::skiac_png_stream_destroy(self.0) }
+  }
+}
+
 #[inline(always)]
 pub(crate) fn radians_to_degrees(rad: f32) -> f32 {
   rad / PI * 180.0
 }
 
+/// Scale the corner radii down uniformly if the radii of any side add up to more than its length
+// https://html.spec.whatwg.org/multipage/canvas.html#dom-context-2d-roundrect step 9
+pub(crate) fn scale_round_rect_radii(width: f32, height: f32, radii: &mut [(f32, f32); 4]) {
+  let [upper_left, upper_right, lower_right, lower_left] = *radii;
+  let top = upper_left.0 + upper_right.0;
+  let right = upper_right.1 + lower_right.1;
+  let bottom = lower_right.0 + lower_left.0;
+  let left = upper_left.1 + lower_left.1;
+  let scale = [width / top, height / right, width / bottom, height / left]
+    .into_iter()
+    .filter(|s| !s.is_nan())
+    .fold(1f32, f32::min);
+  if scale < 1f32 {
+    for (rx, ry) in radii.iter_mut() {
+      *rx *= scale;
+      *ry *= scale;
+    }
+  }
+}
+
 #[inline(always)]
 pub(crate) fn degrees_to_radians(degrees: f32) -> f32 {
   degrees / 180.0 * PI
@@ -3656,22 +4360,99 @@ fn almost_equal(floata: f32, floatb: f32) -> bool {
   (floata - floatb).abs() < 0.00001
 }
 
-pub fn sk_svg_text_to_path(svg: &[u8], fc: &FontCollection) -> Option<SkiaDataRef> {
+pub fn sk_svg_text_to_path(
+  svg: &[u8],
+  fc: &FontCollection,
+  convert_all: bool,
+) -> Result<SkiaDataRef, SkError> {
   let mut output_data = ffi::skiac_sk_data {
     ptr: ptr::null_mut(),
     data: ptr::null_mut(),
     size: 0,
   };
-  unsafe {
-    ffi::skiac_svg_text_to_path(svg.as_ptr(), svg.len(), fc.0, &mut output_data);
+  let flag = if convert_all {
+    SvgExportFlag::ConvertTextToPaths as u32
+  } else {
+    0
   };
-  if output_data.ptr.is_null() {
-    return None;
+  let status =
+    unsafe { ffi::skiac_svg_text_to_path(svg.as_ptr(), svg.len(), fc.0, flag, &mut output_data) };
+  match status {
+    0 if !output_data.ptr.is_null() => Ok(SkiaDataRef(output_data)),
+    1 => Err(SkError::SvgConversionError("input is empty".to_owned())),
+    2 => Err(SkError::SvgConversionError(
+      "input is not a valid svg document".to_owned(),
+    )),
+    _ => Err(SkError::SvgConversionError("render svg failed".to_owned())),
   }
-  Some(SkiaDataRef(output_data))
 }
 
 unsafe extern "C" fn skiac_on_get_style(width: i32, weight: i32, slant: i32, raw_cb: *mut c_void) {
   let cb = Box::leak(Box::from_raw(raw_cb as *mut Box<dyn FnMut(i32, i32, i32)>));
   cb(width, weight, slant);
 }
+
+#[test]
+fn scale_round_rect_radii_clamp() {
+  let mut radii = [(10.0, 10.0); 4];
+  scale_round_rect_radii(100.0, 100.0, &mut radii);
+  assert_eq!(radii, [(10.0, 10.0); 4]);
+
+  // the top side needs 150 + 50 for a width of 100
+  let mut radii = [(150.0, 20.0), (50.0, 20.0), (0.0, 0.0), (0.0, 0.0)];
+  scale_round_rect_radii(100.0, 100.0, &mut radii);
+  assert_eq!(radii, [(75.0, 10.0), (25.0, 10.0), (0.0, 0.0), (0.0, 0.0)]);
+}
+
+#[test]
+fn canvas_composite_operations_to_blend_mode() {
+  // the 26 composite operations of canvas and the `SkBlendMode` values they map to
+  let operations = [
+    ("source-over", BlendMode::SourceOver, 3),
+    ("source-in", BlendMode::SourceIn, 5),
+    ("source-out", BlendMode::SourceOut, 7),
+    ("source-atop", BlendMode::SourceATop, 9),
+    ("destination-over", BlendMode::DestinationOver, 4),
+    ("destination-in", BlendMode::DestinationIn, 6),
+    ("destination-out", BlendMode::DestinationOut, 8),
+    ("destination-atop", BlendMode::DestinationATop, 10),
+    ("lighter", BlendMode::Plus, 12),
+    ("copy", BlendMode::Source, 1),
+    ("xor", BlendMode::Xor, 11),
+    ("multiply", BlendMode::Multiply, 24),
+    ("screen", BlendMode::Screen, 14),
+    ("overlay", BlendMode::Overlay, 15),
+    ("darken", BlendMode::Darken, 16),
+    ("lighten", BlendMode::Lighten, 17),
+    ("color-dodge", BlendMode::ColorDodge, 18),
+    ("color-burn", BlendMode::ColorBurn, 19),
+    ("hard-light", BlendMode::HardLight, 20),
+    ("soft-light", BlendMode::SoftLight, 21),
+    ("difference", BlendMode::Difference, 22),
+    ("exclusion", BlendMode::Exclusion, 23),
+    ("hue", BlendMode::Hue, 25),
+    ("saturation", BlendMode::Saturation, 26),
+    ("color", BlendMode::Color, 27),
+    ("luminosity", BlendMode::Luminosity, 28),
+  ];
+  for (operation, blend_mode, sk_blend_mode) in operations {
+    assert_eq!(operation.parse::<BlendMode>().unwrap(), blend_mode);
+    assert_eq!(blend_mode as u8, sk_blend_mode);
+    assert_eq!(blend_mode.as_str(), operation);
+  }
+  assert!(matches!(
+    "source-overr".parse::<BlendMode>(),
+    Err(SkError::StringToBlendError(s)) if s == "source-overr"
+  ));
+  assert!("".parse::<BlendMode>().is_err());
+}
+
+#[test]
+fn svg_export_flag_check_bits() {
+  assert_eq!(SvgExportFlag::check_bits(0).unwrap(), 0);
+  assert_eq!(SvgExportFlag::check_bits(0x01 | 0x04).unwrap(), 0x05);
+  assert!(matches!(
+    SvgExportFlag::check_bits(0x08),
+    Err(SkError::InvalidSvgExportFlag(0x08))
+  ));
+}
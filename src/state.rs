@@ -3,9 +3,9 @@ use cssparser::RGBA;
 use crate::sk::{ImageFilter, Matrix};
 
 use super::{
-  font::Font,
+  font::{Font, FontKerning, TextRendering},
   pattern::Pattern,
-  sk::{FilterQuality, Paint, TextAlign, TextBaseline, TextDirection},
+  sk::{Antialias, FilterQuality, Paint, TextAlign, TextBaseline, TextDirection},
 };
 
 #[derive(Debug, Clone)]
@@ -18,19 +18,28 @@ pub struct Context2dRenderingState {
   pub shadow_blur: f32,
   pub shadow_color: RGBA,
   pub shadow_color_string: String,
-  pub global_alpha: f32,
+  pub global_alpha: f64,
   pub line_dash_offset: f32,
   pub image_smoothing_enabled: bool,
   pub image_smoothing_quality: FilterQuality,
+  pub antialias: Antialias,
   pub paint: Paint,
   pub font: String,
   pub font_style: Font,
+  pub font_kerning: FontKerning,
+  pub text_rendering: TextRendering,
   pub text_align: TextAlign,
   pub text_baseline: TextBaseline,
   pub text_direction: TextDirection,
+  pub letter_spacing: f32,
+  pub letter_spacing_string: String,
+  pub word_spacing: f32,
+  pub word_spacing_string: String,
   pub transform: Matrix,
   pub filter: Option<ImageFilter>,
   pub filters_string: String,
+  /// The filter references `currentColor`, it's resolved against the fill or stroke style when drawing
+  pub filter_current_color: bool,
 }
 
 impl Default for Context2dRenderingState {
@@ -50,15 +59,34 @@ impl Default for Context2dRenderingState {
       line_dash_offset: 0.0,
       image_smoothing_enabled: true,
       image_smoothing_quality: FilterQuality::default(),
+      antialias: Antialias::default(),
       paint: Paint::default(),
       font: "10px sans-serif".to_owned(),
       font_style: Font::default(),
+      font_kerning: FontKerning::default(),
+      text_rendering: TextRendering::default(),
       text_align: TextAlign::default(),
       text_baseline: TextBaseline::default(),
       text_direction: TextDirection::default(),
+      letter_spacing: 0.0,
+      letter_spacing_string: "0px".to_owned(),
+      word_spacing: 0.0,
+      word_spacing_string: "0px".to_owned(),
       transform: Matrix::identity(),
       filter: None,
       filters_string: "none".to_owned(),
+      filter_current_color: false,
+    }
+  }
+}
+
+impl Context2dRenderingState {
+  /// Sampling of the images and patterns, nearest neighbor if `imageSmoothingEnabled` is `false`
+  pub fn image_filter_quality(&self) -> FilterQuality {
+    if self.image_smoothing_enabled {
+      self.image_smoothing_quality
+    } else {
+      FilterQuality::None
     }
   }
 }
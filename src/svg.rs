@@ -2,25 +2,78 @@ use std::mem;
 
 use napi::{bindgen_prelude::*, JsBuffer};
 
-use crate::{error::SkError, global_fonts::get_font, sk::sk_svg_text_to_path};
+use crate::{
+  error::SkError,
+  global_fonts::get_font,
+  sk::{sk_svg_text_to_path, SkiaDataRef},
+};
+
+#[napi(object)]
+#[derive(Default, Clone)]
+pub struct SvgTextToPathOptions {
+  /// Convert all of the text to paths, default is `true`.
+  /// If `false`, the text is kept as `<text>` elements.
+  pub convert_all: Option<bool>,
+  /// Embedding fonts into the output is not supported by the Skia SVG canvas yet
+  pub embed_fonts: Option<bool>,
+}
 
 #[napi(js_name = "convertSVGTextToPath")]
 pub fn convert_svg_text_to_path(
   env: Env,
   input: Either3<Buffer, String, Unknown>,
+  options: Option<SvgTextToPathOptions>,
 ) -> Result<JsBuffer> {
+  svg_text_to_path(input.as_bytes()?, &options.unwrap_or_default())
+    .and_then(|v| into_js_buffer(env, v))
+}
+
+pub struct ConvertSvgTextToPathTask {
+  svg: Vec<u8>,
+  options: SvgTextToPathOptions,
+}
+
+impl Task for ConvertSvgTextToPathTask {
+  type Output = SkiaDataRef;
+  type JsValue = JsBuffer;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    svg_text_to_path(&self.svg, &self.options)
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    into_js_buffer(env, output)
+  }
+}
+
+#[napi(js_name = "convertSVGTextToPathAsync")]
+pub fn convert_svg_text_to_path_async(
+  input: Either3<Buffer, String, Unknown>,
+  options: Option<SvgTextToPathOptions>,
+) -> Result<AsyncTask<ConvertSvgTextToPathTask>> {
+  Ok(AsyncTask::new(ConvertSvgTextToPathTask {
+    svg: input.as_bytes()?.to_vec(),
+    options: options.unwrap_or_default(),
+  }))
+}
+
+fn svg_text_to_path(svg: &[u8], options: &SvgTextToPathOptions) -> Result<SkiaDataRef> {
+  if options.embed_fonts == Some(true) {
+    return Err(SkError::SvgConversionError("embedding fonts is not supported".to_owned()).into());
+  }
   let font = get_font().map_err(SkError::from)?;
-  sk_svg_text_to_path(input.as_bytes()?, &font)
-    .ok_or_else(|| {
-      Error::new(
-        Status::InvalidArg,
-        "Convert svg text to path failed".to_owned(),
-      )
-    })
-    .and_then(|v| unsafe {
-      env.create_buffer_with_borrowed_data(v.0.ptr, v.0.size, v, |d, _| mem::drop(d))
-    })
-    .map(|b| b.into_raw())
+  Ok(sk_svg_text_to_path(
+    svg,
+    &font,
+    options.convert_all.unwrap_or(true),
+  )?)
+}
+
+fn into_js_buffer(env: Env, data: SkiaDataRef) -> Result<JsBuffer> {
+  unsafe {
+    env.create_buffer_with_borrowed_data(data.0.ptr, data.0.size, data, |d, _| mem::drop(d))
+  }
+  .map(|b| b.into_raw())
 }
 
 trait AsBytes {